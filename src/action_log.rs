@@ -0,0 +1,188 @@
+//! Bundles the recent action history into a file that can be attached to bug reports.
+//!
+//! Everything goes through [`redact`] before it is written, so todo text and file paths only end up in the bundle when
+//! `--include-content` was passed.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use color_eyre::eyre::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+  actions::{Action, EngineAction},
+  resources::ResourceUsage,
+};
+
+/// Stands in for todo text that was left out of the bundle.
+pub const REDACTED: &str = "<redacted>";
+/// Stands in for file paths that were left out of the bundle.
+pub const REDACTED_PATH: &str = "<path>";
+
+/// Details about the build and platform the log was taken on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostics {
+  pub version: String,
+  pub commit: String,
+  pub os: String,
+  /// What the app was using when the log was taken.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub resources: Option<ResourceUsage>,
+}
+
+impl Diagnostics {
+  pub fn collect() -> Self {
+    Self {
+      version: env!("CARGO_PKG_VERSION").to_string(),
+      commit: crate::utils::GIT_COMMIT_HASH.to_string(),
+      os: std::env::consts::OS.to_string(),
+      resources: None,
+    }
+  }
+
+  pub fn with_resources(mut self, resources: ResourceUsage) -> Self {
+    self.resources = Some(resources);
+    self
+  }
+}
+
+/// Everything that goes into an exported action log. Missing sections are listed in `gaps` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActionLog {
+  pub exported_at: String,
+  pub actions: Option<Vec<Action>>,
+  pub errors: Option<Vec<String>>,
+  pub diagnostics: Option<Diagnostics>,
+  pub gaps: Vec<String>,
+}
+
+impl ActionLog {
+  /// Builds a log from the recorded `actions`, oldest first. The errors are the [`EngineAction::Error`]s among them.
+  pub fn new(actions: Option<Vec<Action>>, diagnostics: Option<Diagnostics>, exported_at: DateTime<Local>) -> Self {
+    let mut gaps = Vec::new();
+    let errors = actions.as_ref().map(|actions| {
+      actions
+        .iter()
+        .filter_map(|action| match action {
+          Action::Engine(EngineAction::Error(message)) => Some(message.clone()),
+          _ => None,
+        })
+        .collect()
+    });
+    if actions.is_none() {
+      gaps.push("actions: no action history was available".to_string());
+      gaps.push("errors: no action history was available to collect them from".to_string());
+    }
+    if diagnostics.is_none() {
+      gaps.push("diagnostics: not collected".to_string());
+    }
+    Self { exported_at: exported_at.to_rfc3339(), actions, errors, diagnostics, gaps }
+  }
+
+  /// Serializes the log, redacting it unless `include_content` is set.
+  pub fn to_json(&self, include_content: bool) -> Result<String> {
+    let value = serde_json::to_value(self)?;
+    let value = if include_content { value } else { redact(value) };
+    Ok(serde_json::to_string_pretty(&value)?)
+  }
+
+  /// Writes the log to a timestamped file in `dir`, returning its path.
+  pub fn write(&self, dir: &Path, include_content: bool) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = DateTime::parse_from_rfc3339(&self.exported_at)?.format("%Y%m%d-%H%M%S");
+    let path = dir.join(format!("action-log-{stamp}.json"));
+    std::fs::write(&path, self.to_json(include_content)?)?;
+    Ok(path)
+  }
+}
+
+/// Strips todo text and file paths from every string in a serialized log.
+///
+/// `Home.CompleteInput(...)` and `Home.TodosLoaded(...)` payloads are replaced with [`REDACTED`], and any word that
+/// looks like a path (contains a `/` or `\`) with [`REDACTED_PATH`]. Object keys are left alone since they come from
+/// the log's own structure.
+pub fn redact(value: Value) -> Value {
+  match value {
+    Value::String(s) => Value::String(redact_str(&s)),
+    Value::Array(values) => Value::Array(values.into_iter().map(redact).collect()),
+    Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, redact(value))).collect()),
+    value => value,
+  }
+}
+
+fn redact_str(s: &str) -> String {
+  for action in ["Home.CompleteInput", "Home.TodosLoaded"] {
+    if s.starts_with(&format!("{action}(")) {
+      return format!("{action}({REDACTED})");
+    }
+  }
+  s.split(' ').map(redact_word).collect::<Vec<_>>().join(" ")
+}
+
+/// Replaces `word` if it is a path, keeping any quotes or punctuation wrapped around it.
+fn redact_word(word: &str) -> String {
+  let is_wrapper = |c: char| "\"'()[]{},:;".contains(c);
+  let core = word.trim_start_matches(is_wrapper).trim_end_matches(is_wrapper);
+  if core.contains(['/', '\\']) {
+    let start = word.len() - word.trim_start_matches(is_wrapper).len();
+    format!("{}{REDACTED_PATH}{}", &word[..start], &word[start + core.len()..])
+  } else {
+    word.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+  use crate::actions::HomeAction;
+
+  #[test]
+  fn test_redact_strips_input_and_paths() {
+    let log = json!({
+      "actions": [
+        "Home.CompleteInput(\"call the bank about /home/me/secret.txt\")",
+        "Engine.Error(\"Failed to save /home/me/todos.json: permission denied\")",
+        "Engine.Resize(80, 24)",
+        "Home.TodosLoaded(\"[{\\\"id\\\":1,\\\"title\\\":\\\"call the bank\\\"}]\")",
+      ],
+      "errors": ["Failed to save C:\\Users\\me\\todos.json"],
+      "exported_at": "2024-01-10T12:00:00+00:00",
+    });
+
+    assert_eq!(
+      redact(log),
+      json!({
+        "actions": [
+          "Home.CompleteInput(<redacted>)",
+          "Engine.Error(\"Failed to save <path>: permission denied\")",
+          "Engine.Resize(80, 24)",
+          "Home.TodosLoaded(<redacted>)",
+        ],
+        "errors": ["Failed to save <path>"],
+        "exported_at": "2024-01-10T12:00:00+00:00",
+      })
+    );
+  }
+
+  #[test]
+  fn test_log_notes_gaps_and_collects_errors() -> Result<()> {
+    let now = Local::now();
+    let actions = vec![
+      HomeAction::CompleteInput("buy milk".to_string()).into(),
+      EngineAction::Error("Failed to draw".to_string()).into(),
+    ];
+
+    let log = ActionLog::new(Some(actions), None, now);
+    assert_eq!(log.errors, Some(vec!["Failed to draw".to_string()]));
+    assert_eq!(log.gaps, vec!["diagnostics: not collected".to_string()]);
+    assert!(!log.to_json(false)?.contains("buy milk"));
+    assert!(log.to_json(true)?.contains("buy milk"));
+
+    assert_eq!(ActionLog::new(None, Some(Diagnostics::collect()), now).gaps.len(), 2);
+    Ok(())
+  }
+}
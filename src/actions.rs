@@ -1,31 +1,60 @@
-use std::fmt::{self, Display};
+use std::{
+  fmt::{self, Display},
+  str::FromStr,
+};
 
 use serde::{
   de::{self, Deserializer, Visitor},
-  Deserialize, Serialize,
+  Deserialize, Serialize, Serializer,
 };
 
+use crate::app::Mode;
+
 pub use crate::actions::home_action::ListNavDirection;
 
-pub use self::{engine_actions::EngineAction, home_action::HomeAction};
+pub use self::{
+  engine_actions::EngineAction,
+  home_action::HomeAction,
+  main_menu_action::MainMenuAction,
+  parse::{ActionCall, ParseAction, ParseError},
+};
 
 pub mod engine_actions;
 pub mod home_action;
+pub mod main_menu_action;
+pub mod parse;
 
+/// Hooks each namespace's action enum into [`Action`]: converting into it, and parsing `<Namespace>.<Variant>` text
+/// through the enum's [`ParseAction`] implementation.
 macro_rules! extend_action {
-  ( $x:ty, $y:ident ) => {
-    impl From<$x> for Action {
-      fn from(value: $x) -> Self {
-        Action::$y(value)
+  ( $( $x:ty => $y:ident ),+ $(,)? ) => {
+    $(
+      impl From<$x> for Action {
+        fn from(value: $x) -> Self {
+          Action::$y(value)
+        }
+      }
+    )+
+
+    impl FromStr for Action {
+      type Err = ParseError;
+
+      fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let call = ActionCall::parse(s)?;
+        match call.namespace() {
+          $( stringify!($y) => <$x as ParseAction>::parse_call(&call).map(Action::$y), )+
+          _ => Err(call.unknown_namespace(&[$( stringify!($y) ),+])),
+        }
       }
     }
   };
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
   Engine(EngineAction),
   Home(HomeAction),
+  MainMenu(MainMenuAction),
 }
 
 impl Display for Action {
@@ -33,12 +62,65 @@ impl Display for Action {
     match self {
       Self::Engine(x) => write!(f, "Engine.{x}"),
       Self::Home(x) => write!(f, "Home.{x}"),
+      Self::MainMenu(x) => write!(f, "MainMenu.{x}"),
     }
   }
 }
 
-extend_action!(EngineAction, Engine);
-extend_action!(HomeAction, Home);
+impl Action {
+  /// The action with its step count multiplied by `count`, for navigation, counting or operating on todos typed after
+  /// a count prefix like vim's `3j` or `3dd`. Other actions are returned unchanged.
+  pub fn with_count(self, count: usize) -> Self {
+    match self {
+      Self::Home(HomeAction::NavigateList(dir, n)) => HomeAction::NavigateList(dir, n.saturating_mul(count)).into(),
+      Self::MainMenu(MainMenuAction::NavigateTabs(dir, n)) => {
+        MainMenuAction::NavigateTabs(dir, n.saturating_mul(count)).into()
+      },
+      Self::Home(HomeAction::ScheduleIncrement(n)) => HomeAction::ScheduleIncrement(n.saturating_mul(count)).into(),
+      Self::Home(HomeAction::ScheduleDecrement(n)) => HomeAction::ScheduleDecrement(n.saturating_mul(count)).into(),
+      Self::Home(HomeAction::DeleteTodos(n)) => HomeAction::DeleteTodos(n.saturating_mul(count)).into(),
+      Self::Home(HomeAction::ToggleTodos(n)) => HomeAction::ToggleTodos(n.saturating_mul(count)).into(),
+      action => action,
+    }
+  }
+
+  /// What the action does in plain words, or its config text when it has no description of its own.
+  pub fn description(&self) -> String {
+    let description = match self {
+      Self::Engine(x) => x.description(),
+      Self::Home(x) => x.description(),
+      Self::MainMenu(x) => x.description(),
+    };
+    description.map_or_else(|| self.to_string(), str::to_string)
+  }
+
+  /// Whether the action moves a selection around, and so should only reach the component that has focus.
+  pub fn is_navigation(&self) -> bool {
+    matches!(
+      self,
+      Self::Home(HomeAction::NavigateList(..) | HomeAction::ScrollPage(_))
+        | Self::MainMenu(MainMenuAction::NavigateTabs(..))
+    )
+  }
+
+  /// Whether the action goes to the components of every mode rather than just the current one, because it carries
+  /// data they all keep a copy of.
+  pub fn reaches_every_mode(&self) -> bool {
+    matches!(self, Self::Home(HomeAction::TodosLoaded(_)))
+  }
+}
+
+extend_action!(EngineAction => Engine, HomeAction => Home, MainMenuAction => MainMenu);
+
+/// Actions serialize to the same string format used in the config, so anything written out can be read back in.
+impl Serialize for Action {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    serializer.collect_str(self)
+  }
+}
 
 impl<'de> Deserialize<'de> for Action {
   fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -51,72 +133,387 @@ impl<'de> Deserialize<'de> for Action {
       type Value = Action;
 
       fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a valid string (in the format \"<ActionName>[<param_1> ...]\") representation of Action")
+        formatter.write_str(
+          "a valid string (in the format \"<ActionName>[<param_1> ...]\") representation of Action, or a list of them",
+        )
       }
 
       fn visit_str<E>(self, value: &str) -> Result<Action, E>
       where
         E: de::Error,
       {
-        match value {
-          data if data.starts_with("Engine.") => {
-            let substr: &str = data.split("Engine.").nth(1).unwrap_or_default();
-
-            match substr {
-              "Tick" => Ok(EngineAction::Tick.into()),
-              "Render" => Ok(EngineAction::Render.into()),
-              "Suspend" => Ok(EngineAction::Suspend.into()),
-              "Resume" => Ok(EngineAction::Resume.into()),
-              "Quit" => Ok(EngineAction::Quit.into()),
-              "Refresh" => Ok(EngineAction::Refresh.into()),
-              "ToggleShowHelp" => Ok(EngineAction::ToggleShowHelp.into()),
-              "ToggleShowModeSwitcher" => Ok(EngineAction::ToggleShowModeSwitcher.into()),
-              data if substr.starts_with("Error(") => {
-                let error_msg = data.trim_start_matches("Error(").trim_end_matches(')');
-                Ok(EngineAction::Error(error_msg.to_string()).into())
-              },
-              data if substr.starts_with("Resize(") => {
-                let parts: Vec<&str> = data.trim_start_matches("Resize(").trim_end_matches(')').split(',').collect();
-                if parts.len() == 2 {
-                  let width: u16 = parts[0].trim().parse().map_err(E::custom)?;
-                  let height: u16 = parts[1].trim().parse().map_err(E::custom)?;
-                  Ok(EngineAction::Resize(width, height).into())
-                } else {
-                  Err(E::custom(format!("Invalid Resize format: {}", value)))
-                }
-              },
-              _ => Err(E::custom(format!("Unknown EngineAction variant: {}", value))),
-            }
-          },
-          data if data.starts_with("Home.") => {
-            let substr: &str = data.split("Home.").nth(1).unwrap_or_default();
-
-            match substr {
-              "Help" => Ok(HomeAction::Help.into()),
-              "ScheduleIncrement" => Ok(HomeAction::ScheduleIncrement.into()),
-              "ScheduleDecrement" => Ok(HomeAction::ScheduleDecrement.into()),
-              "ToggleShowHelp" => Ok(HomeAction::ToggleShowHelp.into()),
-              "EnterInsert" => Ok(HomeAction::EnterInsert.into()),
-              "EnterNormal" => Ok(HomeAction::EnterNormal.into()),
-              data if data.starts_with("NavigateList") => {
-                let parts: Vec<&str> = data.split(&['(', ')']).collect();
-
-                match parts[1] {
-                  "Left" => Ok(HomeAction::NavigateList(ListNavDirection::Left).into()),
-                  "Right" => Ok(HomeAction::NavigateList(ListNavDirection::Right).into()),
-                  "Up" => Ok(HomeAction::NavigateList(ListNavDirection::Up).into()),
-                  "Down" => Ok(HomeAction::NavigateList(ListNavDirection::Down).into()),
-                  x => Err(E::custom(format!("Unexpected list navigation direction in config: {}", x))),
-                }
-              },
-              _ => Err(E::custom(format!("Unknown HomeAction variant: {}", value))),
-            }
-          },
-          _ => Err(E::custom(format!("Unknown Action variant: {}", value))),
+        value.parse().map_err(E::custom)
+      }
+
+      /// A list of actions is run as a batch.
+      fn visit_seq<A>(self, mut seq: A) -> Result<Action, A::Error>
+      where
+        A: de::SeqAccess<'de>,
+      {
+        let mut actions = Vec::new();
+        while let Some(action) = seq.next_element()? {
+          actions.push(action);
         }
+        EngineAction::batch(actions).map(Action::from).map_err(de::Error::custom)
+      }
+    }
+
+    deserializer.deserialize_any(ActionVisitor)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::config::Preset;
+
+  fn sample_actions() -> Vec<Action> {
+    vec![
+      EngineAction::Tick.into(),
+      EngineAction::Render.into(),
+      EngineAction::Resize(80, 24).into(),
+      EngineAction::Suspend.into(),
+      EngineAction::Resume.into(),
+      EngineAction::Quit.into(),
+      EngineAction::ConfirmQuit.into(),
+      EngineAction::ForceQuit.into(),
+      EngineAction::Refresh.into(),
+      EngineAction::ReloadTheme.into(),
+      EngineAction::ReloadConfig.into(),
+      EngineAction::ToggleShowHelp.into(),
+      EngineAction::ToggleShowModeSwitcher.into(),
+      EngineAction::ModeSwitcherNav(ListNavDirection::Down).into(),
+      EngineAction::ModeSwitcherNav(ListNavDirection::First).into(),
+      EngineAction::IdleStart.into(),
+      EngineAction::IdleEnd.into(),
+      EngineAction::ChangeMode(Mode::Home).into(),
+      EngineAction::ChangeMode(Mode::MainMenu).into(),
+      EngineAction::PreviousMode.into(),
+      EngineAction::ToggleShowActionHistory.into(),
+      EngineAction::CycleActionHistoryFilter.into(),
+      EngineAction::ExportActionLog.into(),
+      EngineAction::ToggleLayoutDebug.into(),
+      EngineAction::FocusNext.into(),
+      EngineAction::FocusPrevious.into(),
+      EngineAction::StorageReadOnly(true).into(),
+      EngineAction::SwitchPreset(Preset::Vim).into(),
+      EngineAction::PendingKeys(crate::config::parse_key_sequence("<g><ctrl-x>").unwrap()).into(),
+      EngineAction::PendingKeys(Vec::new()).into(),
+      EngineAction::PendingCount(12).into(),
+      EngineAction::StorageReadOnly(false).into(),
+      EngineAction::Notify("Wrote (1) log".to_string()).into(),
+      EngineAction::Error("Failed to draw: (nested (parens))".to_string()).into(),
+      EngineAction::Error("with \"quotes\", commas\nand newlines)".to_string()).into(),
+      EngineAction::Batch(vec![
+        HomeAction::CompleteInput("a, \"b\")".to_string()).into(),
+        EngineAction::Batch(vec![EngineAction::Resize(1, 2).into()]).into(),
+        MainMenuAction::NavigateTabs(ListNavDirection::Left, 1).into(),
+      ])
+      .into(),
+      EngineAction::Batch(Vec::new()).into(),
+      EngineAction::ToggleCommandPalette.into(),
+      EngineAction::ToggleKeybindingEditor.into(),
+      EngineAction::SetBinding(
+        Some(Mode::Home),
+        crate::config::parse_key_sequence("<,><ctrl-x>").unwrap(),
+        Some(Box::new(HomeAction::CompleteInput("a, \"b\")".to_string()).into())),
+      )
+      .into(),
+      EngineAction::SetBinding(None, crate::config::parse_key_sequence("<q>").unwrap(), None).into(),
+      EngineAction::SaveKeybindings.into(),
+      EngineAction::CycleTheme.into(),
+      EngineAction::SetTheme("high-contrast".to_string()).into(),
+      HomeAction::Help.into(),
+      HomeAction::ToggleShowHelp.into(),
+      HomeAction::ScheduleIncrement(5).into(),
+      HomeAction::ScheduleDecrement(1).into(),
+      HomeAction::Increment(42).into(),
+      HomeAction::Decrement(0).into(),
+      HomeAction::CompleteInput("buy milk (2L), eggs)".to_string()).into(),
+      HomeAction::CompleteInput(String::new()).into(),
+      HomeAction::EnterNormal.into(),
+      HomeAction::EnterInsert.into(),
+      HomeAction::EnterProcessing.into(),
+      HomeAction::ExitProcessing.into(),
+      HomeAction::Update.into(),
+      HomeAction::ToggleRelativeNumbers.into(),
+      HomeAction::TrimEntries.into(),
+      HomeAction::Save.into(),
+      HomeAction::DeleteTodo.into(),
+      HomeAction::RemoveTodo(7).into(),
+      HomeAction::DeleteTodos(3).into(),
+      HomeAction::ToggleTodos(1).into(),
+      HomeAction::Undo.into(),
+      HomeAction::Redo.into(),
+      HomeAction::MarkAllComplete.into(),
+      HomeAction::ClearCompleted.into(),
+      HomeAction::SetDue(3, chrono::NaiveDate::from_ymd_opt(2024, 1, 11)).into(),
+      HomeAction::SetDue(3, None).into(),
+      HomeAction::SortBy(crate::todos::TodoSort::Due).into(),
+      HomeAction::SortBy(crate::todos::TodoSort::Created).into(),
+      HomeAction::SortBy(crate::todos::TodoSort::Priority).into(),
+      HomeAction::CycleSelectedPriority.into(),
+      HomeAction::CyclePriority(4).into(),
+      HomeAction::FilterByTag(Some("work \"stuff\"".to_string())).into(),
+      HomeAction::FilterByTag(None).into(),
+      HomeAction::TodosLoaded(crate::todos::TodoStore::new(vec![
+        crate::todos::Todo::new(1, "say \"hi\", (twice)"),
+        crate::todos::Todo { tags: vec!["work".to_string()], ..crate::todos::Todo::new(2, "report") },
+      ]))
+      .into(),
+      HomeAction::TodosLoaded(crate::todos::TodoStore::default()).into(),
+      HomeAction::NavigateList(ListNavDirection::Left, 3).into(),
+      HomeAction::NavigateList(ListNavDirection::Right, 1).into(),
+      HomeAction::NavigateList(ListNavDirection::Up, 1).into(),
+      HomeAction::NavigateList(ListNavDirection::Down, 12).into(),
+      HomeAction::NavigateList(ListNavDirection::First, 1).into(),
+      HomeAction::NavigateList(ListNavDirection::PageDown, 2).into(),
+      MainMenuAction::NavigateTabs(ListNavDirection::Last, 1).into(),
+      HomeAction::ScrollPage(ListNavDirection::Up).into(),
+      HomeAction::ScrollPage(ListNavDirection::Down).into(),
+      MainMenuAction::NavigateTabs(ListNavDirection::Left, 1).into(),
+      MainMenuAction::NavigateTabs(ListNavDirection::Right, 2).into(),
+      MainMenuAction::ActivateTab.into(),
+    ]
+  }
+
+  /// How many variants the action enums have between them. `variant_name` won't compile until a new variant is
+  /// added to it, and then this needs bumping, so a new variant can't go without a sample.
+  const VARIANT_COUNT: usize = 70;
+
+  fn variant_name(action: &Action) -> &'static str {
+    match action {
+      Action::Engine(action) => match action {
+        EngineAction::Tick => "Tick",
+        EngineAction::Render => "Render",
+        EngineAction::Resize(..) => "Resize",
+        EngineAction::Suspend => "Suspend",
+        EngineAction::Resume => "Resume",
+        EngineAction::Quit => "Quit",
+        EngineAction::ConfirmQuit => "ConfirmQuit",
+        EngineAction::ForceQuit => "ForceQuit",
+        EngineAction::Refresh => "Refresh",
+        EngineAction::ReloadTheme => "ReloadTheme",
+        EngineAction::ReloadConfig => "ReloadConfig",
+        EngineAction::ToggleShowHelp => "ToggleShowHelp",
+        EngineAction::Error(_) => "Error",
+        EngineAction::ChangeMode(_) => "ChangeMode",
+        EngineAction::PreviousMode => "PreviousMode",
+        EngineAction::ToggleShowModeSwitcher => "ToggleShowModeSwitcher",
+        EngineAction::ModeSwitcherNav(_) => "ModeSwitcherNav",
+        EngineAction::IdleStart => "IdleStart",
+        EngineAction::IdleEnd => "IdleEnd",
+        EngineAction::ToggleShowActionHistory => "ToggleShowActionHistory",
+        EngineAction::CycleActionHistoryFilter => "CycleActionHistoryFilter",
+        EngineAction::ExportActionLog => "ExportActionLog",
+        EngineAction::ToggleLayoutDebug => "ToggleLayoutDebug",
+        EngineAction::FocusNext => "FocusNext",
+        EngineAction::FocusPrevious => "FocusPrevious",
+        EngineAction::StorageReadOnly(_) => "StorageReadOnly",
+        EngineAction::Notify(_) => "Notify",
+        EngineAction::SwitchPreset(_) => "SwitchPreset",
+        EngineAction::PendingKeys(_) => "PendingKeys",
+        EngineAction::PendingCount(_) => "PendingCount",
+        EngineAction::Batch(_) => "Batch",
+        EngineAction::ToggleCommandPalette => "ToggleCommandPalette",
+        EngineAction::ToggleKeybindingEditor => "ToggleKeybindingEditor",
+        EngineAction::SetBinding(..) => "SetBinding",
+        EngineAction::SaveKeybindings => "SaveKeybindings",
+        EngineAction::CycleTheme => "CycleTheme",
+        EngineAction::SetTheme(_) => "SetTheme",
+      },
+      Action::Home(action) => match action {
+        HomeAction::Help => "Help",
+        // Engine has a ToggleShowHelp of its own.
+        HomeAction::ToggleShowHelp => "Home.ToggleShowHelp",
+        HomeAction::ScheduleIncrement(_) => "ScheduleIncrement",
+        HomeAction::ScheduleDecrement(_) => "ScheduleDecrement",
+        HomeAction::Increment(_) => "Increment",
+        HomeAction::Decrement(_) => "Decrement",
+        HomeAction::CompleteInput(_) => "CompleteInput",
+        HomeAction::EnterNormal => "EnterNormal",
+        HomeAction::EnterInsert => "EnterInsert",
+        HomeAction::EnterProcessing => "EnterProcessing",
+        HomeAction::ExitProcessing => "ExitProcessing",
+        HomeAction::Update => "Update",
+        HomeAction::NavigateList(..) => "NavigateList",
+        HomeAction::ScrollPage(_) => "ScrollPage",
+        HomeAction::ToggleRelativeNumbers => "ToggleRelativeNumbers",
+        HomeAction::TrimEntries => "TrimEntries",
+        HomeAction::Save => "Save",
+        HomeAction::DeleteTodo => "DeleteTodo",
+        HomeAction::RemoveTodo(_) => "RemoveTodo",
+        HomeAction::DeleteTodos(_) => "DeleteTodos",
+        HomeAction::ToggleTodos(_) => "ToggleTodos",
+        HomeAction::Undo => "Undo",
+        HomeAction::Redo => "Redo",
+        HomeAction::MarkAllComplete => "MarkAllComplete",
+        HomeAction::ClearCompleted => "ClearCompleted",
+        HomeAction::SetDue(..) => "SetDue",
+        HomeAction::SortBy(_) => "SortBy",
+        HomeAction::CycleSelectedPriority => "CycleSelectedPriority",
+        HomeAction::CyclePriority(_) => "CyclePriority",
+        HomeAction::FilterByTag(_) => "FilterByTag",
+        HomeAction::TodosLoaded(_) => "TodosLoaded",
+      },
+      Action::MainMenu(action) => match action {
+        MainMenuAction::NavigateTabs(..) => "NavigateTabs",
+        MainMenuAction::ActivateTab => "ActivateTab",
+      },
+    }
+  }
+
+  #[test]
+  fn test_samples_cover_every_variant() {
+    let covered: std::collections::HashSet<_> = sample_actions().iter().map(variant_name).collect();
+    assert_eq!(covered.len(), VARIANT_COUNT);
+  }
+
+  #[test]
+  fn test_display_round_trip() {
+    for action in sample_actions() {
+      let parsed: Action = serde_json::from_value(serde_json::Value::String(action.to_string())).unwrap();
+      assert_eq!(parsed, action);
+    }
+  }
+
+  #[test]
+  fn test_string_payloads_round_trip() {
+    let payloads = [
+      "",
+      "(",
+      ")",
+      "\")",
+      "a, b",
+      "\\",
+      "\\\")",
+      "tab\there\r\n",
+      "arrows 🞁 and ✓",
+      "Engine.Quit",
+      "  spaced  ",
+      r#"{"json": [1, "two"]}"#,
+    ];
+    for payload in payloads {
+      for action in [
+        Action::from(EngineAction::Error(payload.to_string())),
+        EngineAction::Notify(payload.to_string()).into(),
+        HomeAction::CompleteInput(payload.to_string()).into(),
+      ] {
+        assert_eq!(action.to_string().parse::<Action>(), Ok(action));
       }
     }
+  }
+
+  #[test]
+  fn test_serde_json_round_trip() {
+    for action in sample_actions() {
+      let json = serde_json::to_string(&action).unwrap();
+      assert_eq!(json, serde_json::to_string(&action.to_string()).unwrap());
+      assert_eq!(serde_json::from_str::<Action>(&json).unwrap(), action);
+    }
+  }
+
+  #[test]
+  fn test_lists_deserialize_as_batches() {
+    let action: Action = serde_json::from_str(r#"["Home.EnterNormal", ["Engine.Refresh"]]"#).unwrap();
+    assert_eq!(
+      action,
+      EngineAction::Batch(vec![
+        HomeAction::EnterNormal.into(),
+        EngineAction::Batch(vec![EngineAction::Refresh.into()]).into()
+      ])
+      .into()
+    );
+
+    let too_deep = serde_json::from_str::<Action>(r#"[[[[["Engine.Refresh"]]]]]"#).unwrap_err().to_string();
+    assert!(too_deep.starts_with("Batches can be nested at most 4 deep, this one is 5"), "{too_deep}");
+    assert!(serde_json::from_str::<Action>(r#"["Engine.Refresh", "Engine.Nope"]"#).is_err());
+  }
+
+  #[test]
+  fn test_schedule_steps_default_to_one() {
+    let parse = |raw: &str| serde_json::from_value::<Action>(serde_json::Value::String(raw.to_string()));
+    assert_eq!(parse("Home.ScheduleIncrement(5)").unwrap(), HomeAction::ScheduleIncrement(5).into());
+    assert_eq!(parse("Home.ScheduleDecrement(10)").unwrap(), HomeAction::ScheduleDecrement(10).into());
+    assert_eq!(parse("Home.ScheduleIncrement").unwrap(), HomeAction::ScheduleIncrement(1).into());
+    assert_eq!(parse("Home.ScheduleDecrement()").unwrap(), HomeAction::ScheduleDecrement(1).into());
+    assert_eq!(HomeAction::ScheduleIncrement(1).to_string(), "ScheduleIncrement(1)");
+    assert_eq!(
+      parse("Home.ScheduleIncrement(lots)").unwrap_err().to_string(),
+      "Invalid argument `lots`: invalid digit found in string at column 24 of `Home.ScheduleIncrement(lots)`"
+    );
+  }
+
+  #[test]
+  fn test_descriptions_fall_back_to_config_text() {
+    assert_eq!(Action::from(HomeAction::ScheduleIncrement(3)).description(), "Increase the counter");
+    assert_eq!(Action::from(EngineAction::ChangeMode(Mode::Home)).description(), "Go to the todos screen");
+    assert_eq!(Action::from(HomeAction::RemoveTodo(7)).description(), "Home.RemoveTodo(7)");
+    assert_eq!(Action::from(EngineAction::Notify("hi".to_string())).description(), "Engine.Notify(\"hi\")");
+  }
+
+  #[test]
+  fn test_navigation_counts() {
+    let parse = |raw: &str| raw.parse::<Action>();
+    assert_eq!(parse("Home.NavigateList(Down)"), Ok(HomeAction::NavigateList(ListNavDirection::Down, 1).into()));
+    assert_eq!(parse("Home.NavigateList(Down, 3)"), Ok(HomeAction::NavigateList(ListNavDirection::Down, 3).into()));
+    assert_eq!(HomeAction::NavigateList(ListNavDirection::Down, 1).to_string(), "NavigateList(Down)");
+    assert_eq!(
+      parse("MainMenu.NavigateTabs(Left, 2)").map(|action| action.with_count(3)),
+      Ok(MainMenuAction::NavigateTabs(ListNavDirection::Left, 6).into())
+    );
+    assert_eq!(Action::from(HomeAction::Save).with_count(3), HomeAction::Save.into());
+    assert_eq!(Action::from(HomeAction::ScheduleIncrement(1)).with_count(5), HomeAction::ScheduleIncrement(5).into());
+    assert_eq!(Action::from(HomeAction::ScheduleDecrement(2)).with_count(3), HomeAction::ScheduleDecrement(6).into());
+    assert_eq!(parse("Home.DeleteTodos").map(|action| action.with_count(3)), Ok(HomeAction::DeleteTodos(3).into()));
+    assert_eq!(Action::from(HomeAction::ToggleTodos(1)).with_count(2), HomeAction::ToggleTodos(2).into());
+    assert_eq!(
+      Action::from(HomeAction::ScheduleIncrement(2)).with_count(usize::MAX),
+      HomeAction::ScheduleIncrement(usize::MAX).into()
+    );
+    assert_eq!(
+      parse("Home.NavigateList()").unwrap_err().to_string(),
+      "`NavigateList` takes a direction and an optional count at column 6 of `Home.NavigateList()`"
+    );
+  }
+
+  #[test]
+  fn test_malformed_payloads() {
+    assert!(serde_json::from_str::<Action>(r#""Engine.Resize(1, 2, 3)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.Error(unquoted)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Home.Increment(-1)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Home.NavigateList(Sideways)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.ChangeMode(Nowhere)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.Resize(1,2,3garbage)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.Resize(1, 2)x""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.Quit()""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Home.FilterByTag(work)""#).is_err());
+    assert!(serde_json::from_str::<Action>(r#""Engine.SetTheme(monochrome)""#).is_err());
+  }
 
-    deserializer.deserialize_str(ActionVisitor)
+  #[test]
+  fn test_parse_errors_name_the_problem() {
+    let error = |raw: &str| raw.parse::<Action>().unwrap_err().to_string();
+    assert_eq!(error("Engine.Jump"), "Unknown Engine action `Jump` at column 8 of `Engine.Jump`");
+    assert_eq!(
+      error("Away.Quit"),
+      "Unknown action namespace `Away`, expected one of Engine, Home, MainMenu at column 1 of `Away.Quit`"
+    );
+    assert_eq!(
+      error("Home.NavigateList(Sideways)"),
+      "Invalid argument `Sideways`: Unknown list navigation direction: Sideways at column 19 of \
+       `Home.NavigateList(Sideways)`"
+    );
+    assert_eq!(
+      error("Home.CompleteInput(milk)"),
+      "Expected a quoted string at column 20 of `Home.CompleteInput(milk)`"
+    );
+    assert_eq!(
+      error("Engine.SetTheme(\"neon\")"),
+      "Invalid argument: Unknown theme `neon`, expected one of default, high-contrast, monochrome at column \
+       17 of `Engine.SetTheme(\"neon\")`"
+    );
   }
 }
@@ -1,8 +1,19 @@
 use std::fmt::Display;
 
+use crossterm::event::KeyEvent;
 use serde::Serialize;
 
-use crate::app::Mode;
+use super::{
+  parse::{ActionCall, ParseAction, ParseError},
+  Action, ListNavDirection,
+};
+use crate::{
+  app::Mode,
+  config::{key_sequence_to_string, parse_key_sequence, parse_section, section_name, KeyBindings, Preset, Theme},
+};
+
+/// How deeply batches may be nested inside each other, so a batch that keeps producing batches can't run forever.
+pub const MAX_BATCH_DEPTH: usize = 4;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum EngineAction {
@@ -12,18 +23,231 @@ pub enum EngineAction {
   Suspend,
   Resume,
   Quit,
+  /// Asks whether to quit even though some changes couldn't be saved.
+  ConfirmQuit,
+  /// Quits without giving components a chance to object.
+  ForceQuit,
   Refresh,
+  ReloadTheme,
+  /// Re-reads the whole config from disk, keeping the current one if the files don't parse.
+  ReloadConfig,
   ToggleShowHelp,
   Error(String),
   ChangeMode(Mode),
+  /// Switches back to the mode that was active before the last mode change.
+  PreviousMode,
   ToggleShowModeSwitcher,
+  /// Moves the highlight in the open mode switcher, whichever mode is active.
+  ModeSwitcherNav(ListNavDirection),
+  IdleStart,
+  IdleEnd,
+  ToggleShowActionHistory,
+  CycleActionHistoryFilter,
+  /// Writes the action history to a file for attaching to bug reports.
+  ExportActionLog,
+  /// Outlines the areas components draw into, for debugging layouts.
+  ToggleLayoutDebug,
+  /// Moves keyboard focus to the next visible pane or overlay.
+  FocusNext,
+  /// Moves keyboard focus to the previous visible pane or overlay.
+  FocusPrevious,
+  /// Tells components whether the data directory is read-only, so they stop saving to it.
+  StorageReadOnly(bool),
+  /// Shows a short informational message in the status bar.
+  Notify(String),
+  /// Re-applies the key bindings with a different preset beneath the user's own.
+  SwitchPreset(Preset),
+  /// The keys typed so far towards a multi-key binding, empty once it completes or is abandoned.
+  PendingKeys(Vec<KeyEvent>),
+  /// The count typed so far ahead of a navigation key, 0 once it is used or dropped.
+  PendingCount(usize),
+  /// Several actions handled one after the other, each as if it had been triggered on its own.
+  Batch(Vec<Action>),
+  /// Opens or closes the overlay for running any action by name.
+  ToggleCommandPalette,
+  /// Opens or closes the overlay for changing key bindings while the app runs.
+  ToggleKeybindingEditor,
+  /// Binds keys to an action in a section of the key bindings, `None` being the global one, or unbinds them if there
+  /// is no action, on top of the loaded config.
+  SetBinding(Option<Mode>, Vec<KeyEvent>, Option<Box<Action>>),
+  /// Writes the bindings changed since the config was loaded back to the config file.
+  SaveKeybindings,
+  /// Switches to the next of the built-in color themes.
+  CycleTheme,
+  /// Switches to the built-in color theme with the given name, one of [`Theme::NAMES`].
+  SetTheme(String),
+}
+
+impl EngineAction {
+  /// A batch of `actions`, unless that would nest batches more than [`MAX_BATCH_DEPTH`] deep.
+  pub fn batch(actions: Vec<Action>) -> Result<Self, String> {
+    let batch = Self::Batch(actions);
+    match batch.batch_depth() {
+      depth if depth > MAX_BATCH_DEPTH => {
+        Err(format!("Batches can be nested at most {MAX_BATCH_DEPTH} deep, this one is {depth}"))
+      },
+      _ => Ok(batch),
+    }
+  }
+
+  /// How many batches deep the action goes, 0 if it isn't a batch.
+  pub fn batch_depth(&self) -> usize {
+    match self {
+      Self::Batch(actions) => {
+        let inner = |action: &Action| match action {
+          Action::Engine(action) => action.batch_depth(),
+          _ => 0,
+        };
+        1 + actions.iter().map(inner).max().unwrap_or(0)
+      },
+      _ => 0,
+    }
+  }
+
+  /// What the action does, in words for someone reading the help rather than writing a config. `None` for actions
+  /// the app only sends itself, or whose arguments say more than a fixed sentence could.
+  pub fn description(&self) -> Option<&'static str> {
+    match self {
+      Self::Suspend => Some("Suspend the app to the shell"),
+      Self::Quit => Some("Quit"),
+      Self::ForceQuit => Some("Quit without saving"),
+      Self::Refresh => Some("Redraw the whole screen and reread the todos"),
+      Self::ReloadTheme => Some("Reload the colours from the config"),
+      Self::ReloadConfig => Some("Reload the whole config"),
+      Self::ToggleShowHelp => Some("Open or close the help overlay"),
+      Self::ChangeMode(Mode::MainMenu) => Some("Go to the main menu"),
+      Self::ChangeMode(Mode::Home) => Some("Go to the todos screen"),
+      Self::PreviousMode => Some("Go back to the previous screen"),
+      Self::ToggleShowModeSwitcher => Some("Open or close the mode switcher"),
+      Self::ModeSwitcherNav(ListNavDirection::Left | ListNavDirection::Up | ListNavDirection::PageUp) => {
+        Some("Highlight the previous mode in the mode switcher")
+      },
+      Self::ModeSwitcherNav(ListNavDirection::Right | ListNavDirection::Down | ListNavDirection::PageDown) => {
+        Some("Highlight the next mode in the mode switcher")
+      },
+      Self::ModeSwitcherNav(ListNavDirection::First) => Some("Highlight the first mode in the mode switcher"),
+      Self::ModeSwitcherNav(ListNavDirection::Last) => Some("Highlight the last mode in the mode switcher"),
+      Self::ToggleShowActionHistory => Some("Show or hide recent actions"),
+      Self::CycleActionHistoryFilter => Some("Change which recent actions are shown"),
+      Self::ExportActionLog => Some("Save recent actions to a file"),
+      Self::ToggleLayoutDebug => Some("Outline each pane's area"),
+      Self::FocusNext => Some("Focus the next pane"),
+      Self::FocusPrevious => Some("Focus the previous pane"),
+      Self::SwitchPreset(Preset::Default) => Some("Use the default key bindings"),
+      Self::SwitchPreset(Preset::Vim) => Some("Use vim-style key bindings"),
+      Self::ToggleCommandPalette => Some("Run any action by name"),
+      Self::ToggleKeybindingEditor => Some("Open or close the key binding editor"),
+      Self::SaveKeybindings => Some("Save changed key bindings to the config file"),
+      Self::CycleTheme => Some("Switch to the next color theme"),
+      Self::Tick
+      | Self::Render
+      | Self::Resize(..)
+      | Self::Resume
+      | Self::ConfirmQuit
+      | Self::Error(_)
+      | Self::IdleStart
+      | Self::IdleEnd
+      | Self::StorageReadOnly(_)
+      | Self::Notify(_)
+      | Self::PendingKeys(_)
+      | Self::PendingCount(_)
+      | Self::Batch(_)
+      | Self::SetBinding(..)
+      | Self::SetTheme(_) => None,
+    }
+  }
+}
+
+impl ParseAction for EngineAction {
+  fn parse_call(call: &ActionCall<'_>) -> Result<Self, ParseError> {
+    match call.variant() {
+      "Tick" => call.unit(Self::Tick),
+      "Render" => call.unit(Self::Render),
+      "Suspend" => call.unit(Self::Suspend),
+      "Resume" => call.unit(Self::Resume),
+      "Quit" => call.unit(Self::Quit),
+      "ConfirmQuit" => call.unit(Self::ConfirmQuit),
+      "ForceQuit" => call.unit(Self::ForceQuit),
+      "Refresh" => call.unit(Self::Refresh),
+      "ReloadTheme" => call.unit(Self::ReloadTheme),
+      "ReloadConfig" => call.unit(Self::ReloadConfig),
+      "ToggleShowHelp" => call.unit(Self::ToggleShowHelp),
+      "PreviousMode" => call.unit(Self::PreviousMode),
+      "ToggleShowModeSwitcher" => call.unit(Self::ToggleShowModeSwitcher),
+      "IdleStart" => call.unit(Self::IdleStart),
+      "IdleEnd" => call.unit(Self::IdleEnd),
+      "ToggleShowActionHistory" => call.unit(Self::ToggleShowActionHistory),
+      "CycleActionHistoryFilter" => call.unit(Self::CycleActionHistoryFilter),
+      "ExportActionLog" => call.unit(Self::ExportActionLog),
+      "ToggleLayoutDebug" => call.unit(Self::ToggleLayoutDebug),
+      "FocusNext" => call.unit(Self::FocusNext),
+      "FocusPrevious" => call.unit(Self::FocusPrevious),
+      "ToggleCommandPalette" => call.unit(Self::ToggleCommandPalette),
+      "ToggleKeybindingEditor" => call.unit(Self::ToggleKeybindingEditor),
+      "SaveKeybindings" => call.unit(Self::SaveKeybindings),
+      "CycleTheme" => call.unit(Self::CycleTheme),
+      "Resize" => {
+        let [width, height] = call.args()?;
+        Ok(Self::Resize(width.parse()?, height.parse()?))
+      },
+      "Error" => Ok(Self::Error(call.args::<1>()?[0].string()?)),
+      "Notify" => Ok(Self::Notify(call.args::<1>()?[0].string()?)),
+      "SetTheme" => Ok(Self::SetTheme(call.args::<1>()?[0].string_with(|name| match Theme::builtin(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(format!("Unknown theme `{name}`, expected one of {}", Theme::NAMES.join(", "))),
+      })?)),
+      "ChangeMode" => Ok(Self::ChangeMode(call.args::<1>()?[0].parse()?)),
+      "ModeSwitcherNav" => Ok(Self::ModeSwitcherNav(call.args::<1>()?[0].parse()?)),
+      "StorageReadOnly" => Ok(Self::StorageReadOnly(call.args::<1>()?[0].parse()?)),
+      "SwitchPreset" => Ok(Self::SwitchPreset(call.args::<1>()?[0].parse()?)),
+      "PendingCount" => Ok(Self::PendingCount(call.args::<1>()?[0].parse()?)),
+      "PendingKeys" => match call.optional_arg()? {
+        Some(keys) => Ok(Self::PendingKeys(keys.parse_with(parse_key_sequence)?)),
+        None => Ok(Self::PendingKeys(Vec::new())),
+      },
+      "SetBinding" => {
+        let [section, keys, action] = call.args()?;
+        let action = match action.string() {
+          Ok(_) => Some(Box::new(action.string_with(str::parse)?)),
+          Err(_) => action.parse_with(|raw| match raw == KeyBindings::UNBIND {
+            true => Ok(None),
+            false => Err(format!("expected a quoted action or `{}`", KeyBindings::UNBIND)),
+          })?,
+        };
+        Ok(Self::SetBinding(section.parse_with(parse_section)?, keys.parse_with(parse_key_sequence)?, action))
+      },
+      "Batch" => {
+        let actions = call.all_args().iter().map(|arg| arg.string_with(str::parse)).collect::<Result<_, _>>()?;
+        Self::batch(actions).map_err(|e| call.invalid(e))
+      },
+      _ => Err(call.unknown_variant()),
+    }
+  }
 }
 
 impl Display for EngineAction {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       Self::Resize(x, y) => write!(f, "Resize({x}, {y})"),
-      Self::Error(x) => write!(f, "Error({x:?})"),
+      Self::Error(x) => write!(f, "Error({})", serde_json::to_string(x).map_err(|_| std::fmt::Error)?),
+      Self::Notify(x) => write!(f, "Notify({})", serde_json::to_string(x).map_err(|_| std::fmt::Error)?),
+      Self::SetTheme(x) => write!(f, "SetTheme({})", serde_json::to_string(x).map_err(|_| std::fmt::Error)?),
+      Self::PendingKeys(keys) => write!(f, "PendingKeys({})", key_sequence_to_string(keys)),
+      Self::SetBinding(section, keys, action) => {
+        let action = match action {
+          Some(action) => serde_json::to_string(&action.to_string()).map_err(|_| std::fmt::Error)?,
+          None => KeyBindings::UNBIND.to_string(),
+        };
+        write!(f, "SetBinding({}, {}, {action})", section_name(*section), key_sequence_to_string(keys))
+      },
+      Self::Batch(actions) => {
+        let actions = actions
+          .iter()
+          .map(|action| serde_json::to_string(&action.to_string()))
+          .collect::<Result<Vec<_>, _>>()
+          .map_err(|_| std::fmt::Error)?;
+        write!(f, "Batch({})", actions.join(", "))
+      },
       x => write!(f, "{:?}", x),
     }
   }
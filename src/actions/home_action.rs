@@ -1,21 +1,33 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use chrono::NaiveDate;
 use serde::Serialize;
 
+use super::parse::{ActionCall, ParseAction, ParseError};
+use crate::todos::{TodoSort, TodoStore};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum ListNavDirection {
   Left,
   Right,
   Up,
   Down,
+  /// The first item.
+  First,
+  /// The last item.
+  Last,
+  PageUp,
+  PageDown,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum HomeAction {
   Help,
   ToggleShowHelp,
-  ScheduleIncrement,
-  ScheduleDecrement,
+  /// Increments the counter by the given step from a background task. Written without an argument, the step is 1.
+  ScheduleIncrement(usize),
+  /// Decrements the counter by the given step from a background task. Written without an argument, the step is 1.
+  ScheduleDecrement(usize),
   Increment(usize),
   Decrement(usize),
   CompleteInput(String),
@@ -24,31 +36,220 @@ pub enum HomeAction {
   EnterProcessing,
   ExitProcessing,
   Update,
-  NavigateList(ListNavDirection),
+  /// Moves the selection the given number of steps.
+  NavigateList(ListNavDirection, usize),
+  /// Scrolls the todo list a screenful up or down, moving the selection along with it.
+  ScrollPage(ListNavDirection),
+  ToggleRelativeNumbers,
+  TrimEntries,
+  /// Saves the todos, or asks for somewhere else to export them when the data directory is read-only.
+  Save,
+  /// Asks for confirmation before deleting the selected todo.
+  DeleteTodo,
+  /// Deletes the todo with the given id, without asking.
+  RemoveTodo(u32),
+  /// Deletes the given number of todos from the selected one down, as a single change to undo. Written without an
+  /// argument, it is 1.
+  DeleteTodos(usize),
+  /// Flips whether the given number of todos from the selected one down are completed, as a single change to undo.
+  /// Written without an argument, it is 1.
+  ToggleTodos(usize),
+  /// Reverts the most recent change to the todos.
+  Undo,
+  /// Reapplies the most recently undone change to the todos.
+  Redo,
+  /// Marks every todo completed, as a single change to undo.
+  MarkAllComplete,
+  /// Deletes every completed todo, as a single change to undo.
+  ClearCompleted,
+  /// Sets the due date of the todo with the given id, written `SetDue(3, 2024-01-11)`, or clears it with `None`.
+  SetDue(u32, Option<NaiveDate>),
+  /// Reorders the todos.
+  SortBy(TodoSort),
+  /// Cycles the priority of the selected todo.
+  CycleSelectedPriority,
+  /// Raises the priority of the todo with the given id a level, going from High back round to Low.
+  CyclePriority(u32),
+  /// Lists only the todos with the given tag, written `FilterByTag("work")`, or every todo again with `None`.
+  FilterByTag(Option<String>),
+  /// Hands over the todos read from disk in the background, written with them as a quoted JSON array.
+  TodosLoaded(TodoStore),
+}
+
+impl HomeAction {
+  /// A plain description for the help screen. Actions the app sends itself, and those that only make sense with
+  /// their arguments spelled out, have none.
+  pub fn description(&self) -> Option<&'static str> {
+    match self {
+      Self::Help | Self::ToggleShowHelp => Some("Open or close the help overlay"),
+      Self::ScheduleIncrement(_) => Some("Increase the counter"),
+      Self::ScheduleDecrement(_) => Some("Decrease the counter"),
+      Self::EnterInsert => Some("Type a new todo"),
+      Self::EnterNormal => Some("Stop typing"),
+      Self::NavigateList(ListNavDirection::Left | ListNavDirection::Up, _) => Some("Select the previous todo"),
+      Self::NavigateList(ListNavDirection::Right | ListNavDirection::Down, _) => Some("Select the next todo"),
+      Self::NavigateList(ListNavDirection::First, _) => Some("Select the first todo"),
+      Self::NavigateList(ListNavDirection::Last, _) => Some("Select the last todo"),
+      Self::NavigateList(ListNavDirection::PageUp, _)
+      | Self::ScrollPage(
+        ListNavDirection::Left | ListNavDirection::Up | ListNavDirection::First | ListNavDirection::PageUp,
+      ) => Some("Scroll the todos up a page"),
+      Self::NavigateList(ListNavDirection::PageDown, _) | Self::ScrollPage(_) => Some("Scroll the todos down a page"),
+      Self::ToggleRelativeNumbers => Some("Switch between absolute and relative line numbers"),
+      Self::TrimEntries => Some("Trim whitespace from every todo"),
+      Self::Save => Some("Save the todos"),
+      Self::DeleteTodo => Some("Delete the selected todo"),
+      Self::DeleteTodos(_) => Some("Delete the selected todo, or as many as the count from it down"),
+      Self::ToggleTodos(_) => Some("Complete or reopen the selected todo, or as many as the count from it down"),
+      Self::Undo => Some("Undo the last change"),
+      Self::Redo => Some("Redo the last undone change"),
+      Self::MarkAllComplete => Some("Mark every todo complete"),
+      Self::ClearCompleted => Some("Delete every completed todo"),
+      Self::SortBy(TodoSort::Created) => Some("List todos in the order they were added"),
+      Self::SortBy(TodoSort::Due) => Some("List the soonest due todos first"),
+      Self::SortBy(TodoSort::Priority) => Some("List the most important todos first"),
+      Self::CycleSelectedPriority => Some("Change the selected todo's priority"),
+      Self::FilterByTag(None) => Some("List every todo, whatever its tags"),
+      Self::Increment(_)
+      | Self::Decrement(_)
+      | Self::CompleteInput(_)
+      | Self::EnterProcessing
+      | Self::ExitProcessing
+      | Self::Update
+      | Self::RemoveTodo(_)
+      | Self::SetDue(..)
+      | Self::CyclePriority(_)
+      | Self::FilterByTag(Some(_))
+      | Self::TodosLoaded(_) => None,
+    }
+  }
+}
+
+impl FromStr for ListNavDirection {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Left" => Ok(Self::Left),
+      "Right" => Ok(Self::Right),
+      "Up" => Ok(Self::Up),
+      "Down" => Ok(Self::Down),
+      "First" => Ok(Self::First),
+      "Last" => Ok(Self::Last),
+      "PageUp" => Ok(Self::PageUp),
+      "PageDown" => Ok(Self::PageDown),
+      _ => Err(format!("Unknown list navigation direction: {s}")),
+    }
+  }
+}
+
+impl ParseAction for HomeAction {
+  fn parse_call(call: &ActionCall<'_>) -> Result<Self, ParseError> {
+    match call.variant() {
+      "Help" => call.unit(Self::Help),
+      "ToggleShowHelp" => call.unit(Self::ToggleShowHelp),
+      "EnterNormal" => call.unit(Self::EnterNormal),
+      "EnterInsert" => call.unit(Self::EnterInsert),
+      "EnterProcessing" => call.unit(Self::EnterProcessing),
+      "ExitProcessing" => call.unit(Self::ExitProcessing),
+      "Update" => call.unit(Self::Update),
+      "ToggleRelativeNumbers" => call.unit(Self::ToggleRelativeNumbers),
+      "TrimEntries" => call.unit(Self::TrimEntries),
+      "Save" => call.unit(Self::Save),
+      "DeleteTodo" => call.unit(Self::DeleteTodo),
+      "Undo" => call.unit(Self::Undo),
+      "Redo" => call.unit(Self::Redo),
+      "MarkAllComplete" => call.unit(Self::MarkAllComplete),
+      "ClearCompleted" => call.unit(Self::ClearCompleted),
+      "CycleSelectedPriority" => call.unit(Self::CycleSelectedPriority),
+      "ScheduleIncrement" => Ok(Self::ScheduleIncrement(call.optional_arg()?.map_or(Ok(1), |step| step.parse())?)),
+      "ScheduleDecrement" => Ok(Self::ScheduleDecrement(call.optional_arg()?.map_or(Ok(1), |step| step.parse())?)),
+      "DeleteTodos" => Ok(Self::DeleteTodos(call.optional_arg()?.map_or(Ok(1), |count| count.parse())?)),
+      "ToggleTodos" => Ok(Self::ToggleTodos(call.optional_arg()?.map_or(Ok(1), |count| count.parse())?)),
+      "Increment" => Ok(Self::Increment(call.args::<1>()?[0].parse()?)),
+      "Decrement" => Ok(Self::Decrement(call.args::<1>()?[0].parse()?)),
+      "RemoveTodo" => Ok(Self::RemoveTodo(call.args::<1>()?[0].parse()?)),
+      "CyclePriority" => Ok(Self::CyclePriority(call.args::<1>()?[0].parse()?)),
+      "FilterByTag" => {
+        let [tag] = call.args()?;
+        match tag.string() {
+          Ok(tag) => Ok(Self::FilterByTag(Some(tag))),
+          Err(_) => tag.parse_with(|raw| match raw {
+            "None" => Ok(Self::FilterByTag(None)),
+            _ => Err("expected a quoted tag or `None`"),
+          }),
+        }
+      },
+      "CompleteInput" => Ok(Self::CompleteInput(call.args::<1>()?[0].string()?)),
+      "TodosLoaded" => Ok(Self::TodosLoaded(call.args::<1>()?[0].string_with(|json| serde_json::from_str(json))?)),
+      "NavigateList" => parse_navigation(call).map(|(dir, count)| Self::NavigateList(dir, count)),
+      "ScrollPage" => Ok(Self::ScrollPage(call.args::<1>()?[0].parse()?)),
+      "SortBy" => Ok(Self::SortBy(call.args::<1>()?[0].parse()?)),
+      "SetDue" => {
+        let [id, due] = call.args()?;
+        let due = due.parse_with(|raw| match raw {
+          "None" => Ok(None),
+          raw => raw.parse::<NaiveDate>().map(Some),
+        })?;
+        Ok(Self::SetDue(id.parse()?, due))
+      },
+      _ => Err(call.unknown_variant()),
+    }
+  }
+}
+
+/// The direction and optional step count of a navigation action, e.g. `(Left)` or `(Left, 3)`. The count defaults to 1.
+pub(super) fn parse_navigation(call: &ActionCall<'_>) -> Result<(ListNavDirection, usize), ParseError> {
+  match call.all_args() {
+    [dir] => Ok((dir.parse()?, 1)),
+    [dir, count] => Ok((dir.parse()?, count.parse()?)),
+    _ => Err(call.invalid(format!("`{}` takes a direction and an optional count", call.variant()))),
+  }
+}
+
+/// Writes a navigation action's arguments the way [`parse_navigation`] reads them, leaving out a count of 1.
+pub(super) fn write_navigation(
+  f: &mut std::fmt::Formatter<'_>,
+  name: &str,
+  dir: &ListNavDirection,
+  count: usize,
+) -> std::fmt::Result {
+  match count {
+    1 => write!(f, "{name}({dir})"),
+    _ => write!(f, "{name}({dir}, {count})"),
+  }
 }
 
+/// Written the way [`FromStr`] reads it back, so actions holding a direction round-trip through the config.
 impl Display for ListNavDirection {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(
-      f,
-      "NavigateList({})",
-      match self {
-        Self::Up => "🞁",
-        Self::Down => "🞃",
-        Self::Left => "🞀",
-        Self::Right => "🞂",
-      }
-    )
+    write!(f, "{self:?}")
   }
 }
 
 impl Display for HomeAction {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
+      Self::ScheduleIncrement(x) => write!(f, "ScheduleIncrement({x})"),
+      Self::ScheduleDecrement(x) => write!(f, "ScheduleDecrement({x})"),
       Self::Increment(x) => write!(f, "Increment({x})"),
       Self::Decrement(x) => write!(f, "Decrement({x})"),
-      Self::CompleteInput(x) => write!(f, "CompleteInput({x})"),
-      Self::NavigateList(x) => write!(f, "NavigateList.{x:?}"),
+      Self::DeleteTodos(x) => write!(f, "DeleteTodos({x})"),
+      Self::ToggleTodos(x) => write!(f, "ToggleTodos({x})"),
+      Self::CompleteInput(x) => write!(f, "CompleteInput({})", serde_json::to_string(x).map_err(|_| std::fmt::Error)?),
+      Self::NavigateList(dir, count) => write_navigation(f, "NavigateList", dir, *count),
+      Self::ScrollPage(x) => write!(f, "ScrollPage({x})"),
+      Self::SetDue(id, Some(due)) => write!(f, "SetDue({id}, {due})"),
+      Self::SetDue(id, None) => write!(f, "SetDue({id}, None)"),
+      Self::SortBy(x) => write!(f, "SortBy({x})"),
+      Self::FilterByTag(Some(tag)) => {
+        write!(f, "FilterByTag({})", serde_json::to_string(tag).map_err(|_| std::fmt::Error)?)
+      },
+      Self::FilterByTag(None) => write!(f, "FilterByTag(None)"),
+      Self::TodosLoaded(todos) => {
+        let json = serde_json::to_string(todos).map_err(|_| std::fmt::Error)?;
+        write!(f, "TodosLoaded({})", serde_json::to_string(&json).map_err(|_| std::fmt::Error)?)
+      },
       x => write!(f, "{:?}", x),
     }
   }
@@ -0,0 +1,53 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::{
+  home_action::{parse_navigation, write_navigation},
+  parse::{ActionCall, ParseAction, ParseError},
+  ListNavDirection,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MainMenuAction {
+  /// Moves to the tab the given number of steps away.
+  NavigateTabs(ListNavDirection, usize),
+  /// Runs the action configured for the selected tab.
+  ActivateTab,
+}
+
+impl MainMenuAction {
+  /// What the action does, in words for someone reading the help rather than writing a config.
+  pub fn description(&self) -> Option<&'static str> {
+    match self {
+      Self::NavigateTabs(ListNavDirection::Left | ListNavDirection::Up | ListNavDirection::PageUp, _) => {
+        Some("Select the previous tab")
+      },
+      Self::NavigateTabs(ListNavDirection::Right | ListNavDirection::Down | ListNavDirection::PageDown, _) => {
+        Some("Select the next tab")
+      },
+      Self::NavigateTabs(ListNavDirection::First, _) => Some("Select the first tab"),
+      Self::NavigateTabs(ListNavDirection::Last, _) => Some("Select the last tab"),
+      Self::ActivateTab => Some("Open the selected tab"),
+    }
+  }
+}
+
+impl ParseAction for MainMenuAction {
+  fn parse_call(call: &ActionCall<'_>) -> Result<Self, ParseError> {
+    match call.variant() {
+      "NavigateTabs" => parse_navigation(call).map(|(dir, count)| Self::NavigateTabs(dir, count)),
+      "ActivateTab" => call.unit(Self::ActivateTab),
+      _ => Err(call.unknown_variant()),
+    }
+  }
+}
+
+impl Display for MainMenuAction {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NavigateTabs(dir, count) => write_navigation(f, "NavigateTabs", dir, *count),
+      Self::ActivateTab => write!(f, "ActivateTab"),
+    }
+  }
+}
@@ -0,0 +1,358 @@
+//! Parsing of actions written as `Namespace.Variant` or `Namespace.Variant(arg, ...)`, as used in the config.
+//!
+//! [`ActionCall::parse`] splits the text into its parts without knowing anything about the actions themselves. Each
+//! action enum then builds itself from those parts by implementing [`ParseAction`].
+
+use std::{fmt, str::FromStr};
+
+/// Why some text isn't a valid action, and where in it the problem is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  input: String,
+  /// Byte offset into `input`.
+  position: usize,
+  message: String,
+}
+
+impl ParseError {
+  fn new(input: &str, position: usize, message: impl Into<String>) -> Self {
+    Self { input: input.to_string(), position, message: message.into() }
+  }
+
+  /// The 1-based column of the offending character.
+  pub fn column(&self) -> usize {
+    self.input[..self.position].chars().count() + 1
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at column {} of `{}`", self.message, self.column(), self.input)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An action enum that can be built from a parsed [`ActionCall`] in its namespace.
+pub trait ParseAction: Sized {
+  fn parse_call(call: &ActionCall<'_>) -> Result<Self, ParseError>;
+}
+
+/// A single argument between an action's parentheses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg<'a> {
+  input: &'a str,
+  position: usize,
+  value: ArgValue<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ArgValue<'a> {
+  /// Written as is, e.g. `42`, `Left` or `<g><ctrl-x>`.
+  Bare(&'a str),
+  /// A JSON string, so it can hold parentheses, commas and quotes.
+  Quoted(String),
+}
+
+impl Arg<'_> {
+  fn error(&self, message: impl Into<String>) -> ParseError {
+    ParseError::new(self.input, self.position, message)
+  }
+
+  fn bare(&self) -> Result<&str, ParseError> {
+    match &self.value {
+      ArgValue::Bare(raw) => Ok(raw),
+      ArgValue::Quoted(_) => Err(self.error("Expected an unquoted argument")),
+    }
+  }
+
+  /// The argument converted with its [`FromStr`] implementation.
+  pub fn parse<T>(&self) -> Result<T, ParseError>
+  where
+    T: FromStr,
+    T::Err: fmt::Display,
+  {
+    self.parse_with(|raw| raw.parse::<T>())
+  }
+
+  /// The argument converted with `parse`.
+  pub fn parse_with<T, E: fmt::Display>(&self, parse: impl FnOnce(&str) -> Result<T, E>) -> Result<T, ParseError> {
+    let raw = self.bare()?;
+    parse(raw).map_err(|e| self.error(format!("Invalid argument `{raw}`: {e}")))
+  }
+
+  /// The contents of a quoted string argument.
+  pub fn string(&self) -> Result<String, ParseError> {
+    match &self.value {
+      ArgValue::Quoted(s) => Ok(s.clone()),
+      ArgValue::Bare(_) => Err(self.error("Expected a quoted string")),
+    }
+  }
+
+  /// The contents of a quoted string argument converted with `parse`.
+  pub fn string_with<T, E: fmt::Display>(&self, parse: impl FnOnce(&str) -> Result<T, E>) -> Result<T, ParseError> {
+    parse(&self.string()?).map_err(|e| self.error(format!("Invalid argument: {e}")))
+  }
+}
+
+/// An action split into its namespace, variant and arguments, none of which have been checked yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionCall<'a> {
+  input: &'a str,
+  namespace: &'a str,
+  variant: &'a str,
+  variant_position: usize,
+  /// The arguments and where the opening parenthesis is, if there are parentheses at all.
+  args: Option<(usize, Vec<Arg<'a>>)>,
+}
+
+impl<'a> ActionCall<'a> {
+  pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+    let mut cursor = Cursor { input, position: 0 };
+    let namespace = cursor.identifier("Expected an action namespace such as `Engine`")?;
+    cursor.expect('.', "Expected `.` after the namespace")?;
+    let variant_position = cursor.position;
+    let variant = cursor.identifier("Expected an action name after the `.`")?;
+    let args = match cursor.peek() {
+      None => None,
+      Some('(') => {
+        let paren = cursor.position;
+        cursor.bump();
+        Some((paren, cursor.args(paren)?))
+      },
+      Some(_) => return Err(cursor.error("Expected `(` or the end of the action")),
+    };
+    if cursor.peek().is_some() {
+      return Err(cursor.error("Unexpected text after the action"));
+    }
+    Ok(Self { input, namespace, variant, variant_position, args })
+  }
+
+  pub fn namespace(&self) -> &'a str {
+    self.namespace
+  }
+
+  pub fn variant(&self) -> &'a str {
+    self.variant
+  }
+
+  /// Accepts the action only if it has no parentheses, returning `action`.
+  pub fn unit<T>(&self, action: T) -> Result<T, ParseError> {
+    match &self.args {
+      None => Ok(action),
+      Some((paren, _)) => Err(ParseError::new(self.input, *paren, format!("`{}` takes no arguments", self.variant))),
+    }
+  }
+
+  /// The arguments, which must number exactly `N`.
+  pub fn args<const N: usize>(&self) -> Result<[&Arg<'a>; N], ParseError> {
+    let (position, args) = match &self.args {
+      Some((paren, args)) => (*paren, args.iter().collect::<Vec<_>>()),
+      None => (self.input.len(), Vec::new()),
+    };
+    let count = args.len();
+    args.try_into().map_err(|_| {
+      let plural = if N == 1 { "" } else { "s" };
+      ParseError::new(self.input, position, format!("`{}` takes {N} argument{plural}, got {count}", self.variant))
+    })
+  }
+
+  /// However many arguments there are, none if there are no parentheses.
+  pub fn all_args(&self) -> &[Arg<'a>] {
+    self.args.as_ref().map_or(&[], |(_, args)| args)
+  }
+
+  /// The single argument, or `None` if there are no parentheses or they are empty.
+  pub fn optional_arg(&self) -> Result<Option<&Arg<'a>>, ParseError> {
+    match &self.args {
+      None => Ok(None),
+      Some((_, args)) if args.is_empty() => Ok(None),
+      _ => self.args::<1>().map(|[arg]| Some(arg)),
+    }
+  }
+
+  /// An error about the action as a whole, pointing at its name.
+  pub fn invalid(&self, message: impl Into<String>) -> ParseError {
+    ParseError::new(self.input, self.variant_position, message)
+  }
+
+  /// The error for a variant the namespace doesn't have.
+  pub fn unknown_variant(&self) -> ParseError {
+    ParseError::new(self.input, self.variant_position, format!("Unknown {} action `{}`", self.namespace, self.variant))
+  }
+
+  /// The error for a namespace that isn't one of `known`.
+  pub fn unknown_namespace(&self, known: &[&str]) -> ParseError {
+    ParseError::new(
+      self.input,
+      0,
+      format!("Unknown action namespace `{}`, expected one of {}", self.namespace, known.join(", ")),
+    )
+  }
+}
+
+struct Cursor<'a> {
+  input: &'a str,
+  position: usize,
+}
+
+impl<'a> Cursor<'a> {
+  fn error(&self, message: impl Into<String>) -> ParseError {
+    ParseError::new(self.input, self.position, message)
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.input[self.position..].chars().next()
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.peek()?;
+    self.position += c.len_utf8();
+    Some(c)
+  }
+
+  fn expect(&mut self, expected: char, message: &str) -> Result<(), ParseError> {
+    match self.peek() {
+      Some(c) if c == expected => {
+        self.bump();
+        Ok(())
+      },
+      _ => Err(self.error(message)),
+    }
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().is_some_and(char::is_whitespace) {
+      self.bump();
+    }
+  }
+
+  fn identifier(&mut self, message: &str) -> Result<&'a str, ParseError> {
+    let start = self.position;
+    if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+      return Err(self.error(message));
+    }
+    while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+      self.bump();
+    }
+    Ok(&self.input[start..self.position])
+  }
+
+  /// The comma-separated arguments after the `(` at `paren`, up to and including the closing `)`.
+  fn args(&mut self, paren: usize) -> Result<Vec<Arg<'a>>, ParseError> {
+    let mut args = Vec::new();
+    self.skip_whitespace();
+    if self.peek() == Some(')') {
+      self.bump();
+      return Ok(args);
+    }
+    loop {
+      self.skip_whitespace();
+      args.push(self.arg()?);
+      self.skip_whitespace();
+      match self.peek() {
+        Some(',') => self.bump(),
+        Some(')') => {
+          self.bump();
+          return Ok(args);
+        },
+        Some(_) => return Err(self.error("Expected `,` or `)`")),
+        None => return Err(ParseError::new(self.input, paren, "Unclosed `(`")),
+      };
+    }
+  }
+
+  fn arg(&mut self) -> Result<Arg<'a>, ParseError> {
+    let start = self.position;
+    let value = if self.peek() == Some('"') {
+      self.bump();
+      loop {
+        match self.bump() {
+          Some('\\') => {
+            self.bump();
+          },
+          Some('"') => break,
+          Some(_) => {},
+          None => return Err(ParseError::new(self.input, start, "Unterminated string")),
+        }
+      }
+      let quoted = &self.input[start..self.position];
+      ArgValue::Quoted(
+        serde_json::from_str(quoted).map_err(|e| ParseError::new(self.input, start, format!("Invalid string: {e}")))?,
+      )
+    } else {
+      while let Some(c) = self.peek() {
+        match c {
+          ',' | ')' => break,
+          c if c.is_whitespace() => break,
+          // Keys such as `<,>` or `<)>` are taken whole, whatever is inside the brackets.
+          '<' => {
+            self.bump();
+            self.bump();
+            while self.peek().is_some_and(|c| c != '>') {
+              self.bump();
+            }
+            self.bump();
+          },
+          _ => {
+            self.bump();
+          },
+        }
+      }
+      if self.position == start {
+        return Err(self.error("Expected an argument"));
+      }
+      ArgValue::Bare(&self.input[start..self.position])
+    };
+    Ok(Arg { input: self.input, position: start, value })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn error(input: &str) -> String {
+    ActionCall::parse(input).unwrap_err().to_string()
+  }
+
+  #[test]
+  fn test_splits_calls() -> Result<(), ParseError> {
+    let call = ActionCall::parse(r#"Engine.Resize( 80,24 )"#)?;
+    assert_eq!((call.namespace(), call.variant()), ("Engine", "Resize"));
+    let [width, height] = call.args::<2>()?;
+    assert_eq!((width.parse::<u16>()?, height.parse::<u16>()?), (80, 24));
+
+    let call = ActionCall::parse(r#"Home.CompleteInput("a, (b)\" c")"#)?;
+    assert_eq!(call.args::<1>()?[0].string()?, r#"a, (b)" c"#);
+
+    let call = ActionCall::parse("Engine.PendingKeys(<,><)><ctrl-x>)")?;
+    assert_eq!(call.optional_arg()?.map(|arg| arg.bare().unwrap()), Some("<,><)><ctrl-x>"));
+    assert_eq!(ActionCall::parse("Engine.PendingKeys()")?.optional_arg()?, None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_errors_point_at_the_problem() {
+    assert_eq!(error("Engine"), "Expected `.` after the namespace at column 7 of `Engine`");
+    assert_eq!(error("Engine.(1)"), "Expected an action name after the `.` at column 8 of `Engine.(1)`");
+    assert_eq!(error("Engine.Resize(1 2)"), "Expected `,` or `)` at column 17 of `Engine.Resize(1 2)`");
+    assert_eq!(error("Engine.Resize(1,"), "Expected an argument at column 17 of `Engine.Resize(1,`");
+    assert_eq!(error("Engine.Resize(1"), "Unclosed `(` at column 14 of `Engine.Resize(1`");
+    assert_eq!(error("Engine.Quit()x"), "Unexpected text after the action at column 14 of `Engine.Quit()x`");
+    assert_eq!(error(r#"Engine.Error("oops)"#), r#"Unterminated string at column 14 of `Engine.Error("oops)`"#);
+
+    let call = ActionCall::parse("Engine.Resize(1,2,3garbage)").unwrap();
+    assert_eq!(
+      call.args::<2>().unwrap_err().to_string(),
+      "`Resize` takes 2 arguments, got 3 at column 14 of `Engine.Resize(1,2,3garbage)`"
+    );
+    let call = ActionCall::parse("Engine.Resize(1, 2x)").unwrap();
+    assert_eq!(
+      call.args::<2>().unwrap()[1].parse::<u16>().unwrap_err().to_string(),
+      "Invalid argument `2x`: invalid digit found in string at column 18 of `Engine.Resize(1, 2x)`"
+    );
+  }
+}
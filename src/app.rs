@@ -1,15 +1,45 @@
+use std::{
+  collections::{HashMap, VecDeque},
+  str::FromStr,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+
 use color_eyre::eyre::Result;
-use crossterm::event::KeyEvent;
-use ratatui::prelude::Rect;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  prelude::{Alignment, Backend, Rect},
+  widgets::Paragraph,
+  Terminal,
+};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 use crate::{
-  actions::{Action, EngineAction},
+  actions::{engine_actions::MAX_BATCH_DEPTH, Action, EngineAction},
   components::{
-    fps::FpsCounter, help_screen::HelpScreen, home::Home, main_menu::MainMenu, mode_switcher::ModeSwitcher, Component,
+    action_history::ActionHistory,
+    command_palette::CommandPalette,
+    fps::FpsCounter,
+    help_screen::HelpScreen,
+    home::Home,
+    key_hint_bar::KeyHintBar,
+    keybinding_editor::KeybindingEditor,
+    layout_debug::{LayoutDebug, Outline, ResourcePanel},
+    main_menu::MainMenu,
+    mode_switcher::ModeSwitcher,
+    quit_prompt::QuitPrompt,
+    status_bar::StatusBar,
+    which_key::WhichKey,
+    Applicability, Component,
   },
   config::Config,
+  config_watcher::ConfigWatcher,
+  idle::IdleTracker,
+  recording::{Recorder, Replay},
+  resources::{Occupancy, ProcessMemory, ResourceMonitor, ResourceUsage},
+  storage::StorageHealth,
+  tasks::TaskSet,
   tui,
 };
 
@@ -20,147 +50,1389 @@ pub enum Mode {
   Home,
 }
 
+impl Mode {
+  pub const ALL: [Mode; 2] = [Mode::MainMenu, Mode::Home];
+}
+
+impl FromStr for Mode {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "MainMenu" => Ok(Self::MainMenu),
+      "Home" => Ok(Self::Home),
+      _ => Err(format!("Unknown mode: {s}")),
+    }
+  }
+}
+
+/// How long outstanding background tasks get to finish on quit before they are aborted.
+const TASK_SHUTDOWN_GRACE: Duration = Duration::from_millis(250);
+
+/// How often the config files are checked for changes.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every how many ticks the resource usage on the layout debug overlay is sampled.
+const RESOURCE_SAMPLE_TICKS: u32 = 4;
+
 pub struct App {
   pub config: Config,
   pub tick_rate: f64,
   pub frame_rate: f64,
-  pub components: Vec<Box<dyn Component>>,
+  pub components: ComponentRegistry,
   pub should_quit: bool,
   pub should_suspend: bool,
   pub mode: Mode,
-  pub last_tick_key_events: Vec<KeyEvent>,
+  pub pending_keys: ChordBuffer,
+  pub count: CountPrefix,
+  pub idle: IdleTracker,
+  pub frame_limiter: FrameLimiter,
+  pub mode_history: ModeHistory,
+  /// Whether component areas are outlined on top of each frame.
+  pub show_layout_debug: bool,
+  pub storage: StorageHealth,
+  pub focus: FocusRing,
+  /// Background work spawned by components, wound down before the terminal is restored.
+  pub tasks: TaskSet,
+  /// Samples what the app is using, for the layout debug overlay and exported action logs.
+  pub resources: ResourceMonitor,
+  /// Where the actions produced by input are written, if the session is being recorded.
+  pub recorder: Option<Recorder>,
+  /// A recording to play back instead of reading input from the terminal.
+  pub replay: Option<Replay>,
+  /// Where every dispatched action is written, input or not, if the config or environment asks for a trace.
+  pub tracer: Option<Recorder>,
 }
 
 impl App {
-  pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
+  /// Creates the app with the tick and frame rates from the config. They can be overridden afterwards.
+  pub fn new(include_content: bool) -> Result<Self> {
     let mode = Mode::MainMenu;
     let main_menu = MainMenu::new();
     let home = Home::new();
-    let fps = FpsCounter::new();
     let config = Config::new()?;
+    let fps = FpsCounter::new(config.fps.window);
     let help_screen = HelpScreen::new(vec![mode]);
     let mode_switcher = ModeSwitcher::new(mode);
+    let which_key = WhichKey::new(mode);
+    let key_hint_bar = KeyHintBar::new(mode);
+    let resources = ResourceMonitor::new(Arc::new(ProcessMemory), RESOURCE_SAMPLE_TICKS);
+    let action_history = ActionHistory::new().include_content(include_content).resources(resources.shared());
+    let status_bar = StatusBar::new(Duration::from_secs(config.status_bar.error_timeout_secs));
+    let idle = IdleTracker::new(Duration::from_secs(config.idle.timeout_secs), Instant::now());
+    let frame_limiter = FrameLimiter::new(config.max_fps);
+    let mode_history = ModeHistory::new(config.mode_history.depth);
+    let pending_keys = ChordBuffer::new(Duration::from_millis(config.chords.timeout_ms));
+    let tracer = config.action_trace_path().map(|path| Recorder::create(&path)).transpose()?;
 
     Ok(Self {
-      tick_rate,
-      frame_rate,
-      components: vec![Box::new(main_menu), Box::new(help_screen), Box::new(mode_switcher)],
+      tick_rate: config.tick_rate(),
+      frame_rate: config.frame_rate(),
+      components: ComponentRegistry::new(
+        HashMap::from([(Mode::MainMenu, vec![Box::new(main_menu) as _]), (Mode::Home, vec![Box::new(home) as _])]),
+        vec![
+          Box::new(fps),
+          Box::new(help_screen),
+          Box::new(mode_switcher),
+          Box::new(action_history),
+          Box::new(key_hint_bar),
+          Box::new(which_key),
+          Box::new(status_bar),
+          Box::new(QuitPrompt::new()),
+          Box::new(CommandPalette::new()),
+          Box::new(KeybindingEditor::new()),
+        ],
+      ),
       should_quit: false,
       should_suspend: false,
       config,
       mode,
-      last_tick_key_events: Vec::new(),
+      pending_keys,
+      count: CountPrefix::default(),
+      idle,
+      frame_limiter,
+      mode_history,
+      show_layout_debug: false,
+      storage: StorageHealth::current(),
+      focus: FocusRing::default(),
+      tasks: TaskSet::new(),
+      resources,
+      recorder: None,
+      replay: None,
+      tracer,
     })
   }
 
+  fn change_mode(&mut self, mode: Mode) -> Result<()> {
+    if mode != self.mode {
+      self.mode_history.push(self.mode);
+      self.switch_mode(mode)?;
+    }
+    Ok(())
+  }
+
+  /// Returns to the most recent mode in the history, if there is one.
+  fn previous_mode(&mut self) -> Result<()> {
+    if let Some(mode) = self.mode_history.pop() {
+      self.switch_mode(mode)?;
+    }
+    Ok(())
+  }
+
+  fn switch_mode(&mut self, mode: Mode) -> Result<()> {
+    if mode != self.mode {
+      for component in self.components.mode_mut(self.mode) {
+        component.on_exit()?;
+      }
+      for component in self.components.global_mut() {
+        component.on_mode_change(self.mode, mode)?;
+      }
+      for component in self.components.mode_mut(mode) {
+        component.on_enter()?;
+      }
+      self.mode = mode;
+      self.focus = FocusRing::default();
+    }
+    Ok(())
+  }
+
+  /// Re-reads the config from disk and hands it to every component, or reports why it couldn't and keeps the old one.
+  fn reload_config(&mut self, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    match Config::load(&self.config.config._config_dir) {
+      Ok(config) => {
+        self.config = config;
+        self.pending_keys = ChordBuffer::new(Duration::from_millis(self.config.chords.timeout_ms));
+        for component in self.components.all_mut() {
+          component.register_config_handler(self.config.clone())?;
+        }
+        action_tx.send(EngineAction::Notify("Config reloaded".to_string()).into())?;
+      },
+      Err(e) => {
+        log::error!("Failed to reload config: {e}");
+        action_tx.send(EngineAction::Error(format!("Failed to reload config: {e}")).into())?;
+      },
+    }
+    Ok(())
+  }
+
+  /// Hands the config with its new theme to every component and names the theme in the status bar, or reports why
+  /// it couldn't be switched to.
+  fn switched_theme(&mut self, switched: Result<&str, String>, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    match switched {
+      Ok(name) => {
+        for component in self.components.all_mut() {
+          component.register_config_handler(self.config.clone())?;
+        }
+        action_tx.send(EngineAction::Notify(format!("Theme: {name}")).into())?;
+      },
+      Err(e) => action_tx.send(EngineAction::Error(e).into())?,
+    }
+    Ok(())
+  }
+
+  /// Tells the components whether storage is read-only, warning about it once if so.
+  /// The resource usage to show on the layout debug overlay, or `None` while the overlay is hidden.
+  fn layout_debug(&self) -> Option<ResourceUsage> {
+    self.show_layout_debug.then(|| self.resources.usage())
+  }
+
+  fn report_storage(&self, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    action_tx.send(EngineAction::StorageReadOnly(self.storage.is_read_only()).into())?;
+    if let Some(warning) = self.storage.warning() {
+      log::warn!("{warning}");
+      action_tx.send(EngineAction::Error(warning).into())?;
+    }
+    Ok(())
+  }
+
   pub async fn run(&mut self) -> Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
 
-    let mut tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate);
+    let mut tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate).mouse(true);
 
     tui.enter()?;
 
-    for component in self.components.iter_mut() {
+    for component in self.components.all_mut() {
       component.register_action_handler(action_tx.clone())?;
     }
 
-    for component in self.components.iter_mut() {
+    for component in self.components.all_mut() {
       component.register_config_handler(self.config.clone())?;
     }
 
-    for component in self.components.iter_mut() {
+    for component in self.components.all_mut() {
+      component.register_task_handler(self.tasks.clone())?;
+    }
+
+    for component in self.components.all_mut() {
       component.init()?;
     }
+    sync_focus(&mut self.focus, self.components.active_mut(self.mode));
+
+    let unbound_hints: Vec<String> = self
+      .components
+      .all()
+      .flat_map(|component| component.hinted_actions())
+      .filter(|(mode, action)| self.config.binding_for(*mode, action).is_none())
+      .map(|(mode, action)| format!("{action} ({mode:?})"))
+      .collect();
+    if !unbound_hints.is_empty() {
+      log::warn!("UI hints reference actions with no key binding: {}", unbound_hints.join(", "));
+      action_tx.send(
+        EngineAction::Error(format!("UI hints reference unbound actions: {}", unbound_hints.join(", "))).into(),
+      )?;
+    }
+
+    self.report_storage(&action_tx)?;
+    let config_watcher =
+      ConfigWatcher::spawn(self.config.config._config_dir.clone(), CONFIG_POLL_INTERVAL, action_tx.clone());
+
+    // Actions produced by input go through their own channel, so they can be recorded before being handled.
+    let (input_tx, mut input_rx) = mpsc::unbounded_channel();
+    let replaying = match self.replay.take() {
+      Some(replay) => {
+        replay.spawn(input_tx.clone());
+        true
+      },
+      None => false,
+    };
 
     loop {
-      if let Some(e) = tui.next().await {
+      // While replaying, the recording stands in for the terminal's input.
+      if let Some(e) = tui.next().await.filter(|e| !(replaying && is_input_event(e))) {
+        // Checked on every event rather than on ticks, so how long a chord may take doesn't depend on the tick rate.
+        if self.pending_keys.expire(Instant::now()) {
+          input_tx.send(EngineAction::PendingKeys(Vec::new()).into())?;
+          // A count typed ahead of the combination lapses with it.
+          if self.count.take().is_some() {
+            input_tx.send(EngineAction::PendingCount(0).into())?;
+          }
+        }
+        if is_input_event(&e) {
+          if let Some(action) = self.idle.record_activity(Instant::now()) {
+            input_tx.send(action)?;
+          }
+        }
         match e {
-          tui::Event::Quit => action_tx.send(EngineAction::Quit.into())?,
-          tui::Event::Tick => action_tx.send(EngineAction::Tick.into())?,
-          tui::Event::Render => action_tx.send(EngineAction::Render.into())?,
-          tui::Event::Resize(x, y) => action_tx.send(EngineAction::Resize(x, y).into())?,
+          tui::Event::Quit => input_tx.send(EngineAction::Quit.into())?,
+          tui::Event::Tick => {
+            input_tx.send(EngineAction::Tick.into())?;
+            if let Some(action) = self.idle.check(Instant::now()) {
+              input_tx.send(action)?;
+            }
+          },
+          tui::Event::Render => input_tx.send(EngineAction::Render.into())?,
+          tui::Event::Resize(x, y) => input_tx.send(EngineAction::Resize(x, y).into())?,
           tui::Event::Key(key) => {
-            if let Some(keymap) = self.config.keybindings.get(&self.mode) {
-              if let Some(action) = keymap.get(&vec![key]) {
+            let keymap = self.config.keybindings.merged(self.mode);
+            let pending_before = self.pending_keys.keys().to_vec();
+            let count_before = self.count.get();
+            // Digits build up a count for the next navigation or counting key, unless they are bound or being typed as
+            // text.
+            let counted = pending_before.is_empty()
+              && !keymap.contains_key(&vec![key])
+              && !self.components.active(self.mode).any(|c| c.captures_text_input())
+              && self.count.push(key);
+            if !counted {
+              let action =
+                key_action(key, &keymap, &mut self.pending_keys, &self.components, self.mode, Instant::now());
+              if key.code == KeyCode::Esc {
+                // Esc always hands focus back to the primary pane, on top of whatever it is bound to.
+                self.focus.reset();
+              }
+              // Any other key, Esc included, uses up the count, though only navigation, counting and operators do
+              // anything with it.
+              let count = self.count.take_for(action.as_ref(), !self.pending_keys.keys().is_empty());
+              if let Some(action) = action.map(|action| count.map_or(action.clone(), |count| action.with_count(count)))
+              {
                 log::info!("Got action: {action:?}");
-                action_tx.send(action.clone())?;
-              } else {
-                // If the key was not handled as a single key action,
-                // then consider it for multi-key combinations.
-                self.last_tick_key_events.push(key);
-
-                // Check for multi-key combinations
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
-                  log::info!("Got action: {action:?}");
-                  action_tx.send(action.clone())?;
+                if let Some(reason) = inapplicable_reason(self.components.active(self.mode), &action) {
+                  input_tx.send(EngineAction::Notify(reason).into())?;
                 }
+                input_tx.send(action)?;
+              }
+              if self.pending_keys.keys() != pending_before {
+                input_tx.send(EngineAction::PendingKeys(self.pending_keys.keys().to_vec()).into())?;
               }
-            };
+            }
+            if self.count.get() != count_before {
+              input_tx.send(EngineAction::PendingCount(self.count.get().unwrap_or(0)).into())?;
+            }
+          },
+          tui::Event::Mouse(mouse) => {
+            if let Some(action) = self.config.mouse_action(self.mode, &mouse) {
+              log::info!("Got action: {action:?}");
+              input_tx.send(action.clone())?;
+            }
           },
           _ => {},
         }
-        for component in self.components.iter_mut() {
+        for component in self.components.active_mut(self.mode) {
           if let Some(action) = component.handle_events(Some(e.clone()))? {
-            action_tx.send(action)?;
+            input_tx.send(action)?;
           }
         }
       }
 
-      while let Ok(action) = action_rx.try_recv() {
+      // The actions to handle, how deeply each is nested in batches, and whether it came from input. The contents of
+      // batches go to the front, along with whatever the components produce while handling them, so a batch runs as a
+      // whole and in order.
+      let mut batched = take_pending(&mut action_rx, &mut input_rx, &mut self.recorder);
+      while let Some((action, depth, from_input)) =
+        batched.pop_front().or_else(|| action_rx.try_recv().ok().map(|a| (a, 0, false)))
+      {
         if action != EngineAction::Tick.into() && action != EngineAction::Render.into() {
           log::debug!("{action:?}");
         }
+        // The contents of a batch are left out of the trace, since replaying the batch runs them again.
+        if depth == 0 {
+          trace(&mut self.tracer, &action, from_input);
+        }
         if let Action::Engine(engine_action) = &action {
           match engine_action {
-            EngineAction::Tick => {
-              self.last_tick_key_events.drain(..);
+            EngineAction::Batch(_) if depth >= MAX_BATCH_DEPTH => {
+              log::error!("Dropped a batch nested more than {MAX_BATCH_DEPTH} deep: {action}");
+              action_tx
+                .send(EngineAction::Error(format!("Batches can be nested at most {MAX_BATCH_DEPTH} deep")).into())?;
+              continue;
+            },
+            EngineAction::Batch(actions) => {
+              for action in actions.iter().rev() {
+                batched.push_front((action.clone(), depth + 1, from_input));
+              }
+              continue;
+            },
+            EngineAction::ChangeMode(m) => self.change_mode(*m)?,
+            EngineAction::PreviousMode => self.previous_mode()?,
+            EngineAction::Quit => {
+              let mut unsaved = false;
+              for component in self.components.all_mut() {
+                unsaved |= component.prepare_quit()?;
+              }
+              if unsaved {
+                action_tx.send(EngineAction::ConfirmQuit.into())?;
+              } else {
+                self.should_quit = true;
+              }
             },
-            EngineAction::ChangeMode(m) => self.mode = *m,
-            EngineAction::Quit => self.should_quit = true,
+            EngineAction::ForceQuit => self.should_quit = true,
             EngineAction::Suspend => self.should_suspend = true,
             EngineAction::Resume => self.should_suspend = false,
             EngineAction::Resize(w, h) => {
               tui.resize(Rect::new(0, 0, *w, *h))?;
-              tui.draw(|f| {
-                for component in self.components.iter_mut() {
-                  let r = component.draw(f, f.size());
-                  if let Err(e) = r {
-                    action_tx.send(EngineAction::Error(format!("Failed to draw: {:?}", e)).into()).unwrap();
-                  }
-                }
-              })?;
+              let debug = self.layout_debug();
+              draw_components(&mut tui, self.components.active_mut(self.mode), &action_tx, debug.as_ref())?;
             },
+            EngineAction::Render if !self.frame_limiter.should_draw(Instant::now()) => {},
             EngineAction::Render => {
-              tui.draw(|f| {
-                for component in self.components.iter_mut() {
-                  let r = component.draw(f, f.size());
-                  if let Err(e) = r {
-                    action_tx.send(EngineAction::Error(format!("Failed to draw: {:?}", e)).into()).unwrap();
-                  }
+              if self.idle.is_idle() && self.config.idle.blank_screen {
+                tui.draw(|f| {
+                  f.render_widget(Paragraph::new("Idle - press any key").alignment(Alignment::Center), f.size())
+                })?;
+              } else {
+                let debug = self.layout_debug();
+                draw_components(&mut tui, self.components.active_mut(self.mode), &action_tx, debug.as_ref())?;
+              }
+            },
+            // Background work is throttled by restarting the event loop with a lower frame rate while idle, and the
+            // config files aren't polled until input comes back.
+            EngineAction::IdleStart => {
+              tui.frame_rate = self.config.idle.frame_rate.min(self.frame_rate);
+              tui.start();
+              config_watcher.pause();
+            },
+            EngineAction::IdleEnd => {
+              tui.frame_rate = self.frame_rate;
+              tui.start();
+              config_watcher.resume();
+            },
+            EngineAction::Refresh => {
+              // The mount may have become writable (or stopped being so) since the last check.
+              let storage = StorageHealth::current();
+              if storage != self.storage {
+                self.storage = storage;
+                self.report_storage(&action_tx)?;
+              }
+              let debug = self.layout_debug();
+              refresh_components(&mut tui, self.components.active_mut(self.mode), &action_tx, debug.as_ref())?
+            },
+            EngineAction::Tick if self.resources.tick() => {
+              self.resources.refresh_memory(&self.tasks);
+              let queued = action_rx.len() + input_rx.len() + batched.len();
+              let mut buffers: Vec<Occupancy> = self.components.all().flat_map(|c| c.occupancy()).collect();
+              buffers.push(Occupancy::new("mode history", self.mode_history.len(), Some(self.mode_history.depth)));
+              self.resources.sample(self.tasks.len(), queued, buffers);
+            },
+            EngineAction::ToggleLayoutDebug => self.show_layout_debug = !self.show_layout_debug,
+            EngineAction::FocusNext => self.focus.next(),
+            EngineAction::FocusPrevious => self.focus.previous(),
+            EngineAction::SwitchPreset(preset) => {
+              let changed = self.config.apply_preset(*preset);
+              for component in self.components.all_mut() {
+                component.register_config_handler(self.config.clone())?;
+              }
+              action_tx.send(EngineAction::Notify(format!("{preset:?} preset: {changed} bindings changed")).into())?;
+            },
+            EngineAction::ReloadTheme => match self.config.reload_styles() {
+              Ok(()) => {
+                for component in self.components.all_mut() {
+                  component.register_config_handler(self.config.clone())?;
+                }
+              },
+              Err(e) => action_tx.send(EngineAction::Error(format!("Failed to reload theme: {e}")).into())?,
+            },
+            EngineAction::CycleTheme => {
+              let switched = Ok(self.config.cycle_theme());
+              self.switched_theme(switched, &action_tx)?
+            },
+            EngineAction::SetTheme(name) => {
+              let switched = self.config.set_theme(name);
+              self.switched_theme(switched, &action_tx)?
+            },
+            EngineAction::ReloadConfig => self.reload_config(&action_tx)?,
+            EngineAction::SetBinding(section, keys, bound) => {
+              self.config.set_binding(*section, keys.clone(), bound.as_deref().cloned());
+              for component in self.components.all_mut() {
+                component.register_config_handler(self.config.clone())?;
+              }
+            },
+            EngineAction::SaveKeybindings => match self.config.save_keybindings() {
+              Ok(path) => {
+                for component in self.components.all_mut() {
+                  component.register_config_handler(self.config.clone())?;
                 }
-              })?;
+                action_tx.send(EngineAction::Notify(format!("Saved key bindings to {}", path.display())).into())?;
+              },
+              Err(e) => {
+                log::error!("Failed to save key bindings: {e}");
+                action_tx.send(EngineAction::Error(format!("Failed to save key bindings: {e}")).into())?;
+              },
             },
             _ => {},
           }
         }
 
-        for component in self.components.iter_mut() {
-          if let Some(action) = component.update(action.clone())? {
+        let produced = match action.reaches_every_mode() {
+          true => update_components(self.components.all_mut(), action)?,
+          false => update_components(self.components.active_mut(self.mode), action)?,
+        };
+        if depth == 0 {
+          for action in produced {
             action_tx.send(action)?
-          };
+          }
+        } else {
+          for action in produced.into_iter().rev() {
+            batched.push_front((action, depth, false));
+          }
         }
+        sync_focus(&mut self.focus, self.components.active_mut(self.mode));
       }
       if self.should_suspend {
         tui.suspend()?;
         action_tx.send(EngineAction::Resume.into())?;
-        tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate);
+        tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate).mouse(true);
         tui.enter()?;
       } else if self.should_quit {
         tui.stop()?;
         break;
       }
     }
+    if let Err(e) = self.tasks.shutdown(TASK_SHUTDOWN_GRACE).await {
+      log::error!("{e}");
+    }
     tui.exit()?;
     Ok(())
   }
 }
+
+/// The app's components, split into those that belong to a single mode and global overlays shown in every mode.
+#[derive(Default)]
+pub struct ComponentRegistry {
+  by_mode: HashMap<Mode, Vec<Box<dyn Component>>>,
+  global: Vec<Box<dyn Component>>,
+}
+
+impl ComponentRegistry {
+  pub fn new(by_mode: HashMap<Mode, Vec<Box<dyn Component>>>, global: Vec<Box<dyn Component>>) -> Self {
+    Self { by_mode, global }
+  }
+
+  /// Every registered component, regardless of mode.
+  pub fn all(&self) -> impl Iterator<Item = &Box<dyn Component>> {
+    self.by_mode.values().flatten().chain(&self.global)
+  }
+
+  pub fn all_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Component>> {
+    self.by_mode.values_mut().flatten().chain(&mut self.global)
+  }
+
+  /// The components belonging to `mode`, without the global ones.
+  pub fn mode_mut(&mut self, mode: Mode) -> impl Iterator<Item = &mut Box<dyn Component>> {
+    self.by_mode.get_mut(&mode).into_iter().flatten()
+  }
+
+  pub fn active(&self, mode: Mode) -> impl Iterator<Item = &Box<dyn Component>> {
+    self.by_mode.get(&mode).into_iter().flatten().chain(&self.global)
+  }
+
+  pub fn global_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Component>> {
+    self.global.iter_mut()
+  }
+
+  /// The components that are live in `mode`: its own, followed by the global ones so overlays draw on top.
+  pub fn active_mut(&mut self, mode: Mode) -> impl Iterator<Item = &mut Box<dyn Component>> {
+    self.by_mode.get_mut(&mode).into_iter().flatten().chain(&mut self.global)
+  }
+}
+
+/// The modes the app was in before the current one, most recent last.
+#[derive(Debug, Clone, Default)]
+pub struct ModeHistory {
+  stack: Vec<Mode>,
+  depth: usize,
+}
+
+impl ModeHistory {
+  pub fn new(depth: usize) -> Self {
+    Self { stack: Vec::with_capacity(depth), depth }
+  }
+
+  /// Records `mode` as the one being left, forgetting the oldest entry once the history is full.
+  pub fn push(&mut self, mode: Mode) {
+    if self.depth == 0 || self.stack.last() == Some(&mode) {
+      return;
+    }
+    if self.stack.len() == self.depth {
+      self.stack.remove(0);
+    }
+    self.stack.push(mode);
+  }
+
+  pub fn pop(&mut self) -> Option<Mode> {
+    self.stack.pop()
+  }
+
+  pub fn len(&self) -> usize {
+    self.stack.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.stack.is_empty()
+  }
+}
+
+/// A number typed ahead of a navigation key, like vim's `3j`, to move that many steps at once, ahead of Home's
+/// increment and decrement keys to count by that much, or ahead of an operator like `3dd` to apply it to that many
+/// todos.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CountPrefix(Option<usize>);
+
+impl CountPrefix {
+  /// Adds `key` to the count if it is a digit, returning whether it was. A count can't start with 0.
+  pub fn push(&mut self, key: KeyEvent) -> bool {
+    let KeyCode::Char(c) = key.code else {
+      return false;
+    };
+    if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+      return false;
+    }
+    match (c.to_digit(10), self.0) {
+      (None, _) | (Some(0), None) => false,
+      (Some(digit), count) => {
+        self.0 = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+        true
+      },
+    }
+  }
+
+  pub fn get(&self) -> Option<usize> {
+    self.0
+  }
+
+  /// Returns the count, leaving none behind.
+  pub fn take(&mut self) -> Option<usize> {
+    self.0.take()
+  }
+
+  /// Returns the count for the `action` a key produced, leaving none behind. A key partway through a combination,
+  /// with `chord_pending` set and no action yet, keeps the count for the action the combination ends in.
+  pub fn take_for(&mut self, action: Option<&Action>, chord_pending: bool) -> Option<usize> {
+    match action.is_none() && chord_pending {
+      true => None,
+      false => self.take(),
+    }
+  }
+}
+
+/// Keys pressed so far towards a multi-key binding.
+///
+/// A partial sequence is forgotten once more than the timeout passes between two presses, so a stale prefix can't
+/// combine with an unrelated later key.
+#[derive(Debug, Clone, Default)]
+pub struct ChordBuffer {
+  keys: Vec<KeyEvent>,
+  last_press: Option<Instant>,
+  timeout: Duration,
+}
+
+impl ChordBuffer {
+  pub fn new(timeout: Duration) -> Self {
+    Self { keys: Vec::new(), last_press: None, timeout }
+  }
+
+  /// Adds `key`, first dropping the pending keys if the previous one was pressed more than the timeout before `now`.
+  pub fn push(&mut self, key: KeyEvent, now: Instant) -> &[KeyEvent] {
+    self.expire(now);
+    self.last_press = Some(now);
+    self.keys.push(key);
+    &self.keys
+  }
+
+  /// Drops the pending keys if the last one was pressed more than the timeout before `now`, returning whether there
+  /// were any to drop.
+  pub fn expire(&mut self, now: Instant) -> bool {
+    let expired =
+      !self.keys.is_empty() && self.last_press.is_some_and(|last| now.saturating_duration_since(last) > self.timeout);
+    if expired {
+      self.keys.clear();
+    }
+    expired
+  }
+
+  pub fn clear(&mut self) {
+    self.keys.clear();
+  }
+
+  pub fn keys(&self) -> &[KeyEvent] {
+    &self.keys
+  }
+}
+
+/// Caps how often frames are drawn, independently of the rate at which renders are requested.
+#[derive(Debug, Clone, Default)]
+pub struct FrameLimiter {
+  min_interval: Option<Duration>,
+  last_draw: Option<Instant>,
+}
+
+impl FrameLimiter {
+  pub fn new(max_fps: Option<f64>) -> Self {
+    let min_interval = max_fps.filter(|fps| *fps > 0.0).map(|fps| Duration::from_secs_f64(1.0 / fps));
+    Self { min_interval, last_draw: None }
+  }
+
+  /// Returns whether a frame may be drawn at `now`, recording it as drawn if so.
+  pub fn should_draw(&mut self, now: Instant) -> bool {
+    let allowed = match (self.min_interval, self.last_draw) {
+      (Some(min_interval), Some(last_draw)) => now.saturating_duration_since(last_draw) >= min_interval,
+      _ => true,
+    };
+    if allowed {
+      self.last_draw = Some(now);
+    }
+    allowed
+  }
+}
+
+/// Looks up the action bound to `key`, on its own or as the end of a multi-key combination.
+///
+/// Keys are left to the components alone while one of them is taking text input or every key at all. So is Esc while
+/// an overlay holds focus, so it closes the overlay rather than triggering its binding underneath.
+fn key_action(
+  key: KeyEvent,
+  keymap: &HashMap<Vec<KeyEvent>, Action>,
+  pending_keys: &mut ChordBuffer,
+  components: &ComponentRegistry,
+  mode: Mode,
+  now: Instant,
+) -> Option<Action> {
+  if components.active(mode).any(|c| c.captures_all_keys()) {
+    return None;
+  }
+  if is_text_key(&key) && components.active(mode).any(|c| c.captures_text_input()) {
+    return None;
+  }
+  if key.code == KeyCode::Esc && components.active(mode).any(|c| c.has_focus()) {
+    return None;
+  }
+  if let Some(action) = keymap.get(&vec![key]) {
+    pending_keys.clear();
+    return Some(action.clone());
+  }
+  // If the key was not handled as a single key action, then consider it for multi-key combinations.
+  if let Some(action) = keymap.get(pending_keys.push(key, now)).cloned() {
+    pending_keys.clear();
+    return Some(action);
+  }
+  if !is_chord_prefix(keymap, pending_keys.keys()) {
+    // The pending keys can't lead anywhere any more, but this key may still start a new combination.
+    pending_keys.clear();
+    if is_chord_prefix(keymap, &[key]) {
+      pending_keys.push(key, now);
+    }
+  }
+  None
+}
+
+/// Whether `keys` are the start of some longer binding in `keymap`.
+fn is_chord_prefix(keymap: &HashMap<Vec<KeyEvent>, Action>, keys: &[KeyEvent]) -> bool {
+  keymap.keys().any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+}
+
+/// Whether a key could be part of typed text, i.e. it is pressed without Ctrl or Alt. Tab is kept for moving focus.
+fn is_text_key(key: &KeyEvent) -> bool {
+  !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    && !matches!(key.code, KeyCode::Tab | KeyCode::BackTab)
+}
+
+/// Whether `event` comes from the user rather than the passage of time.
+fn is_input_event(event: &tui::Event) -> bool {
+  matches!(event, tui::Event::Key(_) | tui::Event::Mouse(_) | tui::Event::Paste(_) | tui::Event::FocusGained)
+}
+
+/// Tracks which of the visible focusable components has keyboard focus.
+///
+/// Components are identified by their position among the active components of the current mode, and cycled through
+/// in the order given by [`Component::focus_order`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusRing {
+  cycle: Vec<usize>,
+  focused: Option<usize>,
+}
+
+impl FocusRing {
+  /// Rebuilds the cycle from each component's focus order.
+  ///
+  /// A component that has just become visible (e.g. an overlay that opened) takes focus, and focus falls back to the
+  /// primary pane when the focused component disappears.
+  pub fn refresh(&mut self, orders: impl IntoIterator<Item = Option<u8>>) {
+    let mut ordered: Vec<(u8, usize)> =
+      orders.into_iter().enumerate().filter_map(|(i, order)| order.map(|order| (order, i))).collect();
+    ordered.sort();
+    let cycle: Vec<usize> = ordered.into_iter().map(|(_, i)| i).collect();
+
+    let opened = cycle.iter().rev().find(|i| !self.cycle.contains(i)).copied();
+    self.focused = match (opened, self.focused) {
+      (Some(opened), _) if !self.cycle.is_empty() => Some(opened),
+      (_, Some(focused)) if cycle.contains(&focused) => Some(focused),
+      _ => cycle.first().copied(),
+    };
+    self.cycle = cycle;
+  }
+
+  pub fn next(&mut self) {
+    self.step(1);
+  }
+
+  pub fn previous(&mut self) {
+    self.step(-1);
+  }
+
+  fn step(&mut self, offset: isize) {
+    if self.cycle.is_empty() {
+      return;
+    }
+    let position = self.focused.and_then(|focused| self.cycle.iter().position(|i| *i == focused)).unwrap_or(0);
+    let next = (position as isize + offset).rem_euclid(self.cycle.len() as isize) as usize;
+    self.focused = Some(self.cycle[next]);
+  }
+
+  /// Gives focus back to the primary pane, the first in the cycle.
+  pub fn reset(&mut self) {
+    self.focused = self.cycle.first().copied();
+  }
+
+  pub fn focused(&self) -> Option<usize> {
+    self.focused
+  }
+
+  /// The components that can currently take focus, in cycle order.
+  pub fn cycle(&self) -> &[usize] {
+    &self.cycle
+  }
+}
+
+/// Refreshes `focus` from the active components and tells each of them whether it now has focus.
+fn sync_focus<'a>(focus: &mut FocusRing, components: impl IntoIterator<Item = &'a mut Box<dyn Component>>) {
+  let mut components: Vec<_> = components.into_iter().collect();
+  focus.refresh(components.iter().map(|c| c.focus_order()));
+  for (i, component) in components.iter_mut().enumerate() {
+    component.set_focused(focus.focused() == Some(i));
+  }
+}
+
+/// Takes everything waiting to be handled: first whatever components, background tasks and the app itself sent since
+/// the last pass, as it got there before the input did, then the actions produced by input, which are written to
+/// `recorder` if a session is being recorded. None of them is in a batch yet, and only the input is marked as such.
+fn take_pending(
+  action_rx: &mut UnboundedReceiver<Action>,
+  input_rx: &mut UnboundedReceiver<Action>,
+  recorder: &mut Option<Recorder>,
+) -> VecDeque<(Action, usize, bool)> {
+  let mut pending: VecDeque<_> = std::iter::from_fn(|| action_rx.try_recv().ok()).map(|a| (a, 0, false)).collect();
+  while let Ok(action) = input_rx.try_recv() {
+    if let Some(Err(e)) = recorder.as_mut().map(|recorder| recorder.record(&action, Instant::now())) {
+      log::error!("Stopped recording: {e}");
+      *recorder = None;
+    }
+    pending.push_back((action, 0, true));
+  }
+  pending
+}
+
+/// Writes `action` to the trace, if one is being written, marked as derived unless it came from input. Tracing stops
+/// if the file can't be written to.
+fn trace(tracer: &mut Option<Recorder>, action: &Action, from_input: bool) {
+  let written = tracer.as_mut().map(|tracer| match from_input {
+    true => tracer.record(action, Instant::now()),
+    false => tracer.record_derived(action, Instant::now()),
+  });
+  if let Some(Err(e)) = written {
+    log::error!("Stopped tracing actions: {e}");
+    *tracer = None;
+  }
+}
+
+/// Passes an action to the components that should see it, returning any follow-up actions they produce.
+///
+/// Navigation actions are only given to focused components when one has focus, so an open overlay doesn't move the
+/// selection of whatever is underneath it.
+fn update_components<'a>(
+  components: impl IntoIterator<Item = &'a mut Box<dyn Component>>,
+  action: Action,
+) -> Result<Vec<Action>> {
+  let components: Vec<_> = components.into_iter().collect();
+  let focus_only = action.is_navigation() && components.iter().any(|c| c.has_focus());
+  let mut produced = Vec::new();
+  for component in components.into_iter().filter(|c| !focus_only || c.has_focus()) {
+    if let Some(action) = component.update(action.clone())? {
+      produced.push(action);
+    }
+  }
+  Ok(produced)
+}
+
+/// Why `action` would do nothing, if no component that it reaches can handle it and at least one says why not.
+///
+/// Components are picked the same way as in [`update_components`].
+fn inapplicable_reason<'a>(
+  components: impl IntoIterator<Item = &'a Box<dyn Component>>,
+  action: &Action,
+) -> Option<String> {
+  let components: Vec<_> = components.into_iter().collect();
+  let focus_only = action.is_navigation() && components.iter().any(|c| c.has_focus());
+  let mut reason = None;
+  for component in components.into_iter().filter(|c| !focus_only || c.has_focus()) {
+    match component.can_handle(action) {
+      Applicability::Handled => return None,
+      Applicability::Ignored => {},
+      Applicability::NotApplicable(why) => reason = reason.or(Some(why)),
+    }
+  }
+  reason
+}
+
+/// Draws every component into a single frame, reporting draw failures as [`EngineAction::Error`].
+///
+/// With `layout_debug` set, the frame and the areas each component reports through [`Component::layout_areas`] are
+/// outlined on top, along with the resource usage it holds.
+fn draw_components<'a, B: Backend>(
+  terminal: &mut Terminal<B>,
+  components: impl IntoIterator<Item = &'a mut Box<dyn Component>>,
+  action_tx: &UnboundedSender<Action>,
+  layout_debug: Option<&ResourceUsage>,
+) -> Result<()> {
+  terminal.draw(|f| {
+    let mut outlines = vec![Outline::new("frame", f.size())];
+    for component in components {
+      let r = component.draw(f, f.size());
+      if let Err(e) = r {
+        action_tx.send(EngineAction::Error(format!("Failed to draw: {:?}", e)).into()).unwrap();
+      }
+      outlines.extend(component.layout_areas().into_iter().map(|(name, area)| Outline::new(name, area)));
+    }
+    if let Some(usage) = layout_debug {
+      f.render_widget(LayoutDebug(&outlines), f.size());
+      f.render_widget(ResourcePanel(usage), f.size());
+    }
+  })?;
+  Ok(())
+}
+
+/// Clears the terminal and redraws every cell from scratch.
+///
+/// The regular draw path only flushes cells that differ from the previous frame, so anything written to the terminal
+/// behind ratatui's back (e.g. a stray `print!` from a dependency) stays on screen until those cells change again.
+fn refresh_components<'a, B: Backend>(
+  terminal: &mut Terminal<B>,
+  components: impl IntoIterator<Item = &'a mut Box<dyn Component>>,
+  action_tx: &UnboundedSender<Action>,
+  layout_debug: Option<&ResourceUsage>,
+) -> Result<()> {
+  terminal.clear()?;
+  draw_components(terminal, components, action_tx, layout_debug)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use ratatui::{backend::TestBackend, buffer::Cell, widgets::Paragraph};
+
+  use super::*;
+  use crate::{
+    actions::{HomeAction, ListNavDirection},
+    tui::Frame,
+  };
+
+  struct Greeter;
+
+  impl Component for Greeter {
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+      f.render_widget(Paragraph::new("hello"), rect);
+      Ok(())
+    }
+  }
+
+  /// Counts the actions it receives.
+  #[derive(Default)]
+  struct Recorder {
+    focused: bool,
+    seen: std::rc::Rc<std::cell::Cell<usize>>,
+  }
+
+  impl Component for Recorder {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+      self.seen.set(self.seen.get() + 1);
+      Ok(None)
+    }
+
+    fn has_focus(&self) -> bool {
+      self.focused
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_navigation_only_reaches_focused_components() -> Result<()> {
+    let overlay = Recorder { focused: true, ..Default::default() };
+    let underneath = Recorder::default();
+    let (overlay_seen, underneath_seen) = (overlay.seen.clone(), underneath.seen.clone());
+    let mut components: Vec<Box<dyn Component>> = vec![Box::new(underneath), Box::new(overlay)];
+
+    update_components(&mut components, HomeAction::NavigateList(ListNavDirection::Up, 1).into())?;
+    assert_eq!((overlay_seen.get(), underneath_seen.get()), (1, 0));
+
+    update_components(&mut components, EngineAction::Tick.into())?;
+    assert_eq!((overlay_seen.get(), underneath_seen.get()), (2, 1));
+    Ok(())
+  }
+
+  #[test]
+  fn test_registry_only_yields_active_mode_and_globals() -> Result<()> {
+    let (main_menu, home, overlay) = (Recorder::default(), Recorder::default(), Recorder::default());
+    let (main_menu_seen, home_seen, overlay_seen) = (main_menu.seen.clone(), home.seen.clone(), overlay.seen.clone());
+    let mut registry = ComponentRegistry::new(
+      HashMap::from([(Mode::MainMenu, vec![Box::new(main_menu) as _]), (Mode::Home, vec![Box::new(home) as _])]),
+      vec![Box::new(overlay)],
+    );
+
+    update_components(registry.active_mut(Mode::Home), EngineAction::Refresh.into())?;
+    assert_eq!((main_menu_seen.get(), home_seen.get(), overlay_seen.get()), (0, 1, 1));
+    assert_eq!(registry.all().count(), 3);
+    Ok(())
+  }
+
+  #[test]
+  fn test_esc_in_home_normal_mode_returns_to_main_menu() -> Result<()> {
+    use crate::{components::help_screen::HelpScreen, config::parse_key_sequence};
+
+    let keymap =
+      HashMap::from([(parse_key_sequence("<esc>").unwrap(), EngineAction::ChangeMode(Mode::MainMenu).into())]);
+    let mut components = ComponentRegistry::new(
+      HashMap::from([(Mode::Home, vec![Box::new(Home::new()) as Box<dyn Component>])]),
+      vec![Box::new(HelpScreen::new(vec![Mode::Home]))],
+    );
+    let mut pending_keys = ChordBuffer::new(Duration::from_millis(500));
+    let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+    let mut press = |components: &ComponentRegistry| {
+      key_action(esc, &keymap, &mut pending_keys, components, Mode::Home, Instant::now())
+    };
+
+    assert_eq!(press(&components), Some(EngineAction::ChangeMode(Mode::MainMenu).into()));
+
+    // Esc cancels input instead while typing, and closes the help popup while it is open.
+    update_components(components.active_mut(Mode::Home), HomeAction::EnterInsert.into())?;
+    assert_eq!(press(&components), None);
+    update_components(components.active_mut(Mode::Home), HomeAction::EnterNormal.into())?;
+    update_components(components.active_mut(Mode::Home), EngineAction::ToggleShowHelp.into())?;
+    assert_eq!(press(&components), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_no_binding_fires_while_a_component_takes_every_key() -> Result<()> {
+    use crate::{components::keybinding_editor::KeybindingEditor, config::parse_key_sequence};
+
+    let ctrl_c = parse_key_sequence("<ctrl-c>").unwrap();
+    let keymap = HashMap::from([(ctrl_c.clone(), EngineAction::Quit.into())]);
+    let mut components = ComponentRegistry::new(HashMap::new(), vec![Box::new(KeybindingEditor::new())]);
+    let mut pending_keys = ChordBuffer::new(Duration::from_millis(500));
+    let mut press = |components: &ComponentRegistry| {
+      key_action(ctrl_c[0], &keymap, &mut pending_keys, components, Mode::Home, Instant::now())
+    };
+
+    assert_eq!(press(&components), Some(EngineAction::Quit.into()));
+    update_components(components.active_mut(Mode::Home), EngineAction::ToggleKeybindingEditor.into())?;
+    assert_eq!(press(&components), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_feedback_for_actions_nothing_can_handle() -> Result<()> {
+    let mut components = ComponentRegistry::new(
+      HashMap::from([(Mode::Home, vec![Box::new(Home::new()) as Box<dyn Component>])]),
+      vec![Box::new(ModeSwitcher::new(Mode::Home)), Box::new(ActionHistory::new())],
+    );
+    let down: Action = HomeAction::NavigateList(ListNavDirection::Down, 1).into();
+
+    assert_eq!(inapplicable_reason(components.active(Mode::Home), &down), Some("No todos to select".to_string()));
+    assert_eq!(
+      inapplicable_reason(components.active(Mode::Home), &HomeAction::DeleteTodo.into()),
+      Some("No todo to delete".to_string())
+    );
+    assert_eq!(
+      inapplicable_reason(components.active(Mode::Home), &HomeAction::Undo.into()),
+      Some("Nothing to undo".to_string())
+    );
+
+    // An open overlay takes the navigation, so there's nothing to report.
+    update_components(components.active_mut(Mode::Home), EngineAction::ToggleShowModeSwitcher.into())?;
+    assert_eq!(inapplicable_reason(components.active(Mode::Home), &down), None);
+    update_components(components.active_mut(Mode::Home), EngineAction::ToggleShowModeSwitcher.into())?;
+
+    update_components(components.active_mut(Mode::Home), HomeAction::CompleteInput("milk".to_string()).into())?;
+    assert_eq!(inapplicable_reason(components.active(Mode::Home), &down), None);
+    assert_eq!(inapplicable_reason(components.active(Mode::Home), &EngineAction::Refresh.into()), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_mode_history_caps_depth_and_skips_duplicates() {
+    let mut history = ModeHistory::new(2);
+    history.push(Mode::MainMenu);
+    history.push(Mode::MainMenu);
+    assert_eq!(history.len(), 1);
+
+    history.push(Mode::Home);
+    history.push(Mode::MainMenu);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.pop(), Some(Mode::MainMenu));
+    assert_eq!(history.pop(), Some(Mode::Home));
+    assert_eq!(history.pop(), None);
+  }
+
+  #[test]
+  fn test_focus_cycles_visible_panes_and_overlays() -> Result<()> {
+    use ratatui::style::Color;
+
+    use crate::components::help_screen::HelpScreen;
+
+    let mut components = ComponentRegistry::new(
+      HashMap::from([(Mode::Home, vec![Box::new(Home::new()) as Box<dyn Component>])]),
+      vec![
+        Box::new(HelpScreen::new(vec![Mode::Home])),
+        Box::new(ActionHistory::new()),
+        Box::new(ModeSwitcher::new(Mode::Home)),
+      ],
+    );
+    let mut focus = FocusRing::default();
+    let act = |components: &mut ComponentRegistry, focus: &mut FocusRing, action: EngineAction| -> Result<()> {
+      update_components(components.active_mut(Mode::Home), action.into())?;
+      sync_focus(focus, components.active_mut(Mode::Home));
+      Ok(())
+    };
+
+    sync_focus(&mut focus, components.active_mut(Mode::Home));
+    assert_eq!((focus.cycle(), focus.focused()), (&[0][..], Some(0)));
+
+    // Overlays join the cycle in their declared order and take focus as they open.
+    act(&mut components, &mut focus, EngineAction::ToggleShowActionHistory)?;
+    act(&mut components, &mut focus, EngineAction::ToggleShowHelp)?;
+    assert_eq!((focus.cycle(), focus.focused()), (&[0, 2, 1][..], Some(1)));
+
+    focus.next();
+    assert_eq!(focus.focused(), Some(0));
+    focus.previous();
+    focus.previous();
+    assert_eq!(focus.focused(), Some(2));
+
+    // Closing an unfocused overlay leaves focus alone; closing the focused one returns it to the primary pane.
+    act(&mut components, &mut focus, EngineAction::ToggleShowHelp)?;
+    assert_eq!((focus.cycle(), focus.focused()), (&[0, 2][..], Some(2)));
+
+    let history_border = |components: &mut ComponentRegistry| -> Result<Option<Color>> {
+      let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+      let (tx, _rx) = mpsc::unbounded_channel();
+      draw_components(&mut terminal, components.active_mut(Mode::Home), &tx, None)?;
+      Ok(terminal.backend().buffer().content().iter().find(|c| c.symbol == "┌").map(|c| c.fg))
+    };
+    assert_eq!(history_border(&mut components)?, Some(Color::LightGreen));
+
+    act(&mut components, &mut focus, EngineAction::ToggleShowActionHistory)?;
+    assert_eq!((focus.cycle(), focus.focused()), (&[0][..], Some(0)));
+
+    act(&mut components, &mut focus, EngineAction::ToggleShowActionHistory)?;
+    focus.reset();
+    sync_focus(&mut focus, components.active_mut(Mode::Home));
+    assert_eq!(history_border(&mut components)?, Some(Color::Magenta));
+    Ok(())
+  }
+
+  #[test]
+  fn test_count_prefix_collects_digits() {
+    let key = |c| KeyEvent::from(KeyCode::Char(c));
+    let mut count = CountPrefix::default();
+    assert!(!count.push(key('0')));
+    assert!(count.push(key('1')));
+    assert!(count.push(key('0')));
+    assert!(!count.push(key('j')));
+    assert!(!count.push(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::CONTROL)));
+    assert_eq!(count.take(), Some(10));
+    assert_eq!(count.get(), None);
+
+    for _ in 0..30 {
+      count.push(key('9'));
+    }
+    assert_eq!(count.get(), Some(usize::MAX));
+  }
+
+  #[test]
+  fn test_chord_buffer_drops_keys_after_timeout() {
+    let start = Instant::now();
+    let key = |c| KeyEvent::from(crossterm::event::KeyCode::Char(c));
+    let mut chord = ChordBuffer::new(Duration::from_millis(500));
+
+    chord.push(key('g'), start);
+    assert_eq!(chord.push(key('g'), start + Duration::from_millis(400)), &[key('g'), key('g')]);
+
+    chord.clear();
+    chord.push(key('g'), start + Duration::from_millis(1000));
+    // Waiting past the timeout means the stale `g` doesn't combine with the next key.
+    assert_eq!(chord.push(key('x'), start + Duration::from_millis(1600)), &[key('x')]);
+    assert_eq!(chord.keys(), &[key('x')]);
+  }
+
+  #[test]
+  fn test_count_carries_over_to_the_end_of_a_combination() {
+    use crate::config::parse_key_sequence;
+
+    let keymap = HashMap::from([(parse_key_sequence("<d><d>").unwrap(), HomeAction::DeleteTodos(1).into())]);
+    let components = ComponentRegistry::default();
+    let mut pending_keys = ChordBuffer::new(Duration::from_secs(1));
+    let mut count = CountPrefix::default();
+    let mut press = |c| {
+      let key = KeyEvent::from(KeyCode::Char(c));
+      if count.push(key) {
+        return None;
+      }
+      let action = key_action(key, &keymap, &mut pending_keys, &components, Mode::Home, Instant::now());
+      let n = count.take_for(action.as_ref(), !pending_keys.keys().is_empty());
+      Some(action.map(|action| n.map_or(action.clone(), |n| action.with_count(n))))
+    };
+
+    assert_eq!(press('3'), None);
+    assert_eq!(press('d'), Some(None));
+    assert_eq!(press('d'), Some(Some(HomeAction::DeleteTodos(3).into())));
+    assert_eq!(press('d'), Some(None));
+    assert_eq!(press('d'), Some(Some(HomeAction::DeleteTodos(1).into())), "the count was used up");
+  }
+
+  #[test]
+  fn test_chord_timing_is_independent_of_ticks() {
+    use crate::config::{parse_key_sequence, ChordConfig};
+
+    let keymap = HashMap::from([(parse_key_sequence("<g><g>").unwrap(), EngineAction::Refresh.into())]);
+    let components = ComponentRegistry::default();
+    let mut pending_keys = ChordBuffer::new(Duration::from_millis(ChordConfig::default().timeout_ms));
+    let g = KeyEvent::from(KeyCode::Char('g'));
+    let start = Instant::now();
+    let mut press_at =
+      |ms| key_action(g, &keymap, &mut pending_keys, &components, Mode::Home, start + Duration::from_millis(ms));
+
+    // Fast enough: the second g lands inside the default 750ms window, however many ticks went by.
+    assert_eq!(press_at(0), None);
+    assert_eq!(press_at(700), Some(EngineAction::Refresh.into()));
+
+    // Too slow: the first g has expired, so the late one starts a fresh chord that the next g completes.
+    assert_eq!(press_at(1000), None);
+    assert_eq!(press_at(1800), None);
+    assert_eq!(press_at(2000), Some(EngineAction::Refresh.into()));
+
+    assert_eq!(press_at(3000), None);
+    assert!(!pending_keys.expire(start + Duration::from_millis(3500)));
+    assert!(pending_keys.expire(start + Duration::from_millis(3800)));
+    assert!(pending_keys.keys().is_empty());
+  }
+
+  #[test]
+  fn test_leader_starts_a_chord() {
+    use crate::config::{parse_key_sequence, KeyBindings};
+
+    let mut keybindings: KeyBindings = json5::from_str(r#"{ "Home": { "<leader> f": "Engine.Refresh" } }"#).unwrap();
+    let leader = parse_key_sequence("ctrl-space").unwrap();
+    keybindings.expand_leader(&leader);
+    let keymap = keybindings.merged(Mode::Home);
+    let components = ComponentRegistry::default();
+    let mut pending_keys = ChordBuffer::new(Duration::from_millis(500));
+    let start = Instant::now();
+    let mut press_at = |keys: &str, ms| {
+      let key = parse_key_sequence(keys).unwrap()[0];
+      key_action(key, &keymap, &mut pending_keys, &components, Mode::Home, start + Duration::from_millis(ms))
+    };
+
+    assert_eq!(press_at("ctrl-space", 0), None);
+    assert_eq!(press_at("f", 400), Some(EngineAction::Refresh.into()));
+    // Waiting past the chord timeout after the leader drops it, like any other pending keys.
+    assert_eq!(press_at("ctrl-space", 1000), None);
+    assert_eq!(press_at("f", 1600), None);
+    assert_eq!(press_at("ctrl-space", 2000), None);
+    assert_eq!(pending_keys.keys(), leader.as_slice());
+  }
+
+  #[test]
+  fn test_chords_reset_after_match_and_dead_ends() {
+    use crate::config::parse_key_sequence;
+
+    let keymap = HashMap::from([
+      (parse_key_sequence("<g><g>").unwrap(), EngineAction::Refresh.into()),
+      (parse_key_sequence("<j>").unwrap(), EngineAction::FocusNext.into()),
+    ]);
+    let components = ComponentRegistry::default();
+    let mut pending_keys = ChordBuffer::new(Duration::from_millis(500));
+    let mut press = |keys: &str| -> Vec<Option<Action>> {
+      parse_key_sequence(keys)
+        .unwrap()
+        .into_iter()
+        .map(|key| key_action(key, &keymap, &mut pending_keys, &components, Mode::Home, Instant::now()))
+        .collect()
+    };
+    let refresh = Some(EngineAction::Refresh.into());
+
+    assert_eq!(press("<g><g>"), vec![None, refresh.clone()]);
+    // The matched pair doesn't linger, so a third `g` starts over instead of misfiring.
+    assert_eq!(press("<g><g><g>"), vec![None, refresh.clone(), None]);
+    assert_eq!(press("<g>"), vec![refresh.clone()]);
+    // An unbound key in between abandons the chord, and the next `g` starts a new one.
+    assert_eq!(press("<g><x><g><g>"), vec![None, None, None, refresh.clone()]);
+    assert_eq!(press("<x><g><g>"), vec![None, None, refresh]);
+  }
+
+  #[test]
+  fn test_frame_limiter_skips_draws_above_cap() {
+    let start = Instant::now();
+    let mut limiter = FrameLimiter::new(Some(10.0));
+
+    let drawn: Vec<u64> =
+      (0..=300).step_by(25).filter(|ms| limiter.should_draw(start + Duration::from_millis(*ms))).collect();
+
+    assert_eq!(drawn, vec![0, 100, 200, 300]);
+  }
+
+  #[test]
+  fn test_frame_limiter_without_cap_always_draws() {
+    let start = Instant::now();
+    let mut limiter = FrameLimiter::new(None);
+
+    assert!((0..10).all(|_| limiter.should_draw(start)));
+  }
+
+  #[test]
+  fn test_refresh_restores_dirtied_cells() -> Result<()> {
+    let (action_tx, _action_rx) = mpsc::unbounded_channel();
+    let mut components: Vec<Box<dyn Component>> = vec![Box::new(Greeter)];
+    let mut terminal = Terminal::new(TestBackend::new(10, 2))?;
+
+    draw_components(&mut terminal, &mut components, &action_tx, None)?;
+    let expected = terminal.backend().buffer().clone();
+
+    // Scribble over the backend without going through the terminal, like an external write would.
+    let garbage = Cell::default().set_symbol("#").clone();
+    let dirty: Vec<(u16, u16)> = (0..10).flat_map(|x| (0..2).map(move |y| (x, y))).collect();
+    terminal.backend_mut().draw(dirty.iter().map(|(x, y)| (*x, *y, &garbage)))?;
+
+    // A plain draw sees no changes and leaves the garbage in place.
+    draw_components(&mut terminal, &mut components, &action_tx, None)?;
+    assert_eq!(terminal.backend().buffer().get(9, 1).symbol, "#");
+
+    refresh_components(&mut terminal, &mut components, &action_tx, None)?;
+    assert_eq!(terminal.backend().buffer(), &expected);
+    Ok(())
+  }
+
+  /// Hands everything queued to `home` the way the event loop does, tracing it on the way, until nothing is left and
+  /// its background work has finished.
+  async fn handle_until_settled(
+    home: &mut Home,
+    tasks: &TaskSet,
+    channels: &mut (UnboundedSender<Action>, UnboundedReceiver<Action>, UnboundedReceiver<Action>),
+    tracer: &mut Option<crate::recording::Recorder>,
+  ) -> Result<()> {
+    let (action_tx, action_rx, input_rx) = channels;
+    loop {
+      tasks.shutdown(Duration::from_secs(1)).await?;
+      let pending = take_pending(action_rx, input_rx, &mut None);
+      if pending.is_empty() {
+        return Ok(());
+      }
+      for (action, _, from_input) in pending {
+        trace(tracer, &action, from_input);
+        if let Some(produced) = home.update(action)? {
+          action_tx.send(produced)?;
+        }
+      }
+    }
+  }
+
+  #[tokio::test]
+  async fn test_replaying_a_trace_runs_derived_actions_once() -> Result<()> {
+    use crate::recording::{Recorder, Replay};
+
+    let path = std::env::temp_dir().join(format!("{}-trace-{}.jsonl", env!("CARGO_PKG_NAME"), std::process::id()));
+    let session = |tasks: &TaskSet| -> Result<_> {
+      let (action_tx, action_rx) = mpsc::unbounded_channel();
+      let (input_tx, input_rx) = mpsc::unbounded_channel();
+      let mut home = Home::new();
+      home.register_action_handler(action_tx.clone())?;
+      home.register_task_handler(tasks.clone())?;
+      Ok((home, input_tx, (action_tx, action_rx, input_rx)))
+    };
+
+    let tasks = TaskSet::new();
+    let (mut home, input_tx, mut channels) = session(&tasks)?;
+    let mut tracer = Some(Recorder::create(&path)?);
+    input_tx.send(HomeAction::ScheduleIncrement(1).into())?;
+    handle_until_settled(&mut home, &tasks, &mut channels, &mut tracer).await?;
+    assert_eq!(home.counter, 1);
+    drop(tracer);
+
+    let replay = Replay::load(&path, true)?;
+    let derived: Vec<_> = replay.actions.iter().filter(|entry| entry.derived).map(|entry| &entry.action).collect();
+    assert_eq!(
+      derived,
+      [HomeAction::EnterProcessing, HomeAction::Increment(1), HomeAction::ExitProcessing]
+        .map(Action::from)
+        .iter()
+        .collect::<Vec<_>>()
+    );
+
+    let (mut home, input_tx, mut channels) = session(&tasks)?;
+    replay.spawn(input_tx).await?;
+    handle_until_settled(&mut home, &tasks, &mut channels, &mut None).await?;
+    assert_eq!(home.counter, 1);
+    std::fs::remove_file(path)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,142 @@
+//! Writes the effective key bindings out as a cheatsheet, for documentation or printing.
+//!
+//! Bindings are listed per section, the global ones first, in the same order the help screen shows them.
+
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::{
+  app::Mode,
+  cli::KeysFormat,
+  config::{sorted_bindings, KeyBindings, Keymap},
+};
+
+/// One row of the cheatsheet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BindingRow {
+  pub keys: String,
+  pub action: String,
+  /// What the action does, left out when there is nothing to say beyond the action itself.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+}
+
+/// The bindings of the global section or of one mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section {
+  pub name: String,
+  pub bindings: Vec<BindingRow>,
+}
+
+/// The sections to list: the global bindings, then each mode's own, leaving out any with no bindings.
+pub fn sections(keybindings: &KeyBindings) -> Vec<Section> {
+  let global = (KeyBindings::GLOBAL.to_string(), Some(&keybindings.global));
+  let modes = Mode::ALL.map(|mode| (format!("{mode:?}"), keybindings.get(&mode)));
+  std::iter::once(global)
+    .chain(modes)
+    .filter_map(|(name, keymap): (String, Option<&Keymap>)| {
+      let bindings: Vec<BindingRow> = sorted_bindings(keymap?)
+        .into_iter()
+        .map(|(keys, action)| {
+          let description = action.description();
+          let action = action.to_string();
+          BindingRow { keys, description: (description != action).then_some(description), action }
+        })
+        .collect();
+      (!bindings.is_empty()).then_some(Section { name, bindings })
+    })
+    .collect()
+}
+
+pub fn render(keybindings: &KeyBindings, format: KeysFormat) -> Result<String> {
+  let sections = sections(keybindings);
+  match format {
+    KeysFormat::Json => Ok(serde_json::to_string_pretty(&sections)?),
+    KeysFormat::Markdown => Ok(markdown(&sections)),
+  }
+}
+
+fn markdown(sections: &[Section]) -> String {
+  let mut lines = vec!["# Key bindings".to_string()];
+  for section in sections {
+    lines.extend(["".to_string(), format!("## {}", section.name), "".to_string()]);
+    lines.push("| Key | Action | Description |".to_string());
+    lines.push("| --- | --- | --- |".to_string());
+    lines.extend(section.bindings.iter().map(|row| {
+      let description = row.description.as_deref().unwrap_or_default().replace('|', "\\|");
+      format!("| {} | {} | {description} |", code(&row.keys), code(&row.action))
+    }));
+  }
+  lines.push("".to_string());
+  lines.join("\n")
+}
+
+/// `text` as inline code that is safe inside a table cell.
+fn code(text: &str) -> String {
+  let code = match text.contains('`') {
+    true => format!("`` {text} ``"),
+    false => format!("`{text}`"),
+  };
+  code.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{
+    actions::{Action, EngineAction, HomeAction},
+    config::parse_key_sequence,
+  };
+
+  fn keybindings() -> KeyBindings {
+    let keymap = |bindings: &[(&str, Action)]| {
+      bindings.iter().map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action.clone())).collect()
+    };
+    let mut keybindings = KeyBindings::default();
+    keybindings.global = keymap(&[("<ctrl-c>", EngineAction::Quit.into()), ("<q>", EngineAction::Quit.into())]);
+    keybindings
+      .insert(Mode::Home, keymap(&[("<|>", HomeAction::RemoveTodo(3).into()), ("<d>", HomeAction::DeleteTodo.into())]));
+    keybindings.insert(Mode::MainMenu, Keymap::new());
+    keybindings
+  }
+
+  #[test]
+  fn test_markdown_lists_sections_in_help_order() -> Result<()> {
+    assert_eq!(
+      render(&keybindings(), KeysFormat::Markdown)?,
+      [
+        "# Key bindings",
+        "",
+        "## Global",
+        "",
+        "| Key | Action | Description |",
+        "| --- | --- | --- |",
+        "| `ctrl-c` | `Engine.Quit` | Quit |",
+        "| `q` | `Engine.Quit` | Quit |",
+        "",
+        "## Home",
+        "",
+        "| Key | Action | Description |",
+        "| --- | --- | --- |",
+        "| `d` | `Home.DeleteTodo` | Delete the selected todo |",
+        "| `\\|` | `Home.RemoveTodo(3)` |  |",
+        "",
+      ]
+      .join("\n")
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_json_keeps_the_same_order() -> Result<()> {
+    let json: serde_json::Value = serde_json::from_str(&render(&keybindings(), KeysFormat::Json)?)?;
+    assert_eq!(json[0]["name"], "Global");
+    assert_eq!(json[1]["bindings"][0]["keys"], "d");
+    assert_eq!(json[1]["bindings"][0]["description"], "Delete the selected todo");
+    assert_eq!(json[1]["bindings"][1].get("description"), None);
+    assert_eq!(json.as_array().map(Vec::len), Some(2));
+    Ok(())
+  }
+}
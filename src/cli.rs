@@ -1,21 +1,115 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
-use crate::utils::version;
+use crate::{config::positive_rate, utils::version};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub struct Cli {
-  #[arg(short, long, value_name = "FLOAT", help = "Tick rate, i.e. number of ticks per second", default_value_t = 1.0)]
-  pub tick_rate: f64,
+  #[arg(
+    short,
+    long,
+    value_name = "FLOAT",
+    value_parser = parse_rate,
+    help = "Tick rate, i.e. number of ticks per second [default: from the config, or 1]"
+  )]
+  pub tick_rate: Option<f64>,
 
   #[arg(
     short,
     long,
     value_name = "FLOAT",
-    help = "Frame rate, i.e. number of frames per second",
-    default_value_t = 60.0
+    value_parser = parse_rate,
+    help = "Frame rate, i.e. number of frames per second [default: from the config, or 60]"
+  )]
+  pub frame_rate: Option<f64>,
+
+  #[arg(long, help = "Run the given operation without starting the terminal UI")]
+  pub no_tui: bool,
+
+  #[arg(long, value_enum, help = "Format of the summary printed by --no-tui operations", default_value_t)]
+  pub output: OutputFormat,
+
+  #[arg(long, help = "Keep todo text and file paths in exported action logs instead of redacting them")]
+  pub include_content: bool,
+
+  #[arg(
+    long,
+    value_name = "PATH",
+    help = "Record the actions of this session to a file, to reproduce it with --replay"
+  )]
+  pub record: Option<PathBuf>,
+
+  #[arg(
+    long,
+    value_name = "PATH",
+    help = "Play back a file written by --record, or an action trace, instead of reading the keyboard"
+  )]
+  pub replay: Option<PathBuf>,
+
+  #[arg(
+    long,
+    requires = "replay",
+    help = "Play back the --replay file as fast as possible instead of at its recorded pace"
+  )]
+  pub replay_fast: bool,
+
+  #[arg(
+    long,
+    value_enum,
+    value_name = "FORMAT",
+    conflicts_with = "no_tui",
+    help = "Print the key bindings in effect as a cheatsheet and exit, without starting the terminal UI"
   )]
-  pub frame_rate: f64,
+  pub export_keys: Option<KeysFormat>,
+
+  #[arg(long, value_name = "PATH", requires = "export_keys", help = "Write the --export-keys cheatsheet to a file")]
+  pub export_keys_to: Option<PathBuf>,
+
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+/// Operations that can be run from scripts with `--no-tui`.
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+  /// Add a todo for every non-blank line read from stdin.
+  Import,
+  /// Write all todos to a JSON file.
+  Export { path: PathBuf },
+  /// Check that the config and the saved todos can be loaded.
+  Check,
+  /// Print the key bindings and theme in effect, after merging the config files over the defaults.
+  DumpConfig,
+}
+
+fn parse_rate(s: &str) -> Result<f64, String> {
+  positive_rate(s.parse().map_err(|e| format!("{e}"))?)
+}
+
+#[derive(ValueEnum, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+  #[default]
+  Text,
+  Json,
+}
+
+/// Formats of the key binding cheatsheet written by `--export-keys`.
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeysFormat {
+  Markdown,
+  Json,
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::CommandFactory;
+
+  use super::*;
+
+  #[test]
+  fn test_cli_definition() {
+    Cli::command().debug_assert();
+  }
 }
@@ -1,20 +1,50 @@
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyEvent, MouseEvent};
-use ratatui::layout::Rect;
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::Span,
+  widgets::{block::Title, Block, Borders},
+};
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
   actions::Action,
+  app::Mode,
   config::Config,
+  resources::Occupancy,
+  tasks::TaskSet,
   tui::{Event, Frame},
 };
 
+pub mod action_history;
+pub mod command_palette;
+pub mod confirm_dialog;
 pub mod fps;
 pub mod help_screen;
 pub mod home;
+pub mod key_hint_bar;
+pub mod keybinding_editor;
+pub mod layout_cache;
+pub mod layout_debug;
 pub mod list_todos;
 pub mod main_menu;
 pub mod mode_switcher;
+pub mod navigable;
+pub mod quit_prompt;
+pub mod status_bar;
+pub mod which_key;
+
+/// Whether a component can do anything with an action in its current state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Applicability {
+  /// The component will act on it.
+  Handled,
+  /// The component has no interest in it.
+  Ignored,
+  /// The component would normally act on it but can't right now, for the given reason.
+  NotApplicable(String),
+}
 
 pub trait Component {
   #[allow(unused_variables)]
@@ -25,6 +55,11 @@ pub trait Component {
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     Ok(())
   }
+  /// Hands over the app's task set, which any background work should be spawned into.
+  #[allow(unused_variables)]
+  fn register_task_handler(&mut self, tasks: TaskSet) -> Result<()> {
+    Ok(())
+  }
   fn init(&mut self) -> Result<()> {
     Ok(())
   }
@@ -48,5 +83,136 @@ pub trait Component {
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     Ok(None)
   }
+  /// Called on global components when the app switches from one mode to another, before the new mode is drawn.
+  #[allow(unused_variables)]
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    Ok(())
+  }
+  /// Called on a mode's components when the app switches to that mode.
+  fn on_enter(&mut self) -> Result<()> {
+    Ok(())
+  }
+  /// Called on a mode's components when the app switches away from that mode, so they can drop transient state.
+  fn on_exit(&mut self) -> Result<()> {
+    Ok(())
+  }
+  /// Called on every component, whatever the mode, when the user asks to quit, so it can save what it can.
+  ///
+  /// Returns whether anything would still be lost by quitting, in which case the user is asked to confirm first.
+  fn prepare_quit(&mut self) -> Result<bool> {
+    Ok(false)
+  }
+  /// Actions whose key bindings this component mentions in its on-screen hints.
+  fn hinted_actions(&self) -> Vec<(Mode, Action)> {
+    Vec::new()
+  }
+  /// Whether the component is taking typed text. While any component is, keys pressed without Ctrl or Alt are left
+  /// to the components instead of being resolved through the key bindings.
+  fn captures_text_input(&self) -> bool {
+    false
+  }
+  /// Whether the component takes every key itself, Ctrl and Alt combinations included, e.g. while recording a new key
+  /// binding. While any component does, no key is resolved through the key bindings.
+  fn captures_all_keys(&self) -> bool {
+    false
+  }
+  /// Whether the component currently holds input focus, e.g. an open overlay.
+  /// While any component has focus, navigation actions are only routed to the focused components.
+  fn has_focus(&self) -> bool {
+    false
+  }
+  /// Where the component sits in the focus cycle while it is visible, lowest first. `None` keeps it out of the cycle,
+  /// e.g. while it is hidden. The primary content pane of a mode uses 0.
+  fn focus_order(&self) -> Option<u8> {
+    None
+  }
+  /// Tells the component whether the focus cycle has landed on it, so it can highlight its border.
+  #[allow(unused_variables)]
+  fn set_focused(&mut self, focused: bool) {}
+  /// Whether the component can act on `action` right now. Must be cheap, since it is asked on every bound key press.
+  ///
+  /// When no component handles an action and at least one reports it [`Applicability::NotApplicable`], the reason
+  /// is shown instead of the key press silently doing nothing. Components that don't override this stay out of it.
+  #[allow(unused_variables)]
+  fn can_handle(&self, action: &Action) -> Applicability {
+    Applicability::Ignored
+  }
+  /// Named areas the component split its rect into on the last draw, outlined by the layout debug overlay.
+  fn layout_areas(&self) -> Vec<(&'static str, Rect)> {
+    Vec::new()
+  }
+  /// How full the buffers the component keeps are, shown on the diagnostics panel of the layout debug overlay.
+  fn occupancy(&self) -> Vec<Occupancy> {
+    Vec::new()
+  }
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()>;
 }
+
+/// Whether the cell at `column`, `row` lies inside `area`, e.g. to hit-test a mouse event.
+pub fn area_contains(area: Rect, column: u16, row: u16) -> bool {
+  (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// The border style of a focused pane: the `focused_border` style of `mode`, or light green if there isn't one.
+pub fn focused_border_style(config: &Config, mode: Mode) -> Style {
+  if config.theme.monochrome {
+    return Style::default().add_modifier(Modifier::BOLD);
+  }
+  config
+    .styles
+    .get(&mode)
+    .and_then(|styles| styles.get("focused_border"))
+    .copied()
+    .unwrap_or(Style::default().fg(Color::LightGreen))
+}
+
+/// Renders the key bound to an action for a UI hint, or a warning when nothing is bound to it.
+pub fn key_hint(binding: Option<String>, style: Style) -> Span<'static> {
+  match binding {
+    Some(key) => Span::styled(key, style),
+    None => Span::styled("(unbound)", Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC)),
+  }
+}
+
+/// A rect `percent_x` wide and `percent_y` high, as percentages of `area`, in the middle of it. Any cell left over from
+/// centering goes on the right or below.
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+  let scale = |length: u16, percent: u16| (u32::from(length) * u32::from(percent.min(100)) / 100) as u16;
+  let (width, height) = (scale(area.width, percent_x), scale(area.height, percent_y));
+  Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// The bordered block a popup is drawn in, with `title` along its top border.
+pub fn popup_block<'a>(title: impl Into<Title<'a>>) -> Block<'a> {
+  Block::default().title(title).borders(Borders::ALL)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_centered_rect_is_centered() {
+    for (area, percent_x, percent_y) in [
+      (Rect::new(0, 0, 80, 24), 50, 50),
+      (Rect::new(0, 0, 81, 25), 50, 50),
+      (Rect::new(10, 5, 60, 30), 80, 80),
+      (Rect::new(3, 2, 7, 3), 33, 67),
+      (Rect::new(0, 0, 1, 1), 50, 50),
+    ] {
+      let rect = centered_rect(percent_x, percent_y, area);
+      assert_eq!(area.intersection(rect), rect, "{area:?} {rect:?}");
+      let (left, right) = (rect.left() - area.left(), area.right() - rect.right());
+      let (top, bottom) = (rect.top() - area.top(), area.bottom() - rect.bottom());
+      assert!(right == left || right == left + 1, "{area:?} {rect:?}");
+      assert!(bottom == top || bottom == top + 1, "{area:?} {rect:?}");
+    }
+    assert_eq!(centered_rect(50, 50, Rect::new(0, 0, 80, 24)), Rect::new(20, 6, 40, 12));
+    assert_eq!(centered_rect(80, 80, Rect::new(10, 5, 60, 30)), Rect::new(16, 8, 48, 24));
+    // The whole area at most, however large the percentages.
+    assert_eq!(centered_rect(150, 100, Rect::new(2, 2, 10, 10)), Rect::new(2, 2, 10, 10));
+    assert_eq!(centered_rect(0, 50, Rect::new(0, 0, 10, 10)), Rect::new(5, 2, 0, 5));
+  }
+}
@@ -0,0 +1,238 @@
+use std::{
+  collections::VecDeque,
+  fmt,
+  sync::{Arc, Mutex},
+};
+
+use chrono::Local;
+use color_eyre::eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{focused_border_style, Applicability, Component};
+use crate::{
+  action_log::{ActionLog, Diagnostics},
+  actions::{Action, EngineAction, HomeAction, ListNavDirection},
+  app::Mode,
+  config::Config,
+  resources::{Occupancy, ResourceUsage},
+  tui::Frame,
+  utils::get_data_dir,
+};
+
+/// How many of the most recent actions are kept.
+pub const HISTORY_CAPACITY: usize = 100;
+
+/// Which actions the history panel shows.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ActionFilter {
+  #[default]
+  All,
+  Engine,
+  Home,
+}
+
+impl ActionFilter {
+  pub fn next(self) -> Self {
+    match self {
+      Self::All => Self::Engine,
+      Self::Engine => Self::Home,
+      Self::Home => Self::All,
+    }
+  }
+
+  pub fn matches(self, action: &Action) -> bool {
+    match self {
+      Self::All => true,
+      Self::Engine => matches!(action, Action::Engine(_)),
+      Self::Home => matches!(action, Action::Home(_)),
+    }
+  }
+}
+
+impl fmt::Display for ActionFilter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::All => write!(f, "all"),
+      Self::Engine => write!(f, "engine only"),
+      Self::Home => write!(f, "home only"),
+    }
+  }
+}
+
+/// A panel listing the most recently dispatched actions, for debugging.
+#[derive(Debug, Default)]
+pub struct ActionHistory {
+  pub show_history: bool,
+  filter: ActionFilter,
+  actions: VecDeque<Action>,
+  /// Whether exported logs keep todo text and file paths.
+  include_content: bool,
+  /// The app's latest resource snapshot, included in exported logs.
+  resources: Option<Arc<Mutex<ResourceUsage>>>,
+  /// How many of the newest visible actions are scrolled out of view at the bottom.
+  scroll: usize,
+  focused: bool,
+  config: Config,
+  mode: Mode,
+}
+
+impl ActionHistory {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn include_content(mut self, include_content: bool) -> Self {
+    self.include_content = include_content;
+    self
+  }
+
+  pub fn resources(mut self, resources: Arc<Mutex<ResourceUsage>>) -> Self {
+    self.resources = Some(resources);
+    self
+  }
+
+  /// Writes the recorded actions to the data directory, reporting where they went in the status bar.
+  fn export(&self) -> Action {
+    let mut diagnostics = Diagnostics::collect();
+    if let Some(resources) = &self.resources {
+      diagnostics = diagnostics.with_resources(resources.lock().unwrap_or_else(|e| e.into_inner()).clone());
+    }
+    let log = ActionLog::new(Some(self.actions.iter().cloned().collect()), Some(diagnostics), Local::now());
+    match log.write(&get_data_dir(), self.include_content) {
+      Ok(path) => EngineAction::Notify(format!("Action log written to {}", path.display())).into(),
+      Err(e) => EngineAction::Error(format!("Failed to export the action log: {e}")).into(),
+    }
+  }
+
+  fn record(&mut self, action: Action) {
+    if self.actions.len() == HISTORY_CAPACITY {
+      self.actions.pop_front();
+    }
+    self.actions.push_back(action);
+  }
+
+  /// The stored actions that pass the active filter, oldest first.
+  pub fn visible_actions(&self) -> Vec<&Action> {
+    self.actions.iter().filter(|action| self.filter.matches(action)).collect()
+  }
+}
+
+impl Component for ActionHistory {
+  fn can_handle(&self, action: &Action) -> Applicability {
+    match action {
+      Action::Home(HomeAction::NavigateList(ListNavDirection::Up | ListNavDirection::Down, _)) if self.has_focus() => {
+        Applicability::Handled
+      },
+      _ => Applicability::Ignored,
+    }
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if action == EngineAction::ExportActionLog.into() {
+      return Ok(Some(self.export()));
+    }
+    match action {
+      // These fire many times a second, or on every key press, and would drown out everything else.
+      Action::Engine(
+        EngineAction::Tick | EngineAction::Render | EngineAction::PendingKeys(_) | EngineAction::PendingCount(_),
+      ) => {},
+      Action::Engine(EngineAction::ToggleShowActionHistory) => self.show_history = !self.show_history,
+      Action::Engine(EngineAction::CycleActionHistoryFilter) => self.filter = self.filter.next(),
+      // Only reached while focused, since the panel reports focus then.
+      Action::Home(HomeAction::NavigateList(ListNavDirection::Up, count)) => {
+        self.scroll = self.scroll.saturating_add(count).min(self.visible_actions().len().saturating_sub(1))
+      },
+      Action::Home(HomeAction::NavigateList(ListNavDirection::Down, count)) => {
+        self.scroll = self.scroll.saturating_sub(count)
+      },
+      action => self.record(action),
+    }
+    Ok(None)
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    Ok(())
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    self.show_history.then_some(10)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn has_focus(&self) -> bool {
+    self.show_history && self.focused
+  }
+
+  fn occupancy(&self) -> Vec<Occupancy> {
+    vec![Occupancy::new("action history", self.actions.len(), Some(HISTORY_CAPACITY))]
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if !self.show_history {
+      return Ok(());
+    }
+
+    let rect = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+      .split(rect.inner(&Margin::new(1, 1)))[1];
+    let visible = self.visible_actions();
+    let visible = &visible[..visible.len().saturating_sub(self.scroll)];
+    // Show the newest actions that fit, keeping them in the order they happened.
+    let skip = visible.len().saturating_sub(rect.height.saturating_sub(2) as usize);
+    let items: Vec<ListItem> = visible.iter().skip(skip).map(|action| ListItem::new(action.to_string())).collect();
+    let border_style =
+      if self.focused { focused_border_style(&self.config, self.mode) } else { Style::default().fg(Color::Magenta) };
+
+    f.render_widget(Clear, rect);
+    f.render_widget(
+      List::new(items).block(
+        Block::default()
+          .title(format!("Action History ({})", self.filter))
+          .borders(Borders::ALL)
+          .border_style(border_style),
+      ),
+      rect,
+    );
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::actions::HomeAction;
+
+  #[test]
+  fn test_engine_filter_excludes_home_actions() -> Result<()> {
+    let mut history = ActionHistory::new();
+    history.update(EngineAction::Refresh.into())?;
+    history.update(HomeAction::EnterInsert.into())?;
+    history.update(EngineAction::Tick.into())?;
+    history.update(EngineAction::CycleActionHistoryFilter.into())?;
+
+    assert_eq!(history.visible_actions(), vec![&Action::from(EngineAction::Refresh)]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_filter_cycles_back_to_all() -> Result<()> {
+    let mut history = ActionHistory::new();
+    for _ in 0..3 {
+      history.update(EngineAction::CycleActionHistoryFilter.into())?;
+    }
+    assert_eq!(history.filter, ActionFilter::All);
+    Ok(())
+  }
+}
@@ -0,0 +1,326 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::{centered_rect, focused_border_style, popup_block, Component, Frame};
+use crate::{
+  actions::{Action, EngineAction, HomeAction, ListNavDirection, MainMenuAction},
+  app::Mode,
+  config::{Config, Theme},
+  todos::TodoSort,
+};
+
+const PROMPT: &str = "> ";
+
+/// The actions offered before anything is typed: everything a user might want to run by hand, with the arguments it
+/// is usually bound with. Actions the app only sends itself, like ticks and resizes, are left out.
+pub fn commands() -> Vec<Action> {
+  let engine = [
+    EngineAction::Quit,
+    EngineAction::Suspend,
+    EngineAction::Refresh,
+    EngineAction::ReloadTheme,
+    EngineAction::ReloadConfig,
+    EngineAction::ToggleShowHelp,
+    EngineAction::PreviousMode,
+    EngineAction::ToggleShowModeSwitcher,
+    EngineAction::ToggleShowActionHistory,
+    EngineAction::CycleActionHistoryFilter,
+    EngineAction::ExportActionLog,
+    EngineAction::ToggleLayoutDebug,
+    EngineAction::ToggleKeybindingEditor,
+    EngineAction::SaveKeybindings,
+    EngineAction::CycleTheme,
+    EngineAction::FocusNext,
+    EngineAction::FocusPrevious,
+  ]
+  .into_iter()
+  .chain(Mode::ALL.map(EngineAction::ChangeMode))
+  .chain(Theme::NAMES.map(|name| EngineAction::SetTheme(name.to_string())))
+  .map(Action::from);
+  let home = [
+    HomeAction::EnterInsert,
+    HomeAction::DeleteTodo,
+    HomeAction::ToggleTodos(1),
+    HomeAction::Undo,
+    HomeAction::Redo,
+    HomeAction::MarkAllComplete,
+    HomeAction::ClearCompleted,
+    HomeAction::SortBy(TodoSort::Due),
+    HomeAction::SortBy(TodoSort::Created),
+    HomeAction::SortBy(TodoSort::Priority),
+    HomeAction::CycleSelectedPriority,
+    HomeAction::FilterByTag(None),
+    HomeAction::Save,
+    HomeAction::TrimEntries,
+    HomeAction::ToggleRelativeNumbers,
+    HomeAction::ScheduleIncrement(1),
+    HomeAction::ScheduleDecrement(1),
+    HomeAction::NavigateList(ListNavDirection::First, 1),
+    HomeAction::NavigateList(ListNavDirection::Last, 1),
+  ]
+  .into_iter()
+  .map(Action::from);
+  engine.chain(home).chain([MainMenuAction::ActivateTab.into()]).collect()
+}
+
+/// How closely `query` matches `candidate`, ignoring case: its characters have to appear in `candidate` in order, but
+/// not necessarily next to each other. Returns how many characters of `candidate` fall in between, so lower is
+/// better, or `None` if there is no match at all.
+pub fn fuzzy_score(candidate: &str, query: &str) -> Option<usize> {
+  let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+  let (mut first, mut last, mut matched) = (None, 0, 0);
+  for (i, c) in candidate.chars().flat_map(char::to_lowercase).enumerate() {
+    let Some(&wanted) = query.peek() else {
+      break;
+    };
+    if c == wanted {
+      query.next();
+      first.get_or_insert(i);
+      last = i;
+      matched += 1;
+    }
+  }
+  match (query.peek(), first) {
+    (Some(_), _) => None,
+    (None, None) => Some(0),
+    (None, Some(first)) => Some(last + 1 - first - matched),
+  }
+}
+
+/// An overlay for running any action by name, opened with [`EngineAction::ToggleCommandPalette`].
+///
+/// Typing narrows the list down with [`fuzzy_score`], Up and Down pick an entry, Enter sends it to the app and Esc
+/// closes the palette. Anything typed that reads as a whole action, arguments and all, is offered first, so actions
+/// can be run with arguments that aren't listed. Every key goes to the palette while it is open.
+#[derive(Default)]
+pub struct CommandPalette {
+  open: bool,
+  query: Input,
+  selected: usize,
+  commands: Vec<Action>,
+  action_tx: Option<UnboundedSender<Action>>,
+  config: Config,
+  mode: Mode,
+  focused: bool,
+}
+
+impl CommandPalette {
+  pub fn new() -> Self {
+    Self { commands: commands(), ..Self::default() }
+  }
+
+  pub fn is_open(&self) -> bool {
+    self.open
+  }
+
+  /// The actions matching what has been typed, best first.
+  pub fn matches(&self) -> Vec<Action> {
+    let query = self.query.value().trim();
+    let typed = query.parse::<Action>().ok();
+    let mut scored: Vec<_> = self
+      .commands
+      .iter()
+      .filter(|action| Some(*action) != typed.as_ref())
+      .filter_map(|action| fuzzy_score(&action.to_string(), query).map(|score| (score, action)))
+      .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    typed.into_iter().chain(scored.into_iter().map(|(_, action)| action.clone())).collect()
+  }
+
+  fn toggle(&mut self) {
+    self.open = !self.open;
+    // Every time the palette opens, it starts from the full list.
+    self.query.reset();
+    self.selected = 0;
+  }
+
+  /// Sends the selected action off and closes the palette.
+  fn run(&mut self) {
+    if let (Some(action), Some(tx)) = (self.matches().into_iter().nth(self.selected), &self.action_tx) {
+      if let Err(e) = tx.send(action) {
+        log::error!("Failed to send action: {e:?}");
+      }
+    }
+    self.toggle();
+  }
+
+  fn draw_palette(&self, f: &mut Frame, rect: Rect) {
+    let area = centered_rect(67, 67, rect);
+    let border_style =
+      if self.focused { focused_border_style(&self.config, self.mode) } else { self.config.theme.border_style() };
+    let block = popup_block("Run Action").border_style(border_style);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let [query_area, list_area] =
+      *Layout::default().constraints([Constraint::Length(1), Constraint::Min(0)]).split(inner)
+    else {
+      return;
+    };
+    f.render_widget(Paragraph::new(format!("{PROMPT}{}", self.query.value())), query_area);
+    f.set_cursor(query_area.x + (PROMPT.len() + self.query.visual_cursor()) as u16, query_area.y);
+
+    let matches = self.matches();
+    let list = match matches.is_empty() {
+      true => List::new(vec![ListItem::new("No matching actions").dim()]),
+      false => List::new(matches.iter().map(|action| ListItem::new(action.to_string())).collect::<Vec<_>>())
+        .highlight_style(Style::default().reversed()),
+    };
+    let mut state = ListState::default().with_selected((!matches.is_empty()).then_some(self.selected));
+    f.render_stateful_widget(list, list_area, &mut state);
+  }
+}
+
+impl Component for CommandPalette {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.action_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if !self.open {
+      return Ok(None);
+    }
+    match key.code {
+      KeyCode::Esc => self.toggle(),
+      KeyCode::Enter => self.run(),
+      KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Down => self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1)),
+      _ => {
+        if self.query.handle_event(&crossterm::event::Event::Key(key)).is_some_and(|change| change.value) {
+          self.selected = 0;
+        }
+      },
+    }
+    Ok(None)
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if action == EngineAction::ToggleCommandPalette.into() {
+      self.toggle();
+    }
+    Ok(None)
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    Ok(())
+  }
+
+  fn captures_text_input(&self) -> bool {
+    self.open
+  }
+
+  fn has_focus(&self) -> bool {
+    self.open
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    self.open.then_some(40)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if self.open {
+      self.draw_palette(f, rect);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn type_text(palette: &mut CommandPalette, text: &str) -> Result<()> {
+    for c in text.chars() {
+      palette.handle_key_events(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+    }
+    Ok(())
+  }
+
+  fn press(palette: &mut CommandPalette, code: KeyCode) -> Result<()> {
+    palette.handle_key_events(KeyEvent::new(code, KeyModifiers::NONE))?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_fuzzy_score_prefers_closer_matches() {
+    assert_eq!(fuzzy_score("Engine.Quit", ""), Some(0));
+    assert_eq!(fuzzy_score("Engine.Quit", "quit"), Some(0));
+    assert_eq!(fuzzy_score("Home.Undo", "hundo"), Some(4));
+    assert_eq!(fuzzy_score("Home.Undo", "odnu"), None);
+    assert_eq!(fuzzy_score("Home.Redo", "redo!"), None);
+  }
+
+  #[test]
+  fn test_commands_round_trip_through_their_names() {
+    for action in commands() {
+      assert_eq!(action.to_string().parse::<Action>(), Ok(action));
+    }
+  }
+
+  #[test]
+  fn test_filters_and_runs_the_selected_action() -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut palette = CommandPalette::new();
+    palette.register_action_handler(tx)?;
+    palette.update(EngineAction::ToggleCommandPalette.into())?;
+    assert!(palette.captures_text_input());
+    assert_eq!(palette.matches().len(), commands().len());
+
+    type_text(&mut palette, "redo")?;
+    assert_eq!(palette.matches(), vec![HomeAction::Redo.into(), EngineAction::ReloadConfig.into()]);
+    press(&mut palette, KeyCode::Down)?;
+    press(&mut palette, KeyCode::Down)?;
+    press(&mut palette, KeyCode::Enter)?;
+    assert_eq!(rx.try_recv(), Ok(EngineAction::ReloadConfig.into()));
+    assert!(!palette.is_open());
+
+    // Opening again starts afresh, and Esc closes without running anything.
+    palette.update(EngineAction::ToggleCommandPalette.into())?;
+    assert_eq!(palette.query.value(), "");
+    press(&mut palette, KeyCode::Esc)?;
+    assert!(!palette.is_open());
+    assert!(rx.try_recv().is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_typed_action_with_arguments_is_offered_first() -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut palette = CommandPalette::new();
+    palette.register_action_handler(tx)?;
+    palette.update(EngineAction::ToggleCommandPalette.into())?;
+
+    type_text(&mut palette, "Home.ScheduleIncrement(5)")?;
+    assert_eq!(palette.matches(), vec![HomeAction::ScheduleIncrement(5).into()]);
+    press(&mut palette, KeyCode::Enter)?;
+    assert_eq!(rx.try_recv(), Ok(HomeAction::ScheduleIncrement(5).into()));
+    Ok(())
+  }
+
+  #[test]
+  fn test_keys_are_ignored_while_closed() -> Result<()> {
+    let mut palette = CommandPalette::new();
+    type_text(&mut palette, "quit")?;
+    assert_eq!(palette.query.value(), "");
+    assert!(!palette.has_focus());
+    Ok(())
+  }
+}
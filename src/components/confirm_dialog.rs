@@ -0,0 +1,89 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::{Component, Frame};
+use crate::actions::Action;
+
+/// A modal yes/no question, answered with `y` or `n` (or Esc).
+///
+/// The owning component forwards its key events here while the dialog is open, and closes it once an answer comes
+/// back as either the confirm or the cancel action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmDialog {
+  prompt: String,
+  confirm: Action,
+  cancel: Action,
+}
+
+impl ConfirmDialog {
+  pub fn new(prompt: impl Into<String>, confirm: Action, cancel: Action) -> Self {
+    Self { prompt: prompt.into(), confirm, cancel }
+  }
+
+  pub fn prompt(&self) -> &str {
+    &self.prompt
+  }
+
+  /// The area the dialog takes up, centered in `rect` and no bigger than it.
+  fn area(&self, rect: Rect) -> Rect {
+    let width = (self.prompt.chars().count() as u16 + 4).min(rect.width);
+    let height = 3.min(rect.height);
+    Rect::new(rect.x + (rect.width - width) / 2, rect.y + (rect.height - height) / 2, width, height)
+  }
+}
+
+impl Component for ConfirmDialog {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    Ok(match key.code {
+      KeyCode::Char('y' | 'Y') => Some(self.confirm.clone()),
+      KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(self.cancel.clone()),
+      _ => None,
+    })
+  }
+
+  fn captures_text_input(&self) -> bool {
+    true
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let area = self.area(rect);
+    f.render_widget(Clear, area);
+    f.render_widget(
+      Paragraph::new(self.prompt.as_str())
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red))),
+      area,
+    );
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+  use pretty_assertions::assert_eq;
+  use ratatui::{backend::TestBackend, Terminal};
+
+  use super::*;
+  use crate::actions::{EngineAction, HomeAction};
+
+  #[test]
+  fn test_answers_and_centered_popup() -> Result<()> {
+    let mut dialog =
+      ConfirmDialog::new("Delete 'milk'? (y/n)", HomeAction::RemoveTodo(1).into(), EngineAction::Render.into());
+    let mut press = |c| dialog.handle_key_events(KeyEvent::new(c, KeyModifiers::NONE));
+    assert_eq!(press(KeyCode::Char('x'))?, None);
+    assert_eq!(press(KeyCode::Char('y'))?, Some(HomeAction::RemoveTodo(1).into()));
+    assert_eq!(press(KeyCode::Char('n'))?, Some(EngineAction::Render.into()));
+    assert_eq!(press(KeyCode::Esc)?, Some(EngineAction::Render.into()));
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 9))?;
+    terminal.draw(|f| dialog.draw(f, f.size()).unwrap())?;
+    let buffer = terminal.backend().buffer();
+    assert_eq!(dialog.area(buffer.area), Rect::new(8, 3, 24, 3));
+    let row: String = (8..32).map(|x| buffer.get(x, 4).symbol.clone()).collect();
+    assert_eq!(row, "│ Delete 'milk'? (y/n) │");
+    Ok(())
+  }
+}
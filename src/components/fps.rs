@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{collections::VecDeque, time::Instant};
 
 use color_eyre::eyre::Result;
 use ratatui::{prelude::*, widgets::*};
@@ -15,32 +15,54 @@ pub enum Ticker {
   RenderTick,
 }
 
+/// Number of render ticks averaged over when no window is configured.
+pub const DEFAULT_WINDOW: usize = 30;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FpsCounter {
   app_start_time: Instant,
   app_frames: u32,
   app_fps: f64,
 
-  render_start_time: Instant,
-  render_frames: u32,
-  render_fps: f64,
+  /// Times of the most recent render ticks, oldest first, holding at most `window` entries.
+  render_times: VecDeque<Instant>,
+  window: usize,
 }
 
 impl Default for FpsCounter {
   fn default() -> Self {
-    Self::new()
+    Self::new(DEFAULT_WINDOW)
   }
 }
 
 impl FpsCounter {
-  pub fn new() -> Self {
+  /// Creates a counter whose rolling average covers the last `window` render ticks.
+  pub fn new(window: usize) -> Self {
+    let window = window.max(2);
     Self {
       app_start_time: Instant::now(),
       app_frames: 0,
       app_fps: 0.0,
-      render_start_time: Instant::now(),
-      render_frames: 0,
-      render_fps: 0.0,
+      render_times: VecDeque::with_capacity(window),
+      window,
+    }
+  }
+
+  /// Frame rate implied by the gap between the last two render ticks.
+  pub fn instant_fps(&self) -> f64 {
+    match (self.render_times.iter().nth_back(1), self.render_times.back()) {
+      (Some(prev), Some(last)) => fps(1, last.saturating_duration_since(*prev).as_secs_f64()),
+      _ => 0.0,
+    }
+  }
+
+  /// Frame rate averaged over the render ticks in the window.
+  pub fn average_fps(&self) -> f64 {
+    match (self.render_times.front(), self.render_times.back()) {
+      (Some(first), Some(last)) => {
+        fps(self.render_times.len() - 1, last.saturating_duration_since(*first).as_secs_f64())
+      },
+      _ => 0.0,
     }
   }
 
@@ -56,16 +78,19 @@ impl FpsCounter {
     Ok(())
   }
 
-  fn render_tick(&mut self) -> Result<()> {
-    self.render_frames += 1;
-    let now = Instant::now();
-    let elapsed = (now - self.render_start_time).as_secs_f64();
-    if elapsed >= 1.0 {
-      self.render_fps = self.render_frames as f64 / elapsed;
-      self.render_start_time = now;
-      self.render_frames = 0;
+  fn render_tick(&mut self, now: Instant) {
+    if self.render_times.len() == self.window {
+      self.render_times.pop_front();
     }
-    Ok(())
+    self.render_times.push_back(now);
+  }
+}
+
+fn fps(frames: usize, elapsed_secs: f64) -> f64 {
+  if elapsed_secs > 0.0 {
+    frames as f64 / elapsed_secs
+  } else {
+    0.0
   }
 }
 
@@ -75,7 +100,7 @@ impl Component for FpsCounter {
       self.app_tick()?
     };
     if let Action::Engine(EngineAction::Render) = action {
-      self.render_tick()?
+      self.render_tick(Instant::now())
     };
     Ok(None)
   }
@@ -91,9 +116,40 @@ impl Component for FpsCounter {
 
     let rect = rects[0];
 
-    let s = format!("{:.2} fps (app) {:.2} fps (render)", self.app_fps, self.render_fps);
+    let s = format!(
+      "{:.2} fps (app) {:.2} fps (render) {:.2} fps (render, last {})",
+      self.app_fps,
+      self.instant_fps(),
+      self.average_fps(),
+      self.window
+    );
     let block = Block::default().title(block::Title::from(s.dim()).alignment(Alignment::Right));
     f.render_widget(block, rect);
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_rolling_average_covers_window() {
+    let start = Instant::now();
+    let mut counter = FpsCounter::new(3);
+    assert_eq!((counter.instant_fps(), counter.average_fps()), (0.0, 0.0));
+
+    // Ticks 100ms apart, then one 50ms after; only the last three ticks count towards the average.
+    for ms in [0, 100, 200, 300, 350] {
+      counter.render_tick(start + Duration::from_millis(ms));
+    }
+
+    assert_eq!(counter.render_times.len(), 3);
+    assert!((counter.instant_fps() - 20.0).abs() < 1e-9);
+    assert!((counter.average_fps() - 2.0 / 0.15).abs() < 1e-9);
+  }
+}
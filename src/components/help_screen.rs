@@ -1,22 +1,37 @@
 use std::collections::HashMap;
 
 use color_eyre::eyre::Result;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-  layout::{Constraint, Margin, Rect},
+  layout::{Constraint, Layout, Margin, Rect},
   style::*,
   text::*,
   widgets::*,
 };
 
+use tui_input::{backend::crossterm::EventHandler, Input};
+
 use crate::{
-  actions::{Action, HomeAction},
+  actions::{Action, HomeAction, ListNavDirection},
   app::Mode,
-  config::{key_event_to_string, Config},
+  config::{sorted_bindings, Config, KeyBindings, Keymap},
   tui::Frame,
 };
 
-use super::Component;
+use super::{centered_rect, focused_border_style, navigable::Navigable, popup_block, Component};
+
+/// A line of the key-bindings table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HelpRow {
+  Spacer,
+  /// The title of a section: a mode, or the global bindings.
+  Heading(String),
+  Binding {
+    keys: String,
+    action: String,
+    description: String,
+  },
+}
 
 #[derive(Default)]
 pub struct HelpScreen {
@@ -24,11 +39,23 @@ pub struct HelpScreen {
   watched_modes: Vec<Mode>,
   config: Config,
   state: TableState,
+  /// Text typed to narrow the table down to matching bindings.
+  filter: Input,
+  /// Whether keys are going into the filter, from pressing `/` until Enter or Esc.
+  searching: bool,
+  /// How many rows of the table fit on screen, as of the last draw.
+  page_size: usize,
+  /// Whether every watched mode is listed, rather than only the current one.
+  all_modes: bool,
+  /// Whether the actions are listed as written in the config, next to their descriptions.
+  show_actions: bool,
+  mode: Mode,
+  focused: bool,
 }
 
 impl HelpScreen {
   pub fn new(watched_modes: Vec<Mode>) -> Self {
-    Self { show_help: false, watched_modes, config: Config::default(), state: TableState::default() }
+    Self { watched_modes, ..Self::default() }
   }
 
   pub fn add_mode(&mut self, mode: Mode) {
@@ -37,59 +64,190 @@ impl HelpScreen {
     }
   }
 
+  /// The modes whose bindings are listed: the current one, or every watched one once toggled to the full listing.
+  fn listed_modes(&self) -> Vec<Mode> {
+    match self.all_modes {
+      true => self.watched_modes.clone(),
+      false => vec![self.mode],
+    }
+  }
+
+  /// The rows of the table: a blank line and a heading per section, then one row per binding whose keys, action or
+  /// description match the filter.
+  ///
+  /// The global bindings come first, once, followed by each listed mode's own. Sections without any matching binding
+  /// are left out altogether.
+  fn rows(&self) -> Vec<HelpRow> {
+    let filter = self.filter.value().to_lowercase();
+    let keybindings = &self.config.keybindings;
+    let global = (!keybindings.global.is_empty()).then(|| (KeyBindings::GLOBAL.to_string(), Some(&keybindings.global)));
+    let modes = self.listed_modes().into_iter().map(|mode| (format!("{mode:?}"), keybindings.get(&mode)));
+    global
+      .into_iter()
+      .chain(modes)
+      .flat_map(|(heading, keymap): (String, Option<&Keymap>)| {
+        let bindings: Vec<HelpRow> = keymap
+          .map(sorted_bindings)
+          .unwrap_or_default()
+          .into_iter()
+          .map(|(keys, action)| HelpRow::Binding {
+            keys,
+            action: action.to_string(),
+            description: action.description(),
+          })
+          .filter(|row| match row {
+            HelpRow::Binding { keys, action, description } => {
+              [keys, action, description].iter().any(|text| text.to_lowercase().contains(&filter))
+            },
+            _ => true,
+          })
+          .collect();
+
+        if bindings.is_empty() && !filter.is_empty() {
+          Vec::new()
+        } else {
+          [HelpRow::Spacer, HelpRow::Heading(heading)].into_iter().chain(bindings).collect()
+        }
+      })
+      .collect()
+  }
+
+  /// Moves the selected row. Until a row is selected, moving down starts at the top and moving up at the bottom.
+  fn scroll(&mut self, dir: ListNavDirection) {
+    match self.state.selected() {
+      Some(_) => self.navigate(dir, 1),
+      None if matches!(dir, ListNavDirection::Down | ListNavDirection::PageDown) => self.select_first(),
+      None => self.select_last(),
+    }
+  }
+
+  fn toggle(&mut self) {
+    self.show_help = !self.show_help;
+    // Every time the popup opens, it starts from the full, unfiltered list.
+    if self.show_help {
+      self.filter.reset();
+      self.searching = false;
+      self.state.select(None);
+    }
+  }
+
+  /// How many bindings are left once filtered.
+  fn match_count(&self) -> usize {
+    self.rows().iter().filter(|row| matches!(row, HelpRow::Binding { .. })).count()
+  }
+
+  /// The popup's title, saying which bindings are listed and how to switch.
+  fn title(&self) -> Line<'static> {
+    let (view, other) = match self.all_modes {
+      true => ("all modes".to_string(), format!("{:?} only", self.mode)),
+      false => (format!("{:?}", self.mode), "all modes".to_string()),
+    };
+    let mut title = vec![
+      Span::styled("Key Bindings", Style::default().add_modifier(Modifier::BOLD)),
+      format!(" - {view} (ctrl-v: {other})").into(),
+    ];
+    if !self.filter.value().is_empty() {
+      title.push(format!(" - {} matches", self.match_count()).into());
+    }
+    Line::from(title)
+  }
+
   fn draw_help(&mut self, f: &mut Frame, rect: &Rect) {
-    let rect = rect.inner(&Margin { horizontal: 4, vertical: 4 });
+    let rect = centered_rect(80, 80, *rect);
     f.render_widget(Clear, rect);
-    let block = Block::default()
-      .title(Line::from(vec![Span::styled("Key Bindings", Style::default().add_modifier(Modifier::BOLD))]))
-      .borders(Borders::ALL)
-      .border_style(Style::default().fg(Color::Yellow));
+    let block = popup_block(self.title()).border_style(if self.focused {
+      focused_border_style(&self.config, self.mode)
+    } else {
+      self.config.theme.border_style()
+    });
     f.render_widget(block, rect);
 
-    // Map the keybindings to a vector of rows.
-    // Each vector prints the key(s) and the action it performs.
-    // TODO: Change Action printing to prettier format.
+    // Each row prints the key(s) and what the action does, plus the action itself for those writing configs.
     let rows: Vec<Row> = self
-      .watched_modes
-      .iter()
-      .map(|mode| (mode, self.config.keybindings.get(mode).unwrap().clone()))
-      .flat_map(|(mode, bindings)| {
-        let mut rows = vec![
-          Row::new(vec![Cell::from("")]),
-          Row::new(vec![Cell::from(format!("{mode:?}")).style(Style::default().underlined())]),
-        ];
-
-        bindings
-          .iter()
-          .map(|(key, val)| {
-            Row::new(vec![
-              key
-                .iter()
-                .map(key_event_to_string)
-                .enumerate()
-                .map(|(i, k)| match i {
-                  0 => k,
-                  _ => format!(", {}", k),
-                })
-                .collect(),
-              format!("{val}"),
-            ])
-          })
-          .for_each(|r| rows.push(r));
-
-        rows
+      .rows()
+      .into_iter()
+      .map(|row| match row {
+        HelpRow::Spacer => Row::new(vec![Cell::from("")]),
+        HelpRow::Heading(heading) => Row::new(vec![Cell::from(heading).style(Style::default().underlined())]),
+        HelpRow::Binding { keys, action, description } if self.show_actions => {
+          Row::new(vec![keys, action, description])
+        },
+        HelpRow::Binding { keys, description, .. } => Row::new(vec![keys, description]),
       })
       .collect();
+    let (header, widths) = match self.show_actions {
+      true => (
+        vec!["Key", "Action", "Description"],
+        vec![Constraint::Percentage(15), Constraint::Percentage(35), Constraint::Percentage(50)],
+      ),
+      false => (vec!["Key", "Description"], vec![Constraint::Percentage(15), Constraint::Percentage(85)]),
+    };
 
     // Construct the final table.
     let table = Table::new(rows)
-      .header(Row::new(vec!["Key", "Action"]).bottom_margin(1).style(Style::default().add_modifier(Modifier::BOLD)))
-      .widths(&[Constraint::Percentage(10), Constraint::Percentage(90)])
-      .column_spacing(1);
+      .header(Row::new(header).bottom_margin(1).style(Style::default().add_modifier(Modifier::BOLD)))
+      .widths(&widths)
+      .column_spacing(1)
+      .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
     let location = rect.inner(&Margin { vertical: 4, horizontal: 2 });
+    let [table_area, _, filter_area] = *Layout::default()
+      .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(1)])
+      .split(location)
+    else {
+      return;
+    };
+    let filter = match self.searching || !self.filter.value().is_empty() {
+      true => Line::from(vec![Span::styled("/", self.config.theme.dim_style()), self.filter.value().into()]),
+      false => Line::styled(
+        match self.show_actions {
+          true => "/ to search, ctrl-a: hide actions",
+          false => "/ to search, ctrl-a: show actions",
+        },
+        self.config.theme.dim_style(),
+      ),
+    };
     f.render_widget(Clear, location);
-    f.render_stateful_widget(table, location, &mut self.state);
+    f.render_widget(Paragraph::new(filter), filter_area);
+    if self.searching {
+      f.set_cursor(filter_area.x + 1 + self.filter.visual_cursor() as u16, filter_area.y);
+    }
+    // The header and the gap beneath it take up two rows.
+    self.page_size = (table_area.height as usize).saturating_sub(2);
+    let row_count = self.len();
+    f.render_stateful_widget(table, table_area, &mut self.state);
+
+    if row_count > self.page_size {
+      let mut scrollbar_state =
+        ScrollbarState::new(row_count.saturating_sub(1)).position(self.state.selected().unwrap_or(0));
+      f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        rect.inner(&Margin { vertical: 1, horizontal: 0 }),
+        &mut scrollbar_state,
+      );
+    }
+  }
+}
+
+impl Navigable for HelpScreen {
+  fn len(&self) -> usize {
+    self.rows().len()
+  }
+
+  fn index(&self) -> usize {
+    self.state.selected().unwrap_or(0)
+  }
+
+  fn set_index(&mut self, index: usize) {
+    self.state.select(Some(index));
+  }
+
+  fn page_size(&self) -> usize {
+    self.page_size
+  }
+
+  fn wraps(&self) -> bool {
+    true
   }
 }
 
@@ -100,9 +258,70 @@ impl Component for HelpScreen {
     Ok(())
   }
 
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if self.show_help {
+      // While searching, plain letters go into the filter, so Ctrl-k and Ctrl-j stand in for k and j.
+      let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+      let plain = !self.searching && (key.modifiers - KeyModifiers::SHIFT).is_empty();
+      match key.code {
+        KeyCode::Up => self.scroll(ListNavDirection::Up),
+        KeyCode::Down => self.scroll(ListNavDirection::Down),
+        KeyCode::Char('k') if ctrl || plain => self.scroll(ListNavDirection::Up),
+        KeyCode::Char('j') if ctrl || plain => self.scroll(ListNavDirection::Down),
+        // Tab already moves focus, so the view switches on Ctrl-v instead.
+        KeyCode::Char('v') if ctrl => {
+          self.all_modes = !self.all_modes;
+          self.state.select(None);
+        },
+        KeyCode::Char('a') if ctrl => self.show_actions = !self.show_actions,
+        KeyCode::PageUp => self.scroll(ListNavDirection::PageUp),
+        KeyCode::PageDown => self.scroll(ListNavDirection::PageDown),
+        KeyCode::Char('/') if plain => self.searching = true,
+        // Enter keeps the filter but hands plain keys back to scrolling.
+        KeyCode::Enter if self.searching => self.searching = false,
+        // Esc clears the filter first, then closes the popup, since typed keys no longer reach the key bindings.
+        KeyCode::Esc if !self.searching && self.filter.value().is_empty() => self.show_help = false,
+        KeyCode::Esc => {
+          self.filter.reset();
+          self.searching = false;
+          self.state.select(None);
+        },
+        _ if self.searching => {
+          let changed = self.filter.handle_event(&crossterm::event::Event::Key(key)).is_some_and(|change| change.value);
+          if changed {
+            self.state.select(None);
+          }
+        },
+        _ => {},
+      }
+    }
+    Ok(None)
+  }
+
+  fn has_focus(&self) -> bool {
+    self.show_help
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    self.show_help.then_some(20)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    Ok(())
+  }
+
+  fn captures_text_input(&self) -> bool {
+    self.show_help
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     if action == Action::Engine(crate::actions::engine_actions::EngineAction::ToggleShowHelp) {
-      self.show_help = !self.show_help;
+      self.toggle();
     }
 
     Ok(None)
@@ -116,3 +335,240 @@ impl Component for HelpScreen {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{actions::EngineAction, config::parse_key_sequence};
+
+  fn help_screen() -> HelpScreen {
+    let mut config = Config::default();
+    let bindings = [("<q>", "Engine.Quit"), ("<l>", "Engine.ToggleShowHelp")]
+      .into_iter()
+      .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), serde_json::from_value(action.into()).unwrap()))
+      .collect();
+    config.keybindings.insert(Mode::MainMenu, bindings);
+    HelpScreen { config, ..HelpScreen::new(vec![Mode::MainMenu]) }
+  }
+
+  fn press(help: &mut HelpScreen, code: KeyCode) {
+    help.handle_key_events(KeyEvent::new(code, KeyModifiers::empty())).unwrap();
+  }
+
+  fn type_text(help: &mut HelpScreen, text: &str) {
+    for c in text.chars() {
+      press(help, KeyCode::Char(c));
+    }
+  }
+
+  #[test]
+  fn test_scrolling_wraps() {
+    let mut help = help_screen();
+    help.show_help = true;
+
+    press(&mut help, KeyCode::Up);
+    assert_eq!(help.state.selected(), Some(3));
+    press(&mut help, KeyCode::Down);
+    assert_eq!(help.state.selected(), Some(0));
+    press(&mut help, KeyCode::Up);
+    assert_eq!(help.state.selected(), Some(3));
+  }
+
+  fn bindings(help: &HelpScreen) -> Vec<String> {
+    help
+      .rows()
+      .into_iter()
+      .filter_map(|row| match row {
+        HelpRow::Binding { action, .. } => Some(action),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_filter_matches_keys_and_actions() -> Result<()> {
+    let mut help = help_screen();
+    help.update(EngineAction::ToggleShowHelp.into())?;
+
+    type_text(&mut help, "/quit");
+    assert_eq!(bindings(&help), vec!["Engine.Quit".to_string()]);
+
+    press(&mut help, KeyCode::Esc);
+    type_text(&mut help, "/l");
+    assert_eq!(bindings(&help), vec!["Engine.ToggleShowHelp".to_string()]);
+
+    press(&mut help, KeyCode::Char('z'));
+    assert!(help.rows().is_empty());
+
+    press(&mut help, KeyCode::Esc);
+    assert_eq!(bindings(&help).len(), 2);
+    assert!(help.show_help);
+
+    press(&mut help, KeyCode::Esc);
+    assert!(!help.show_help);
+    Ok(())
+  }
+
+  #[test]
+  fn test_filter_resets_when_reopened() -> Result<()> {
+    let mut help = help_screen();
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    type_text(&mut help, "/q");
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    help.update(EngineAction::ToggleShowHelp.into())?;
+
+    assert_eq!(help.filter.value(), "");
+    assert_eq!(bindings(&help).len(), 2);
+    Ok(())
+  }
+
+  #[test]
+  fn test_global_bindings_listed_once_first() {
+    let mut help = help_screen();
+    help.add_mode(Mode::Home);
+    help.config.keybindings.global.insert(parse_key_sequence("<ctrl-c>").unwrap(), EngineAction::Quit.into());
+    help.all_modes = true;
+
+    let headings: Vec<HelpRow> = help.rows().into_iter().filter(|row| matches!(row, HelpRow::Heading(_))).collect();
+    assert_eq!(headings, ["Global", "MainMenu", "Home"].map(|heading| HelpRow::Heading(heading.to_string())).to_vec());
+    assert_eq!(
+      help.rows()[2],
+      HelpRow::Binding {
+        keys: "ctrl-c".to_string(),
+        action: "Engine.Quit".to_string(),
+        description: "Quit".to_string()
+      }
+    );
+  }
+
+  #[test]
+  fn test_pages_and_scrollbar_on_a_small_screen() -> Result<()> {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut help = help_screen();
+    for c in 'a'..='p' {
+      let keys = parse_key_sequence(&format!("<ctrl-{c}>")).unwrap();
+      help.config.keybindings.get_mut(&Mode::MainMenu).unwrap().insert(keys, EngineAction::Refresh.into());
+    }
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    // The popup takes 24 of the 30 rows, and its margins, the filter, the header and the gaps take 12 of those.
+    let mut terminal = Terminal::new(TestBackend::new(60, 30))?;
+    terminal.draw(|f| help.draw(f, f.size()).unwrap())?;
+    assert_eq!((help.len(), help.page_size()), (20, 12));
+    let scrollbar: String = (0..30).map(|y| terminal.backend().buffer().get(53, y).symbol.clone()).collect();
+    assert!(scrollbar.contains('█'), "{scrollbar:?}");
+
+    press(&mut help, KeyCode::PageDown);
+    assert_eq!(help.state.selected(), Some(0));
+    press(&mut help, KeyCode::PageDown);
+    assert_eq!(help.state.selected(), Some(12));
+    press(&mut help, KeyCode::PageDown);
+    assert_eq!(help.state.selected(), Some(19));
+    help.handle_key_events(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL))?;
+    assert_eq!(help.state.selected(), Some(18));
+    help.handle_key_events(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL))?;
+    help.handle_key_events(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL))?;
+    assert_eq!(help.state.selected(), Some(0));
+    assert_eq!(help.filter.value(), "");
+    Ok(())
+  }
+
+  #[test]
+  fn test_lists_the_current_mode_until_toggled() -> Result<()> {
+    let mut help = help_screen();
+    help.add_mode(Mode::Home);
+    help
+      .config
+      .keybindings
+      .insert(Mode::Home, [(parse_key_sequence("<d>").unwrap(), HomeAction::DeleteTodo.into())].into());
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    let headings = |help: &HelpScreen| {
+      help
+        .rows()
+        .into_iter()
+        .filter_map(|row| match row {
+          HelpRow::Heading(heading) => Some(heading),
+          _ => None,
+        })
+        .collect::<Vec<_>>()
+    };
+    let title = |help: &HelpScreen| help.title().spans.iter().map(|span| span.content.to_string()).collect::<String>();
+
+    assert_eq!(headings(&help), ["MainMenu"]);
+    assert_eq!(title(&help), "Key Bindings - MainMenu (ctrl-v: all modes)");
+    help.on_mode_change(Mode::MainMenu, Mode::Home)?;
+    assert_eq!(headings(&help), ["Home"]);
+    assert_eq!(bindings(&help), ["Home.DeleteTodo"]);
+
+    help.handle_key_events(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL))?;
+    assert_eq!(headings(&help), ["MainMenu", "Home"]);
+    assert_eq!(title(&help), "Key Bindings - all modes (ctrl-v: Home only)");
+    assert_eq!(help.filter.value(), "");
+    Ok(())
+  }
+
+  #[test]
+  fn test_search_starts_with_slash_and_locks_on_enter() -> Result<()> {
+    let mut help = help_screen();
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    let title = |help: &HelpScreen| help.title().spans.iter().map(|span| span.content.to_string()).collect::<String>();
+
+    // Until `/` is pressed, j and k scroll rather than filter.
+    type_text(&mut help, "jj");
+    assert_eq!((help.filter.value(), help.state.selected()), ("", Some(1)));
+
+    type_text(&mut help, "/engine");
+    assert_eq!(title(&help), "Key Bindings - MainMenu (ctrl-v: all modes) - 2 matches");
+    type_text(&mut help, ".q");
+    assert_eq!(title(&help), "Key Bindings - MainMenu (ctrl-v: all modes) - 1 matches");
+
+    // Enter locks the filter in place, and j goes back to scrolling.
+    press(&mut help, KeyCode::Enter);
+    press(&mut help, KeyCode::Char('j'));
+    assert_eq!((help.filter.value(), help.state.selected()), ("engine.q", Some(0)));
+
+    press(&mut help, KeyCode::Esc);
+    assert_eq!(help.filter.value(), "");
+    assert!(help.show_help);
+    Ok(())
+  }
+
+  #[test]
+  fn test_describes_actions_and_shows_them_on_request() -> Result<()> {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut help = help_screen();
+    help.update(EngineAction::ToggleShowHelp.into())?;
+    let mut terminal = Terminal::new(TestBackend::new(80, 30))?;
+    let mut screen = |help: &mut HelpScreen| -> Result<String> {
+      terminal.draw(|f| help.draw(f, f.size()).unwrap())?;
+      let buffer = terminal.backend().buffer();
+      Ok(buffer.content().iter().map(|cell| cell.symbol.as_str()).collect())
+    };
+
+    let described = screen(&mut help)?;
+    assert!(described.contains("Open or close the help overlay"), "{described}");
+    assert!(!described.contains("Engine.ToggleShowHelp"));
+
+    help.handle_key_events(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL))?;
+    let with_actions = screen(&mut help)?;
+    assert!(with_actions.contains("Engine.ToggleShowHelp"), "{with_actions}");
+    assert!(with_actions.contains("Open or close the help"));
+
+    // Descriptions can be searched for too.
+    type_text(&mut help, "/overlay");
+    assert_eq!(bindings(&help), ["Engine.ToggleShowHelp"]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_keys_ignored_while_hidden() {
+    let mut help = help_screen();
+    press(&mut help, KeyCode::Down);
+    assert_eq!(help.state.selected(), None);
+    assert!(!help.has_focus());
+  }
+}
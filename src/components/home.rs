@@ -1,5 +1,6 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
+use chrono::{Local, NaiveDate};
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use lazy_static::lazy_static;
@@ -9,10 +10,22 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::trace;
 use tui_input::{backend::crossterm::EventHandler, Input};
 
-use super::{Component, Frame};
+use super::{
+  confirm_dialog::ConfirmDialog,
+  focused_border_style, key_hint,
+  layout_cache::LayoutCache,
+  list_todos::{priority_marker, tag_spans},
+  navigable::{ListCursor, Navigable},
+  Applicability, Component, Frame,
+};
 use crate::{
   actions::{Action, EngineAction, HomeAction, ListNavDirection},
-  config::{key_event_to_string, KeyBindings},
+  app,
+  config::{key_event_to_string, Config, KeyBindings},
+  due_date::ENGLISH,
+  resources::Occupancy,
+  tasks::TaskSet,
+  todos::{default_todos_path, Operator, Todo, TodoOp, TodoSort, TodoStore, UndoStack},
 };
 
 #[derive(Default, Copy, Clone, PartialEq, Eq)]
@@ -21,15 +34,62 @@ pub enum Mode {
   Normal,
   Insert,
   Processing,
+  /// Asking for a path to export the todos to, because the data directory can't be written.
+  Export,
 }
 
-lazy_static! {
-  pub static ref LIST_OPS: HashMap<&'static str, Action> = HashMap::from([
-    ("List", HomeAction::ScheduleIncrement.into()),
-    ("Add", HomeAction::ScheduleDecrement.into()),
-    ("Edit", HomeAction::ScheduleIncrement.into()),
-    ("Delete", HomeAction::ScheduleDecrement.into()),
-  ]);
+/// How many lines are drawn above the todos in the list pane.
+const HEADER_LINES: usize = 7;
+
+/// Submits the input or export path being typed. Typing takes every key before the keybindings see it, so this and
+/// [`CANCEL_KEY`] are hard-wired rather than bindable; the hints name them from here so they stay accurate.
+const SUBMIT_KEY: KeyCode = KeyCode::Enter;
+/// Leaves the input or export prompt without submitting it.
+const CANCEL_KEY: KeyCode = KeyCode::Esc;
+
+/// Frames of the spinner shown in the title while processing, one per render.
+pub const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The parts of [`Home`]'s UI state that are set aside while another app mode is active.
+#[derive(Default, Clone)]
+struct HomeSnapshot {
+  mode: Mode,
+  input: Input,
+  todo_op_index: usize,
+}
+
+/// Computes the number shown next to each of `count` lines.
+///
+/// Absolute numbers start at 1. Relative numbers give each line's distance from `selected`, with the selected line
+/// itself showing either 0 or its absolute number.
+pub fn line_numbers(count: usize, selected: usize, relative: bool, current_line_absolute: bool) -> Vec<usize> {
+  (0..count)
+    .map(|i| if !relative || (i == selected && current_line_absolute) { i + 1 } else { i.abs_diff(selected) })
+    .collect()
+}
+
+/// Whether `action` changes or saves the todos, including starting to type a new one, which has to wait until they
+/// have loaded.
+fn changes_todos(action: &HomeAction) -> bool {
+  matches!(
+    action,
+    HomeAction::EnterInsert
+      | HomeAction::CompleteInput(_)
+      | HomeAction::TrimEntries
+      | HomeAction::Save
+      | HomeAction::DeleteTodo
+      | HomeAction::RemoveTodo(_)
+      | HomeAction::DeleteTodos(_)
+      | HomeAction::ToggleTodos(_)
+      | HomeAction::Undo
+      | HomeAction::Redo
+      | HomeAction::MarkAllComplete
+      | HomeAction::ClearCompleted
+      | HomeAction::SetDue(..)
+      | HomeAction::SortBy(_)
+      | HomeAction::CycleSelectedPriority
+      | HomeAction::CyclePriority(_)
+  )
 }
 
 #[derive(Default)]
@@ -37,18 +97,43 @@ pub struct Home {
   pub counter: usize,
   pub app_ticker: usize,
   pub render_ticker: usize,
+  /// The spinner frame to show next, only advanced while processing or loading.
+  pub spinner_index: usize,
   pub mode: Mode,
   pub input: Input,
   pub action_tx: Option<UnboundedSender<Action>>,
   pub keymap: HashMap<Vec<KeyEvent>, Action>,
-  pub text: Vec<String>,
+  pub config: Config,
+  pub todos: TodoStore,
+  pub todos_path: PathBuf,
   pub last_events: Vec<KeyEvent>,
   pub todo_op_index: usize,
+  pub selected_todo: usize,
+  /// The first line shown in the list pane.
+  pub scroll_offset: usize,
+  /// How many lines fit in the list pane, as of the last draw.
+  pub viewport_height: usize,
+  pub relative_numbers: bool,
+  /// Mode to return to once processing finishes.
+  pub previous_mode: Mode,
+  /// Set while the data directory is read-only, which turns autosave off.
+  pub read_only: bool,
+  /// Set from starting to read the todos in the background until they arrive. Nothing may change or save the todos
+  /// meanwhile, or the ones on disk would be lost.
+  pub loading: bool,
+  pub export_path: Input,
+  pub focused: bool,
+  pub tasks: TaskSet,
+  pub history: UndoStack,
+  /// The question waiting on a y/n answer, which gets every key until it is answered.
+  confirm: Option<ConfirmDialog>,
+  snapshot: Option<HomeSnapshot>,
+  layout: LayoutCache,
 }
 
 impl Home {
   pub fn new() -> Self {
-    Self::default()
+    Self { todos_path: default_todos_path(), ..Self::default() }
   }
 
   pub fn set_keymap(&mut self, keymap: HashMap<Vec<KeyEvent>, Action>) {
@@ -64,30 +149,97 @@ impl Home {
   pub fn render_tick(&mut self) {
     log::debug!("Render Tick");
     self.render_ticker = self.render_ticker.saturating_add(1);
+    if self.mode == Mode::Processing || self.loading {
+      self.spinner_index = (self.spinner_index + 1) % SPINNER_FRAMES.len();
+    }
   }
 
+  /// Adds a todo, taking its due date from any `@phrase` in the text.
   pub fn add(&mut self, s: String) {
-    self.text.push(s)
+    let id = self.todos.add_from_input(&s, Local::now().date_naive()).id;
+    self.history.record(TodoOp::Added(id));
+    // New todos go at the end, so scroll down to show them.
+    self.scroll_offset = self.max_scroll();
+  }
+
+  /// Whether the input is as long as the config allows.
+  fn input_at_limit(&self) -> bool {
+    self.config.max_input_len.is_some_and(|max| self.input.value().chars().count() >= max)
+  }
+
+  /// The due date the current input would resolve to if it were submitted now.
+  fn input_due_date(&self) -> Option<NaiveDate> {
+    ENGLISH.extract(self.input.value(), Local::now().date_naive()).1
   }
 
   pub fn schedule_increment(&mut self, i: usize) {
-    let tx = self.action_tx.clone().unwrap();
-    tokio::spawn(async move {
-      tx.send(HomeAction::EnterProcessing.into()).unwrap();
-      tx.send(HomeAction::Increment(i).into()).unwrap();
-      tx.send(HomeAction::ExitProcessing.into()).unwrap();
-    });
+    self.schedule(HomeAction::Increment(i));
   }
 
   pub fn schedule_decrement(&mut self, i: usize) {
-    let tx = self.action_tx.clone().unwrap();
-    tokio::spawn(async move {
-      tx.send(HomeAction::EnterProcessing.into()).unwrap();
-      tx.send(HomeAction::Decrement(i).into()).unwrap();
-      tx.send(HomeAction::ExitProcessing.into()).unwrap();
+    self.schedule(HomeAction::Decrement(i));
+  }
+
+  /// Sends `action` from a background task, wrapped in entering and leaving the processing mode.
+  fn schedule(&mut self, action: HomeAction) {
+    let Some(tx) = self.action_tx.clone() else {
+      return;
+    };
+    self.tasks.spawn(async move {
+      for action in [HomeAction::EnterProcessing, action, HomeAction::ExitProcessing] {
+        // The receiver is gone once the app has quit, and there's nobody left to tell.
+        if let Err(e) = tx.send(action.into()) {
+          log::debug!("Dropping {:?}: the app is shutting down", e.0);
+          return;
+        }
+      }
     });
   }
 
+  /// Reads the todos from a background task, which hands them over with [`HomeAction::TodosLoaded`]. Without a way
+  /// to send that, they are read straight away instead.
+  fn load_todos(&mut self) {
+    let Some(tx) = self.action_tx.clone() else {
+      self.todos = TodoStore::load(&self.todos_path);
+      return;
+    };
+    self.loading = true;
+    let path = self.todos_path.clone();
+    self.tasks.spawn(async move {
+      let todos = match tokio::task::spawn_blocking(move || TodoStore::load(&path)).await {
+        Ok(store) => store,
+        Err(e) => {
+          log::error!("Failed to load todos: {e}");
+          TodoStore::unreadable()
+        },
+      };
+      if let Err(e) = tx.send(HomeAction::TodosLoaded(todos).into()) {
+        log::debug!("Dropping {:?}: the app is shutting down", e.0);
+      }
+    });
+  }
+
+  /// Forgets the cached layout and reads the todos from disk again, in case something else changed them, unless that
+  /// would throw away unsaved changes.
+  fn refresh(&mut self) -> Option<Action> {
+    self.layout.invalidate();
+    if self.loading {
+      return None;
+    }
+    if self.todos.is_dirty() {
+      return Some(EngineAction::Notify("Kept the unsaved todos instead of reloading them".to_string()).into());
+    }
+    self.load_todos();
+    None
+  }
+
+  /// Takes over the todos read in the background.
+  fn todos_loaded(&mut self, todos: TodoStore) {
+    self.todos = todos;
+    self.loading = false;
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
   pub fn increment(&mut self, i: usize) {
     self.counter = self.counter.saturating_add(i);
   }
@@ -96,16 +248,219 @@ impl Home {
     self.counter = self.counter.saturating_sub(i);
   }
 
-  pub fn navigate_list(&mut self, dir: ListNavDirection) {
+  /// Trims the whitespace around every title and drops the todos left empty, as one change that can be undone.
+  /// Returns a notification saying how many were changed.
+  pub fn trim_entries(&mut self) -> Option<Action> {
+    let report = self.todos.trim_titles();
+    let message = format!("Trimmed {} entries and removed {} empty ones", report.trimmed.len(), report.removed.len());
+    log::info!("{message}");
+    if let Some(op) = report.into_op() {
+      self.history.record(op);
+    }
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+    Some(EngineAction::Notify(message).into())
+  }
+
+  /// Asks whether to delete the selected todo.
+  pub fn confirm_delete(&mut self) {
+    if let Some(todo) = self.todos.todos().get(self.selected_todo) {
+      self.confirm = Some(ConfirmDialog::new(
+        format!("Delete '{}'? (y/n)", todo.title),
+        HomeAction::RemoveTodo(todo.id).into(),
+        // Without the title, since notifications go into the action history and exported action logs unredacted.
+        EngineAction::Notify("Kept the todo".to_string()).into(),
+      ));
+    }
+  }
+
+  pub fn remove(&mut self, id: u32) {
+    if let Some(todo) = self.todos.remove(id) {
+      log::info!("Deleted todo {}", todo.id);
+      self.history.record(TodoOp::Removed(todo));
+    }
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
+  /// Applies `operator` to `count` todos from the selected one down, stopping at the end of the list, as one change
+  /// that can be undone.
+  pub fn operate(&mut self, operator: Operator, count: usize) {
+    // Home lists every todo, so the range counts through all of them.
+    let view: Vec<u32> = self.todos.todos().iter().map(|todo| todo.id).collect();
+    if let Some(op) =
+      operator.apply(&mut self.todos, &view, self.selected_todo..self.selected_todo.saturating_add(count))
+    {
+      self.history.record(op);
+    }
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
+  /// Sets or clears a todo's due date, as a change that can be undone.
+  pub fn set_due(&mut self, id: u32, due: Option<NaiveDate>) {
+    if let Some(old_due) = self.todos.set_due(id, due).filter(|old_due| *old_due != due) {
+      self.history.record(TodoOp::DueChanged { id, old_due });
+    }
+  }
+
+  /// Raises a todo's priority a level, going from High back round to Low, as a change that can be undone.
+  pub fn cycle_priority(&mut self, id: u32) {
+    let Some(priority) = self.todos.todos().iter().find(|todo| todo.id == id).map(|todo| todo.priority.next()) else {
+      return;
+    };
+    if let Some(old_priority) = self.todos.set_priority(id, priority) {
+      self.history.record(TodoOp::PriorityChanged { id, old_priority });
+    }
+  }
+
+  /// Reorders the todos, keeping the same todo selected.
+  pub fn sort(&mut self, order: TodoSort) {
+    let selected = self.todos.todos().get(self.selected_todo).map(|todo| todo.id);
+    self.todos.sort(order);
+    if let Some(index) = selected.and_then(|id| self.todos.todos().iter().position(|todo| todo.id == id)) {
+      self.selected_todo = index;
+      self.scroll_to_selected();
+    }
+  }
+
+  /// Marks every todo completed, as one change that can be undone.
+  pub fn mark_all_complete(&mut self) {
+    let ids = self.todos.complete_all();
+    log::info!("Marked {} todos complete", ids.len());
+    if !ids.is_empty() {
+      self.history.record(TodoOp::Compound(ids.into_iter().map(TodoOp::ToggledCompleted).collect()));
+    }
+  }
+
+  /// Deletes every completed todo, as one change that can be undone.
+  pub fn clear_completed(&mut self) {
+    let removed = self.todos.clear_completed();
+    log::info!("Cleared {} completed todos", removed.len());
+    if !removed.is_empty() {
+      self.history.record(TodoOp::Compound(removed.into_iter().map(TodoOp::Removed).collect()));
+    }
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
+  pub fn undo(&mut self) {
+    self.history.undo(&mut self.todos);
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
+  pub fn redo(&mut self) {
+    self.history.redo(&mut self.todos);
+    self.selected_todo = self.selected_todo.min(self.todos.len().saturating_sub(1));
+  }
+
+  pub fn navigate_list(&mut self, dir: ListNavDirection, count: usize) {
     if self.mode == Mode::Normal {
-      match (dir, self.todo_op_index) {
-        (ListNavDirection::Left, 0) => self.todo_op_index = LIST_OPS.len() - 1,
-        (ListNavDirection::Left, _) => self.todo_op_index -= 1,
-        (ListNavDirection::Right, _) => {
-          self.todo_op_index = if self.todo_op_index == LIST_OPS.len() - 1 { 0 } else { self.todo_op_index + 1 }
+      match dir {
+        ListNavDirection::Left | ListNavDirection::Right => {
+          ListCursor { index: &mut self.todo_op_index, len: self.config.tabs.len(), wraps: true }.navigate(dir, count)
         },
-        _ => {},
-      };
+        ListNavDirection::Up | ListNavDirection::Down | ListNavDirection::First | ListNavDirection::Last => {
+          // Jumping to the first todo goes right back to the top, header and all.
+          if dir == ListNavDirection::First {
+            self.scroll_offset = 0;
+          }
+          ListCursor { index: &mut self.selected_todo, len: self.todos.len(), wraps: false }.navigate(dir, count);
+          self.scroll_to_selected();
+        },
+        ListNavDirection::PageUp | ListNavDirection::PageDown => {
+          // Any more pages than there are todos would only scroll past the end.
+          for _ in 0..count.min(self.todos.len()) {
+            self.scroll_page(dir.clone());
+          }
+        },
+      }
+    }
+  }
+
+  /// Scrolls a screenful up or down, taking the selection along so it stays on screen. Up and PageUp both scroll up, and
+  /// Down and PageDown both scroll down.
+  pub fn scroll_page(&mut self, dir: ListNavDirection) {
+    if self.mode != Mode::Normal || self.todos.is_empty() {
+      return;
+    }
+    let page = self.viewport_height.max(1);
+    match dir {
+      ListNavDirection::Up | ListNavDirection::PageUp => {
+        self.scroll_offset = self.scroll_offset.saturating_sub(page);
+        self.selected_todo = self.selected_todo.saturating_sub(page);
+      },
+      ListNavDirection::Down | ListNavDirection::PageDown => {
+        self.scroll_offset = (self.scroll_offset + page).min(self.max_scroll());
+        self.selected_todo = (self.selected_todo + page).min(self.todos.len() - 1);
+      },
+      _ => return,
+    }
+    self.scroll_to_selected();
+  }
+
+  /// The furthest the list pane can scroll while still being filled.
+  fn max_scroll(&self) -> usize {
+    (HEADER_LINES + self.todos.len()).saturating_sub(self.viewport_height)
+  }
+
+  /// Scrolls just far enough for the selected todo to be on screen.
+  fn scroll_to_selected(&mut self) {
+    let line = HEADER_LINES + self.selected_todo;
+    if line < self.scroll_offset {
+      self.scroll_offset = line;
+    } else if self.viewport_height > 0 && line >= self.scroll_offset + self.viewport_height {
+      self.scroll_offset = line + 1 - self.viewport_height;
+    }
+  }
+
+  /// Saves the todos automatically if they changed, unless storage is read-only or the file they should have come
+  /// from couldn't be read, which only an explicit save may replace.
+  fn save_todos(&mut self) {
+    if self.read_only || self.loading || !self.todos.is_dirty() {
+      return;
+    }
+    if self.todos.load_failed() {
+      log::warn!("Not saving over {}, which couldn't be read", self.todos_path.display());
+      return;
+    }
+    if let Err(e) = self.todos.save(&self.todos_path) {
+      error!("Failed to save todos to {}: {:?}", self.todos_path.display(), e);
+    }
+  }
+
+  /// Handles an explicit save, which asks for an export path instead when storage is read-only.
+  fn save(&mut self) -> Option<Action> {
+    if self.read_only {
+      self.export_path = Input::default();
+      self.mode = Mode::Export;
+      return None;
+    }
+    Some(match self.todos.save(&self.todos_path) {
+      Ok(()) => EngineAction::Notify(format!("Saved todos to {}", self.todos_path.display())).into(),
+      Err(e) => EngineAction::Error(format!("Failed to save todos: {e}")).into(),
+    })
+  }
+
+  fn export(&mut self) -> Action {
+    self.mode = Mode::Normal;
+    let path = PathBuf::from(self.export_path.value().trim());
+    match self.todos.save(&path) {
+      Ok(()) => EngineAction::Notify(format!("Exported todos to {}", path.display())).into(),
+      Err(e) => EngineAction::Error(format!("Failed to export todos to {}: {e}", path.display())).into(),
+    }
+  }
+
+  fn save_snapshot(&mut self) {
+    self.snapshot =
+      Some(HomeSnapshot { mode: self.mode, input: self.input.clone(), todo_op_index: self.todo_op_index });
+    // Stop capturing keystrokes while Home isn't on screen.
+    if matches!(self.mode, Mode::Insert | Mode::Export) {
+      self.mode = Mode::Normal;
+    }
+  }
+
+  fn restore_snapshot(&mut self) {
+    if let Some(snapshot) = self.snapshot.take() {
+      self.mode = snapshot.mode;
+      self.input = snapshot.input;
+      self.todo_op_index = snapshot.todo_op_index;
     }
   }
 
@@ -116,7 +471,7 @@ impl Home {
       .constraints([Constraint::Min(0), Constraint::Length(3)])
       .split(f.size());
 
-    let tabs = Tabs::new(vec!["List", "View", "Edit", "Delete"])
+    let tabs = Tabs::new(self.config.tabs.iter().map(|tab| tab.label.as_str()).collect())
       .block(Block::default().title("List operations").borders(Borders::TOP))
       .style(Style::default().white())
       .highlight_style(Style::default().yellow().on_blue().underlined())
@@ -129,7 +484,9 @@ impl Home {
 
 impl Component for Home {
   fn register_config_handler(&mut self, config: crate::config::Config) -> Result<()> {
-    self.set_keymap(config.keybindings.get(&crate::app::Mode::Home).unwrap().clone());
+    self.set_keymap(config.keymap(crate::app::Mode::Home));
+    self.relative_numbers = config.line_numbers.relative;
+    self.config = config;
 
     Ok(())
   }
@@ -139,13 +496,99 @@ impl Component for Home {
     Ok(())
   }
 
+  fn register_task_handler(&mut self, tasks: TaskSet) -> Result<()> {
+    self.tasks = tasks;
+    Ok(())
+  }
+
+  fn init(&mut self) -> Result<()> {
+    self.load_todos();
+    Ok(())
+  }
+
+  fn captures_text_input(&self) -> bool {
+    matches!(self.mode, Mode::Insert | Mode::Export) || self.confirm.is_some()
+  }
+
+  fn can_handle(&self, action: &Action) -> Applicability {
+    match action {
+      Action::Home(action) if self.loading && changes_todos(action) => {
+        Applicability::NotApplicable("Still loading the todos".to_string())
+      },
+      Action::Home(HomeAction::NavigateList(dir, _))
+        if !matches!(dir, ListNavDirection::Left | ListNavDirection::Right) && self.todos.is_empty() =>
+      {
+        Applicability::NotApplicable("No todos to select".to_string())
+      },
+      Action::Home(HomeAction::DeleteTodo | HomeAction::DeleteTodos(_)) if self.todos.is_empty() => {
+        Applicability::NotApplicable("No todo to delete".to_string())
+      },
+      Action::Home(HomeAction::ToggleTodos(_)) if self.todos.is_empty() => {
+        Applicability::NotApplicable("No todo to complete".to_string())
+      },
+      Action::Home(HomeAction::MarkAllComplete) if self.todos.todos().iter().all(|todo| todo.is_completed) => {
+        Applicability::NotApplicable("Every todo is already complete".to_string())
+      },
+      Action::Home(HomeAction::ClearCompleted) if !self.todos.todos().iter().any(|todo| todo.is_completed) => {
+        Applicability::NotApplicable("No completed todos to clear".to_string())
+      },
+      Action::Home(HomeAction::CycleSelectedPriority) if self.todos.is_empty() => {
+        Applicability::NotApplicable("No todo selected".to_string())
+      },
+      Action::Home(HomeAction::SetDue(id, _) | HomeAction::CyclePriority(id))
+        if !self.todos.todos().iter().any(|todo| todo.id == *id) =>
+      {
+        Applicability::NotApplicable(format!("No todo with id {id}"))
+      },
+      Action::Home(HomeAction::Undo) if !self.history.can_undo() => {
+        Applicability::NotApplicable("Nothing to undo".to_string())
+      },
+      Action::Home(HomeAction::Redo) if !self.history.can_redo() => {
+        Applicability::NotApplicable("Nothing to redo".to_string())
+      },
+      Action::Home(_) => Applicability::Handled,
+      _ => Applicability::Ignored,
+    }
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    Some(0)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn on_enter(&mut self) -> Result<()> {
+    self.restore_snapshot();
+    Ok(())
+  }
+
+  fn on_exit(&mut self) -> Result<()> {
+    self.save_snapshot();
+    self.save_todos();
+    Ok(())
+  }
+
+  fn prepare_quit(&mut self) -> Result<bool> {
+    self.save_todos();
+    Ok(self.todos.is_dirty())
+  }
+
   fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if let Some(dialog) = &mut self.confirm {
+      let answer = dialog.handle_key_events(key)?;
+      if answer.is_some() {
+        self.confirm = None;
+      }
+      return Ok(answer);
+    }
     self.last_events.push(key);
     let action = match self.mode {
       Mode::Normal | Mode::Processing => return Ok(None),
       Mode::Insert => match key.code {
-        KeyCode::Esc => HomeAction::EnterNormal.into(),
-        KeyCode::Enter => {
+        CANCEL_KEY => HomeAction::EnterNormal.into(),
+        SUBMIT_KEY => {
           if let Some(sender) = &self.action_tx {
             if let Err(e) = sender.send(HomeAction::CompleteInput(self.input.value().to_string()).into()) {
               error!("Failed to send action: {:?}", e);
@@ -154,7 +597,19 @@ impl Component for Home {
           HomeAction::EnterNormal.into()
         },
         _ => {
+          let before = self.input.clone();
           self.input.handle_event(&crossterm::event::Event::Key(key));
+          if self.config.max_input_len.is_some_and(|max| self.input.value().chars().count() > max) {
+            self.input = before;
+          }
+          HomeAction::Update.into()
+        },
+      },
+      Mode::Export => match key.code {
+        CANCEL_KEY => HomeAction::EnterNormal.into(),
+        SUBMIT_KEY if !self.export_path.value().trim().is_empty() => self.export(),
+        _ => {
+          self.export_path.handle_event(&crossterm::event::Event::Key(key));
           HomeAction::Update.into()
         },
       },
@@ -167,11 +622,15 @@ impl Component for Home {
       Action::Engine(e) => match e {
         EngineAction::Tick => self.tick(),
         EngineAction::Render => self.render_tick(),
+        EngineAction::StorageReadOnly(read_only) => self.read_only = read_only,
+        EngineAction::Resize(..) => self.layout.invalidate(),
+        EngineAction::Refresh => return Ok(self.refresh()),
         _ => (),
       },
+      Action::Home(h) if self.loading && changes_todos(&h) => log::debug!("Ignoring {h} while the todos load"),
       Action::Home(h) => match h {
-        HomeAction::ScheduleIncrement => self.schedule_increment(1),
-        HomeAction::ScheduleDecrement => self.schedule_decrement(1),
+        HomeAction::ScheduleIncrement(i) => self.schedule_increment(i),
+        HomeAction::ScheduleDecrement(i) => self.schedule_decrement(i),
         HomeAction::Increment(i) => self.increment(i),
         HomeAction::Decrement(i) => self.decrement(i),
         HomeAction::CompleteInput(s) => self.add(s),
@@ -182,87 +641,188 @@ impl Component for Home {
           self.mode = Mode::Insert;
         },
         HomeAction::EnterProcessing => {
+          if self.mode != Mode::Processing {
+            self.previous_mode = self.mode;
+          }
           self.mode = Mode::Processing;
         },
-        HomeAction::NavigateList(dir) => {
-          self.navigate_list(dir);
+        HomeAction::NavigateList(dir, count) => {
+          self.navigate_list(dir, count);
         },
-        HomeAction::ExitProcessing => {
-          // TODO: Make this go to previous mode instead
-          self.mode = Mode::Normal;
+        HomeAction::ScrollPage(dir) => self.scroll_page(dir),
+        HomeAction::ToggleRelativeNumbers => {
+          self.relative_numbers = !self.relative_numbers;
+        },
+        HomeAction::TrimEntries => return Ok(self.trim_entries()),
+        HomeAction::Save => return Ok(self.save()),
+        HomeAction::DeleteTodo => self.confirm_delete(),
+        HomeAction::RemoveTodo(id) => self.remove(id),
+        HomeAction::DeleteTodos(count) => self.operate(Operator::Delete, count),
+        HomeAction::ToggleTodos(count) => self.operate(Operator::ToggleCompleted, count),
+        HomeAction::Undo => self.undo(),
+        HomeAction::Redo => self.redo(),
+        HomeAction::MarkAllComplete => self.mark_all_complete(),
+        HomeAction::ClearCompleted => self.clear_completed(),
+        HomeAction::SetDue(id, due) => self.set_due(id, due),
+        HomeAction::SortBy(order) => self.sort(order),
+        HomeAction::CycleSelectedPriority => {
+          return Ok(self.todos.todos().get(self.selected_todo).map(|todo| HomeAction::CyclePriority(todo.id).into()))
+        },
+        HomeAction::CyclePriority(id) => self.cycle_priority(id),
+        HomeAction::TodosLoaded(todos) => self.todos_loaded(todos),
+        HomeAction::ExitProcessing if self.mode == Mode::Processing => {
+          self.mode = self.previous_mode;
         },
         _ => (),
       },
+      _ => (),
     }
     Ok(None)
   }
 
+  fn hinted_actions(&self) -> Vec<(app::Mode, Action)> {
+    vec![(app::Mode::Home, HomeAction::EnterInsert.into())]
+  }
+
+  fn occupancy(&self) -> Vec<Occupancy> {
+    vec![Occupancy::new("todos", self.todos.len(), None)]
+  }
+
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
-    let rects = Layout::default().constraints([Constraint::Percentage(100), Constraint::Min(3)].as_ref()).split(rect);
-
-    let mut text: Vec<Line> = self.text.clone().iter().map(|l| Line::from(l.clone())).collect();
-    text.insert(0, "".into());
-    text.insert(0, "Type into input and hit enter to display here".dim().into());
-    text.insert(0, "".into());
-    text.insert(0, format!("Render Ticker: {}", self.render_ticker).into());
-    text.insert(0, format!("App Ticker: {}", self.app_ticker).into());
-    text.insert(0, format!("Counter: {}", self.counter).into());
-    text.insert(0, "".into());
-    text.insert(
-      0,
-      Line::from(vec![
-        "Press ".into(),
-        Span::styled("j", Style::default().fg(Color::Red)),
-        " or ".into(),
-        Span::styled("k", Style::default().fg(Color::Red)),
-        " to ".into(),
-        Span::styled("increment", Style::default().fg(Color::Yellow)),
-        " or ".into(),
-        Span::styled("decrement", Style::default().fg(Color::Yellow)),
-        ".".into(),
-      ]),
+    let binding = |action: HomeAction| self.config.binding_for(app::Mode::Home, &action.into());
+    let (submit, cancel) = (key_event_to_string(&SUBMIT_KEY.into()), key_event_to_string(&CANCEL_KEY.into()));
+    let theme = self.config.theme;
+    let rects =
+      self.layout.split(&Layout::default().constraints([Constraint::Percentage(100), Constraint::Min(3)]), rect);
+
+    let numbers = line_numbers(
+      self.todos.len(),
+      self.selected_todo,
+      self.relative_numbers,
+      self.config.line_numbers.current_line_absolute,
     );
-    text.insert(0, "".into());
+    let number_width = numbers.iter().max().map_or(1, |n| n.to_string().len());
+    let today = Local::now().date_naive();
+    let mut text: Vec<Line> = vec![
+      "".into(),
+      format!("Counter: {}", self.counter).into(),
+      format!("App Ticker: {}", self.app_ticker).into(),
+      format!("Render Ticker: {}", self.render_ticker).into(),
+      "".into(),
+      match self.loading {
+        true => "Loading todos…".dim().into(),
+        false => "Type into input and hit enter to display here".dim().into(),
+      },
+      "".into(),
+    ];
+    debug_assert_eq!(text.len(), HEADER_LINES);
+    text.extend(self.todos.todos().iter().zip(numbers).enumerate().map(|(i, (t, n))| {
+      let mut spans = vec![format!("{n:>number_width$} ").dim(), priority_marker(t), t.title.clone().into()];
+      spans.extend(tag_spans(t));
+      if let Some(due) = t.due {
+        spans.push(match t.is_overdue(today) {
+          true => Span::styled(format!(" (due {due})"), theme.error_style()),
+          false => format!(" (due {due})").dim(),
+        });
+      }
+      let mut line = Line::from(spans);
+      if i == self.selected_todo {
+        line.patch_style(Style::default().add_modifier(Modifier::REVERSED));
+      }
+      line
+    }));
 
+    self.viewport_height = rects[0].height.saturating_sub(2) as usize;
+    self.scroll_offset = self.scroll_offset.min(self.max_scroll());
     f.render_widget(
       Paragraph::new(text)
+        .scroll((self.scroll_offset as u16, 0))
         .block(
           Block::default()
-            .title("ratatui async template")
+            .title(match self.mode {
+              _ if self.loading => format!("{} ratatui async template", SPINNER_FRAMES[self.spinner_index]),
+              Mode::Processing => format!("{} ratatui async template", SPINNER_FRAMES[self.spinner_index]),
+              _ => "ratatui async template".to_string(),
+            })
             .title_alignment(Alignment::Center)
             .borders(Borders::ALL)
             .border_style(match self.mode {
-              Mode::Processing => Style::default().fg(Color::Yellow),
+              Mode::Processing => theme.highlight_style(),
+              _ if self.focused => focused_border_style(&self.config, app::Mode::Home),
               _ => Style::default(),
             })
             .border_type(BorderType::Rounded),
         )
-        .style(Style::default().fg(Color::Cyan))
+        .style(theme.primary_style())
         .alignment(Alignment::Center),
       rects[0],
     );
+    let mut scrollbar_state = ScrollbarState::new(self.max_scroll()).position(self.scroll_offset);
+    f.render_stateful_widget(
+      Scrollbar::new(ScrollbarOrientation::VerticalRight).style(theme.border_style()),
+      rects[0].inner(&Margin { vertical: 1, horizontal: 0 }),
+      &mut scrollbar_state,
+    );
     let width = rects[1].width.max(3) - 3; // keep 2 for borders and 1 for cursor
     let scroll = self.input.visual_scroll(width as usize);
+    let mut input_block = Block::default().borders(Borders::ALL);
+    if let Some(due) = self.input_due_date() {
+      input_block = input_block.title(
+        ratatui::widgets::block::Title::from(format!(" due {} ", due.format("%a %Y-%m-%d")).green())
+          .alignment(Alignment::Right),
+      );
+    }
+    if self.mode == Mode::Insert {
+      let count = self.input.value().chars().count();
+      let (count, style) = match self.config.max_input_len {
+        Some(max) if self.input_at_limit() => (format!(" {count}/{max} (limit reached) "), theme.error_style()),
+        Some(max) => (format!(" {count}/{max} "), Style::default()),
+        None => (format!(" {count} chars "), Style::default()),
+      };
+      // Bottom left, since the last keys pressed are shown along the right of the bottom row.
+      input_block = input_block.title(
+        ratatui::widgets::block::Title::from(Span::styled(count, style))
+          .position(ratatui::widgets::block::Position::Bottom)
+          .alignment(Alignment::Left),
+      );
+    }
     let input = Paragraph::new(self.input.value())
       .style(match self.mode {
-        Mode::Insert => Style::default().fg(Color::Yellow),
+        Mode::Insert => theme.highlight_style(),
         _ => Style::default(),
       })
       .scroll((0, scroll as u16))
-      .block(Block::default().borders(Borders::ALL).title(Line::from(vec![
+      .block(input_block.title(Line::from(vec![
         Span::raw("Enter Input Mode "),
-        Span::styled("(Press ", Style::default().fg(Color::DarkGray)),
-        Span::styled("/", Style::default().add_modifier(Modifier::BOLD).fg(Color::Gray)),
-        Span::styled(" to start, ", Style::default().fg(Color::DarkGray)),
-        Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD).fg(Color::Gray)),
-        Span::styled(" to save and exit, ", Style::default().fg(Color::DarkGray)),
-        Span::styled("ESC", Style::default().add_modifier(Modifier::BOLD).fg(Color::Gray)),
-        Span::styled(" to exit without saving)", Style::default().fg(Color::DarkGray)),
+        Span::styled("(Press ", theme.dim_style()),
+        key_hint(binding(HomeAction::EnterInsert), theme.accent_style().add_modifier(Modifier::BOLD)),
+        Span::styled(" to start, ", theme.dim_style()),
+        Span::styled(submit.as_str(), theme.accent_style().add_modifier(Modifier::BOLD)),
+        Span::styled(" to save and exit, ", theme.dim_style()),
+        Span::styled(cancel.as_str(), theme.accent_style().add_modifier(Modifier::BOLD)),
+        Span::styled(" to exit without saving)", theme.dim_style()),
       ])));
     f.render_widget(input, rects[1]);
     if self.mode == Mode::Insert {
       f.set_cursor((rects[1].x + 1 + self.input.cursor() as u16).min(rects[1].x + rects[1].width - 2), rects[1].y + 1)
     }
+    if self.mode == Mode::Export {
+      let prompt = Paragraph::new(self.export_path.value())
+        .style(theme.highlight_style())
+        .scroll((0, self.export_path.visual_scroll(width as usize) as u16))
+        .block(
+          Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.highlight_style())
+            .title(format!("Storage is read-only. Export todos to ({submit} to write, {cancel} to cancel)")),
+        );
+      f.render_widget(Clear, rects[1]);
+      f.render_widget(prompt, rects[1]);
+      f.set_cursor(
+        (rects[1].x + 1 + self.export_path.visual_cursor() as u16).min(rects[1].x + rects[1].width - 2),
+        rects[1].y + 1,
+      )
+    }
 
     f.render_widget(
       Block::default()
@@ -279,6 +839,467 @@ impl Component for Home {
 
     self.draw_menu(f);
 
+    if let Some(dialog) = &mut self.confirm {
+      dialog.draw(f, rect)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use ratatui::{backend::TestBackend, Terminal};
+
+  use super::*;
+  use crate::{config::parse_key_sequence, todos::Priority};
+
+  fn render_to_string(home: &mut Home) -> Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 20))?;
+    terminal.draw(|f| home.draw(f, f.size()).unwrap())?;
+    Ok(terminal.backend().buffer().content().iter().map(|c| c.symbol.as_str()).collect())
+  }
+
+  #[test]
+  fn test_input_hint_follows_binding() -> Result<()> {
+    let mut home = Home::new();
+    let mut config = Config::default();
+    config
+      .keybindings
+      .insert(app::Mode::Home, HashMap::from([(parse_key_sequence("<i>").unwrap(), HomeAction::EnterInsert.into())]));
+    home.register_config_handler(config)?;
+    assert!(render_to_string(&mut home)?.contains("(Press i to start"));
+
+    // Submitting and cancelling are hard-wired, so rebinding leaves them named as they are while typing.
+    home.update(HomeAction::EnterInsert.into())?;
+    assert!(render_to_string(&mut home)?.contains("(Press i to start, enter to save and exit, esc to exit without"));
+    assert_eq!(home.handle_key_events(KeyEvent::from(CANCEL_KEY))?, Some(HomeAction::EnterNormal.into()));
+    home.update(HomeAction::EnterNormal.into())?;
+
+    let mut config = Config::default();
+    config.keybindings.insert(app::Mode::Home, HashMap::new());
+    home.register_config_handler(config)?;
+    assert!(render_to_string(&mut home)?.contains("(Press (unbound) to start"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_mode_switch_restores_home_state() -> Result<()> {
+    let todos_path =
+      std::env::temp_dir().join(format!("{}-home-{}", env!("CARGO_PKG_NAME"), std::process::id())).join("todos.json");
+    let mut home = Home { todos_path: todos_path.clone(), ..Home::default() };
+    home.todo_op_index = 2;
+    home.mode = Mode::Insert;
+    home.input = Input::new("half-typed todo".to_string());
+    home.add("saved on exit".to_string());
+
+    home.on_exit()?;
+    assert!(home.mode == Mode::Normal);
+    assert_eq!(TodoStore::load(&todos_path), home.todos);
+
+    home.on_enter()?;
+    assert_eq!(home.todo_op_index, 2);
+    assert!(home.mode == Mode::Insert);
+    assert_eq!(home.input.value(), "half-typed todo");
+
+    std::fs::remove_dir_all(todos_path.parent().unwrap())?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_read_only_save_prompts_for_export() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-home-export-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    let mut home = Home { todos_path: dir.join("todos.json"), ..Home::default() };
+    home.add("keep me".to_string());
+    home.update(EngineAction::StorageReadOnly(true).into())?;
+
+    assert!(home.prepare_quit()?, "quitting would lose the new todo");
+    assert!(!home.todos_path.exists(), "autosave is off while read-only");
+
+    assert_eq!(home.update(HomeAction::Save.into())?, None);
+    assert!(home.mode == Mode::Export);
+    let export_path = dir.join("export.json");
+    for c in export_path.to_str().unwrap().chars() {
+      home.handle_key_events(KeyEvent::from(KeyCode::Char(c)))?;
+    }
+    let action = home.handle_key_events(KeyEvent::from(KeyCode::Enter))?;
+
+    assert!(matches!(action, Some(Action::Engine(EngineAction::Notify(_)))));
+    assert!(home.mode == Mode::Normal);
+    assert_eq!(TodoStore::load(&export_path), home.todos);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_autosave_skips_unchanged_todos() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-home-clean-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    let todos_path = dir.join("todos.json");
+    let mut home = Home { todos_path: todos_path.clone(), ..Home::default() };
+    home.init()?;
+
+    home.on_exit()?;
+    assert!(!home.prepare_quit()?);
+    assert!(!todos_path.exists(), "nothing changed, so nothing is written");
+
+    home.add("milk".to_string());
+    home.on_exit()?;
+    assert_eq!(TodoStore::load(&todos_path).len(), 1);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_unreadable_todos_are_not_saved_over() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-home-corrupt-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    let todos_path = dir.join("todos.json");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&todos_path, "not json")?;
+    let mut home = Home { todos_path: todos_path.clone(), ..Home::default() };
+    home.init()?;
+    assert!(home.todos.is_empty());
+
+    home.on_exit()?;
+    assert_eq!(std::fs::read(&todos_path)?, b"not json");
+    home.add("typed after the failed load".to_string());
+    assert!(home.prepare_quit()?, "quitting would lose the new todo");
+    home.on_exit()?;
+    assert_eq!(std::fs::read(&todos_path)?, b"not json");
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_relative_line_numbers() {
+    assert_eq!(line_numbers(5, 2, false, true), vec![1, 2, 3, 4, 5]);
+    assert_eq!(line_numbers(5, 2, true, false), vec![2, 1, 0, 1, 2]);
+    assert_eq!(line_numbers(5, 2, true, true), vec![2, 1, 3, 1, 2]);
+    assert_eq!(line_numbers(3, 0, true, false), vec![0, 1, 2]);
+    assert!(line_numbers(0, 0, true, false).is_empty());
+  }
+
+  #[test]
+  fn test_spinner_advances_only_while_processing() -> Result<()> {
+    let mut home = Home::new();
+    home.update(EngineAction::Render.into())?;
+    assert_eq!(home.spinner_index, 0);
+    assert!(render_to_string(&mut home)?.contains("─ratatui async template─"));
+
+    home.update(HomeAction::EnterProcessing.into())?;
+    home.update(EngineAction::Render.into())?;
+    home.update(EngineAction::Render.into())?;
+    assert_eq!(home.spinner_index, 2);
+    assert!(render_to_string(&mut home)?.contains("⠹ ratatui async template"));
+
+    for _ in 0..SPINNER_FRAMES.len() {
+      home.update(EngineAction::Render.into())?;
+    }
+    assert_eq!(home.spinner_index, 2);
+    Ok(())
+  }
+
+  #[test]
+  fn test_exit_processing_returns_to_insert() -> Result<()> {
+    let mut home = Home::new();
+    home.update(HomeAction::EnterInsert.into())?;
+    home.input = Input::new("draft".to_string()).with_cursor(2);
+
+    home.update(HomeAction::EnterProcessing.into())?;
+    assert!(home.mode == Mode::Processing);
+    // A second batch starting before the first ends mustn't make Processing the mode to return to.
+    home.update(HomeAction::EnterProcessing.into())?;
+    assert!(home.previous_mode == Mode::Insert);
+
+    home.update(HomeAction::ExitProcessing.into())?;
+    assert!(home.mode == Mode::Insert);
+    assert_eq!(home.input.cursor(), 2);
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_quit_while_scheduled_work_is_in_flight() -> Result<()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut home = Home::new();
+    home.register_action_handler(tx)?;
+    let tasks = TaskSet::new();
+    home.register_task_handler(tasks.clone())?;
+
+    home.update(HomeAction::ScheduleIncrement(1).into())?;
+    // Quitting drops the app's receiver before the task gets to send anything.
+    drop(rx);
+    tasks.shutdown(Duration::from_secs(1)).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_todos_load_in_the_background() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-home-load-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    let todos_path = dir.join("todos.json");
+    TodoStore::new(vec![Todo::new(1, "from disk")]).save(&todos_path)?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut home = Home { todos_path: todos_path.clone(), ..Home::default() };
+    home.register_action_handler(tx)?;
+
+    home.init()?;
+    assert!(home.loading);
+    assert!(render_to_string(&mut home)?.contains("Loading todos…"));
+    assert_eq!(
+      home.can_handle(&HomeAction::EnterInsert.into()),
+      Applicability::NotApplicable("Still loading the todos".to_string())
+    );
+    // Nothing typed or saved in the meantime may overwrite what is on disk.
+    home.update(HomeAction::CompleteInput("too early".to_string()).into())?;
+    assert_eq!(home.update(HomeAction::Save.into())?, None);
+    home.on_exit()?;
+    assert_eq!(TodoStore::load(&todos_path).len(), 1);
+
+    let loaded = rx.recv().await.expect("the loaded todos are sent");
+    assert_eq!(loaded, HomeAction::TodosLoaded(TodoStore::new(vec![Todo::new(1, "from disk")])).into());
+    home.update(loaded)?;
+    assert!(!home.loading);
+    assert_eq!(home.todos.todos(), [Todo::new(1, "from disk")]);
+    assert_eq!(home.can_handle(&HomeAction::EnterInsert.into()), Applicability::Handled);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_refresh_splits_the_layout_again_and_rereads_the_todos() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("{}-home-refresh-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    let todos_path = dir.join("todos.json");
+    TodoStore::new(vec![Todo::new(1, "milk")]).save(&todos_path)?;
+    let mut home = Home { todos_path: todos_path.clone(), ..Home::default() };
+    home.init()?;
+    render_to_string(&mut home)?;
+    render_to_string(&mut home)?;
+    assert_eq!(home.layout.splits(), 1);
+
+    TodoStore::new(vec![Todo::new(1, "milk"), Todo::new(2, "eggs")]).save(&todos_path)?;
+    assert_eq!(home.update(EngineAction::Refresh.into())?, None);
+    assert!(render_to_string(&mut home)?.contains("eggs"));
+    assert_eq!(home.layout.splits(), 2);
+    assert_eq!(home.todos.len(), 2);
+
+    // Todos that haven't been saved yet aren't thrown away.
+    home.add("bread".to_string());
+    assert_eq!(
+      home.update(EngineAction::Refresh.into())?,
+      Some(EngineAction::Notify("Kept the unsaved todos instead of reloading them".to_string()).into())
+    );
+    assert_eq!(home.todos.len(), 3);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_delete_asks_for_confirmation() -> Result<()> {
+    use crossterm::event::KeyModifiers;
+
+    let mut home = Home::new();
+    home.add("milk".to_string());
+    home.add("eggs".to_string());
+    home.update(HomeAction::DeleteTodo.into())?;
+    assert!(home.captures_text_input());
+    assert!(render_to_string(&mut home)?.contains("Delete 'milk'? (y/n)"));
+
+    let press = |home: &mut Home, c| home.handle_key_events(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    assert_eq!(press(&mut home, 'n')?, Some(EngineAction::Notify("Kept the todo".to_string()).into()));
+    assert!(!home.captures_text_input());
+
+    home.update(HomeAction::DeleteTodo.into())?;
+    let answer = press(&mut home, 'y')?.unwrap();
+    assert_eq!(answer, HomeAction::RemoveTodo(1).into());
+    home.update(answer)?;
+    assert_eq!(home.todos.todos().iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), ["eggs"]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_counted_operators_apply_from_the_selection_down() -> Result<()> {
+    let mut home = Home::new();
+    for title in ["milk", "eggs", "bread", "jam"] {
+      home.add(title.to_string());
+    }
+    let titles = |home: &Home| home.todos.todos().iter().map(|t| t.title.clone()).collect::<Vec<_>>();
+    home.update(HomeAction::NavigateList(ListNavDirection::Down, 2).into())?;
+
+    // 3dd on the third of four todos takes the last two and stops there.
+    home.update(Action::from(HomeAction::DeleteTodos(1)).with_count(3))?;
+    assert_eq!(titles(&home), ["milk", "eggs"]);
+    assert_eq!(home.selected_todo, 1);
+    home.update(HomeAction::Undo.into())?;
+    assert_eq!(titles(&home), ["milk", "eggs", "bread", "jam"]);
+
+    home.update(HomeAction::NavigateList(ListNavDirection::First, 1).into())?;
+    home.update(Action::from(HomeAction::ToggleTodos(1)).with_count(2))?;
+    assert_eq!(home.todos.todos().iter().map(|t| t.is_completed).collect::<Vec<_>>(), [true, true, false, false]);
+    home.update(HomeAction::Undo.into())?;
+    assert!(home.todos.todos().iter().all(|t| !t.is_completed));
+    Ok(())
+  }
+
+  #[test]
+  fn test_bulk_complete_and_clear_undo_in_one_step() -> Result<()> {
+    let mut home = Home::new();
+    for title in ["milk", "eggs", "bread"] {
+      home.add(title.to_string());
+    }
+    home.todos.toggle_completed(2);
+    let todos = |home: &Home| home.todos.todos().iter().map(|t| (t.title.clone(), t.is_completed)).collect::<Vec<_>>();
+    let todo = |title: &str, done| (title.to_string(), done);
+
+    home.update(HomeAction::MarkAllComplete.into())?;
+    assert_eq!(todos(&home), [todo("milk", true), todo("eggs", true), todo("bread", true)]);
+    assert!(matches!(home.can_handle(&HomeAction::MarkAllComplete.into()), Applicability::NotApplicable(_)));
+    home.update(HomeAction::Undo.into())?;
+    assert_eq!(todos(&home), [todo("milk", false), todo("eggs", true), todo("bread", false)]);
+
+    home.update(HomeAction::NavigateList(ListNavDirection::Last, 1).into())?;
+    home.update(HomeAction::ClearCompleted.into())?;
+    assert_eq!(todos(&home), [todo("milk", false), todo("bread", false)]);
+    assert_eq!(home.selected_todo, 1);
+    assert!(matches!(home.can_handle(&HomeAction::ClearCompleted.into()), Applicability::NotApplicable(_)));
+    home.update(HomeAction::Undo.into())?;
+    assert_eq!(todos(&home), [todo("milk", false), todo("eggs", true), todo("bread", false)]);
+    home.update(HomeAction::Redo.into())?;
+    assert_eq!(todos(&home), [todo("milk", false), todo("bread", false)]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_trimming_reports_counts_and_undoes_in_one_step() -> Result<()> {
+    let mut home = Home::new();
+    for title in [" milk ", "eggs", "  "] {
+      home.add(title.to_string());
+    }
+    let titles = |home: &Home| home.todos.todos().iter().map(|t| t.title.clone()).collect::<Vec<_>>();
+    let before = titles(&home);
+
+    assert_eq!(
+      home.update(HomeAction::TrimEntries.into())?,
+      Some(EngineAction::Notify("Trimmed 1 entries and removed 1 empty ones".to_string()).into())
+    );
+    assert_eq!(titles(&home), ["milk", "eggs"]);
+    home.update(HomeAction::Undo.into())?;
+    assert_eq!(titles(&home), before);
+    home.update(HomeAction::Redo.into())?;
+    assert_eq!(titles(&home), ["milk", "eggs"]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_cycles_the_selected_todos_priority() -> Result<()> {
+    let mut home = Home::new();
+    assert!(matches!(home.can_handle(&HomeAction::CycleSelectedPriority.into()), Applicability::NotApplicable(_)));
+    home.add("milk".to_string());
+    home.add("eggs".to_string());
+    home.update(HomeAction::NavigateList(ListNavDirection::Last, 1).into())?;
+
+    let cycle = home.update(HomeAction::CycleSelectedPriority.into())?.unwrap();
+    assert_eq!(cycle, HomeAction::CyclePriority(2).into());
+    home.update(cycle.clone())?;
+    home.update(cycle)?;
+    assert_eq!(home.todos.todos()[1].priority, Priority::Low);
+    home.update(HomeAction::Undo.into())?;
+    assert_eq!(home.todos.todos()[1].priority, Priority::High);
+
+    home.update(HomeAction::SortBy(TodoSort::Priority).into())?;
+    assert_eq!(home.todos.todos()[0].title, "eggs");
+    assert_eq!(home.selected_todo, 0);
+    assert!(matches!(home.can_handle(&HomeAction::CyclePriority(9).into()), Applicability::NotApplicable(_)));
+    Ok(())
+  }
+
+  #[test]
+  fn test_input_counts_characters_up_to_the_limit() -> Result<()> {
+    use crossterm::event::KeyModifiers;
+
+    let mut home = Home::new();
+    let mut config = Config::default();
+    config.max_input_len = Some(3);
+    home.register_config_handler(config)?;
+    home.update(HomeAction::EnterInsert.into())?;
+    for c in "abcd".chars() {
+      home.handle_key_events(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+    }
+    assert_eq!(home.input.value(), "abc");
+    assert!(render_to_string(&mut home)?.contains(" 3/3 (limit reached) "));
+
+    home.handle_key_events(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE))?;
+    assert!(render_to_string(&mut home)?.contains(" 2/3 "));
+
+    home.register_config_handler(Config::default())?;
+    home.handle_key_events(KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE))?;
+    home.handle_key_events(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE))?;
+    assert!(render_to_string(&mut home)?.contains(" 4 chars "));
+    Ok(())
+  }
+
+  #[test]
+  fn test_list_scrolls_with_selection_and_pages() -> Result<()> {
+    let mut home = Home::new();
+    for i in 1..=30 {
+      home.todos.add(format!("todo {i}"));
+    }
+    // 20 rows leave 15 lines inside the list pane's borders.
+    render_to_string(&mut home)?;
+    assert_eq!((home.viewport_height, home.scroll_offset), (15, 0));
+
+    for _ in 0..8 {
+      home.update(HomeAction::NavigateList(ListNavDirection::Down, 1).into())?;
+    }
+    // The selected todo is on the 16th line, one past the bottom, so the pane scrolls by one.
+    assert_eq!((home.selected_todo, home.scroll_offset), (8, 1));
+
+    home.update(HomeAction::ScrollPage(ListNavDirection::Down).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (23, 16));
+    home.update(HomeAction::ScrollPage(ListNavDirection::Down).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (29, 22));
+    assert!(render_to_string(&mut home)?.contains("todo 30"));
+
+    home.update(HomeAction::ScrollPage(ListNavDirection::Up).into())?;
+    home.update(HomeAction::ScrollPage(ListNavDirection::Up).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (0, 0));
+
+    home.add("new".to_string());
+    assert_eq!(home.scroll_offset, 23);
+    assert!(render_to_string(&mut home)?.contains("new"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_jumps_to_either_end_and_pages_through_short_lists() -> Result<()> {
+    let mut home = Home::new();
+    for i in 1..=30 {
+      home.todos.add(format!("todo {i}"));
+    }
+    render_to_string(&mut home)?;
+
+    home.update(HomeAction::NavigateList(ListNavDirection::Last, 1).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (29, 22));
+    home.update(HomeAction::NavigateList(ListNavDirection::First, 1).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (0, 0));
+    home.update(HomeAction::NavigateList(ListNavDirection::PageDown, 2).into())?;
+    assert_eq!(home.selected_todo, 29);
+
+    // A page down near the end of a list shorter than the screen stops on the last todo.
+    let mut home = Home::new();
+    for i in 1..=3 {
+      home.todos.add(format!("todo {i}"));
+    }
+    render_to_string(&mut home)?;
+    home.update(HomeAction::NavigateList(ListNavDirection::Down, 1).into())?;
+    home.update(HomeAction::NavigateList(ListNavDirection::PageDown, 1).into())?;
+    assert_eq!((home.selected_todo, home.scroll_offset), (2, 0));
+    home.update(HomeAction::NavigateList(ListNavDirection::PageUp, 1).into())?;
+    assert_eq!(home.selected_todo, 0);
     Ok(())
   }
 }
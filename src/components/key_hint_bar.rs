@@ -0,0 +1,120 @@
+use color_eyre::eyre::Result;
+use crossterm::event::KeyModifiers;
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::{
+  app::Mode,
+  config::{key_event_to_string, key_sequence_display, Config},
+  tui::Frame,
+};
+
+/// Separates the hints from each other.
+const SEPARATOR: &str = "  ";
+
+/// Lists the current mode's own key bindings along the bottom of the screen, as `key: Action`, as many as fit.
+#[derive(Debug, Default)]
+pub struct KeyHintBar {
+  mode: Mode,
+  config: Config,
+}
+
+impl KeyHintBar {
+  pub fn new(mode: Mode) -> Self {
+    Self { mode, ..Self::default() }
+  }
+
+  /// The mode's bindings, most relevant first: single plain keys, then other single keys, then multi-key sequences.
+  pub fn hints(&self) -> Vec<(String, String)> {
+    let mut bindings: Vec<_> = self.config.keybindings.get(&self.mode).into_iter().flatten().collect();
+    bindings.sort_by_key(|(keys, _)| {
+      let plain = keys.iter().all(|key| (key.modifiers - KeyModifiers::SHIFT).is_empty());
+      (keys.len(), !plain, keys.iter().map(key_event_to_string).collect::<Vec<_>>())
+    });
+    bindings.into_iter().map(|(keys, action)| (key_sequence_display(keys), action.to_string())).collect()
+  }
+}
+
+impl Component for KeyHintBar {
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    Ok(())
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let theme = self.config.theme;
+    let mut spans = vec![Span::raw(" ")];
+    let mut width = 1;
+    for (keys, action) in self.hints() {
+      let separator = if width > 1 { SEPARATOR } else { "" };
+      let hint_width = separator.len() + keys.chars().count() + 2 + action.chars().count();
+      if width + hint_width > rect.width as usize {
+        break;
+      }
+      spans.push(separator.into());
+      spans.push(Span::styled(keys, theme.accent_style()));
+      spans.push(format!(": {action}").into());
+      width += hint_width;
+    }
+    if width == 1 {
+      return Ok(());
+    }
+
+    // Only as wide as the hints, so whatever sits at the other end of the row stays visible.
+    let bar = Rect { y: rect.bottom().saturating_sub(1), height: rect.height.min(1), width: width as u16, ..rect };
+    f.render_widget(Clear, bar);
+    f.render_widget(Paragraph::new(Line::from(spans)), bar);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use ratatui::{backend::TestBackend, Terminal};
+
+  use super::*;
+  use crate::{
+    actions::{EngineAction, HomeAction},
+    config::parse_key_sequence,
+  };
+
+  #[test]
+  fn test_shows_current_mode_bindings_that_fit() -> Result<()> {
+    let mut config = Config::default();
+    let keymap = |bindings: &[(&str, crate::actions::Action)]| {
+      bindings.iter().map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action.clone())).collect()
+    };
+    config.keybindings.insert(
+      Mode::Home,
+      keymap(&[
+        ("<g><g>", EngineAction::Refresh.into()),
+        ("<ctrl-z>", HomeAction::Undo.into()),
+        ("<j>", HomeAction::ScheduleIncrement(1).into()),
+      ]),
+    );
+    config.keybindings.insert(Mode::MainMenu, keymap(&[("<q>", EngineAction::Quit.into())]));
+    let mut bar = KeyHintBar::new(Mode::Home);
+    bar.register_config_handler(config)?;
+
+    let hint = |keys: &str, action: &str| (keys.to_string(), action.to_string());
+    assert_eq!(
+      bar.hints(),
+      vec![hint("j", "Home.ScheduleIncrement(1)"), hint("ctrl-z", "Home.Undo"), hint("g g", "Engine.Refresh")]
+    );
+
+    let mut terminal = Terminal::new(TestBackend::new(48, 3))?;
+    terminal.draw(|f| bar.draw(f, f.size()).unwrap())?;
+    let bottom: String = (0..48).map(|x| terminal.backend().buffer().get(x, 2).symbol.clone()).collect();
+    assert_eq!(bottom, " j: Home.ScheduleIncrement(1)  ctrl-z: Home.Undo");
+
+    bar.on_mode_change(Mode::Home, Mode::MainMenu)?;
+    assert_eq!(bar.hints(), vec![hint("q", "Engine.Quit")]);
+    Ok(())
+  }
+}
@@ -0,0 +1,447 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+
+use super::{centered_rect, focused_border_style, navigable::Navigable, popup_block, Component, Frame};
+use crate::{
+  actions::{Action, EngineAction, ListNavDirection},
+  app::Mode,
+  config::{key_sequence_display, key_sequence_to_string, section_name, Config},
+};
+
+/// A binding as the editor lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingRow {
+  /// The section of the key bindings it is in, `None` for the global one.
+  pub section: Option<Mode>,
+  pub keys: Vec<KeyEvent>,
+  pub action: Action,
+}
+
+/// What the editor is doing with the selected binding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum EditState {
+  #[default]
+  Browsing,
+  /// Recording the binding's new keys, and when the last of them was pressed.
+  Capturing { keys: Vec<KeyEvent>, last: Option<Instant> },
+  /// Waiting for the go-ahead to bind `keys` over the bindings they clash with.
+  Confirming { keys: Vec<KeyEvent>, clashes: Vec<(Option<Mode>, Vec<KeyEvent>, Action)> },
+}
+
+/// An overlay listing every key binding so they can be changed while the app runs, opened with
+/// [`EngineAction::ToggleKeybindingEditor`].
+///
+/// Enter on a binding records the keys typed next, and once none follow within the chord timeout they replace the
+/// binding's keys. Should they clash with other bindings, those are listed first and only unbound once Enter is pressed
+/// again. Esc cancels whatever is in progress, or closes the editor. Changes take effect straight away through
+/// [`EngineAction::SetBinding`], and Ctrl-s writes them to the config file. Every key goes to the editor while it is
+/// open, so recording can't set off the bindings being recorded.
+#[derive(Default)]
+pub struct KeybindingEditor {
+  open: bool,
+  state: EditState,
+  table: TableState,
+  /// How many rows of the table fit on screen, as of the last draw.
+  page_size: usize,
+  config: Config,
+  mode: Mode,
+  focused: bool,
+}
+
+impl KeybindingEditor {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_open(&self) -> bool {
+    self.open
+  }
+
+  /// Every binding, the global ones first and then each mode's own, sorted within each section like the help screen.
+  pub fn rows(&self) -> Vec<BindingRow> {
+    let keybindings = &self.config.keybindings;
+    let modes = Mode::ALL.iter().filter_map(|mode| Some((Some(*mode), keybindings.get(mode)?)));
+    std::iter::once((None, &keybindings.global))
+      .chain(modes)
+      .flat_map(|(section, keymap)| {
+        let mut rows: Vec<BindingRow> = keymap
+          .iter()
+          .map(|(keys, action)| BindingRow { section, keys: keys.clone(), action: action.clone() })
+          .collect();
+        rows.sort_by_cached_key(|row| (row.action.to_string(), key_sequence_to_string(&row.keys)));
+        rows
+      })
+      .collect()
+  }
+
+  fn selected_row(&self) -> Option<BindingRow> {
+    self.rows().into_iter().nth(self.index())
+  }
+
+  fn toggle(&mut self) {
+    self.open = !self.open;
+    self.state = EditState::Browsing;
+    self.table.select(self.open.then_some(0));
+  }
+
+  /// Finishes recording once no key has come for the chord timeout. Returns the rebinding if it can go ahead without
+  /// unbinding anything else, and otherwise asks for confirmation.
+  fn finish_capture(&mut self, now: Instant) -> Option<Action> {
+    let EditState::Capturing { keys, last: Some(last) } = &self.state else {
+      return None;
+    };
+    if now.saturating_duration_since(*last) < Duration::from_millis(self.config.chords.timeout_ms) {
+      return None;
+    }
+    let keys = keys.clone();
+    self.state = EditState::Browsing;
+    let row = self.selected_row().filter(|row| row.keys != keys)?;
+    let clashes: Vec<_> = self
+      .config
+      .keybindings
+      .clashes(row.section, &keys)
+      .into_iter()
+      .filter(|(section, bound, _)| *section != row.section || *bound != row.keys)
+      .collect();
+    match clashes.is_empty() {
+      true => Some(rebind(&row, keys, Vec::new())),
+      false => {
+        self.state = EditState::Confirming { keys, clashes };
+        None
+      },
+    }
+  }
+
+  fn hint(&self) -> Line<'static> {
+    let dim = self.config.theme.dim_style();
+    match &self.state {
+      EditState::Browsing => Line::styled("enter: change keys, ctrl-s: save to the config file, esc: close", dim),
+      EditState::Capturing { keys, .. } if keys.is_empty() => {
+        let action = self.selected_row().map(|row| row.action.to_string()).unwrap_or_default();
+        Line::styled(format!("Press the new keys for {action}, esc to cancel"), dim)
+      },
+      EditState::Capturing { keys, .. } => {
+        Line::styled(format!("New keys: {}, esc to cancel", key_sequence_display(keys)), dim)
+      },
+      EditState::Confirming { keys, clashes } => {
+        let clashes: Vec<String> = clashes
+          .iter()
+          .map(|(section, bound, action)| {
+            format!("{} ({action}) in {}", key_sequence_display(bound), section_name(*section))
+          })
+          .collect();
+        Line::styled(
+          format!("{} would unbind {}. enter: go ahead, esc: cancel", key_sequence_display(keys), clashes.join(", ")),
+          self.config.theme.error_style(),
+        )
+      },
+    }
+  }
+
+  fn draw_editor(&mut self, f: &mut Frame, rect: Rect) {
+    let rect = centered_rect(80, 80, rect);
+    let mut title = vec![Span::styled("Edit Key Bindings", Style::default().add_modifier(Modifier::BOLD))];
+    if self.config.has_unsaved_bindings() {
+      title.push(" - unsaved changes".into());
+    }
+    let border_style =
+      if self.focused { focused_border_style(&self.config, self.mode) } else { self.config.theme.border_style() };
+    let block = popup_block(Line::from(title)).border_style(border_style);
+    let inner = block.inner(rect).inner(&Margin { horizontal: 1, vertical: 0 });
+    f.render_widget(Clear, rect);
+    f.render_widget(block, rect);
+
+    let [table_area, _, hint_area] =
+      *Layout::default().constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(2)]).split(inner)
+    else {
+      return;
+    };
+
+    let capturing = match &self.state {
+      EditState::Capturing { keys, .. } => Some(format!("{}…", key_sequence_display(keys))),
+      _ => None,
+    };
+    let selected = self.index();
+    let rows: Vec<Row> = self
+      .rows()
+      .into_iter()
+      .enumerate()
+      .map(|(i, row)| {
+        let keys = match &capturing {
+          Some(keys) if i == selected => Cell::from(keys.clone()).style(self.config.theme.highlight_style()),
+          _ => Cell::from(key_sequence_display(&row.keys)),
+        };
+        Row::new(vec![Cell::from(section_name(row.section)), keys, Cell::from(row.action.to_string())])
+      })
+      .collect();
+    let row_count = rows.len();
+    let widths = [Constraint::Length(10), Constraint::Percentage(25), Constraint::Min(0)];
+    let table = Table::new(rows)
+      .header(
+        Row::new(vec!["Section", "Keys", "Action"])
+          .bottom_margin(1)
+          .style(Style::default().add_modifier(Modifier::BOLD)),
+      )
+      .widths(&widths)
+      .column_spacing(1)
+      .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    // The header and the gap beneath it take up two rows.
+    self.page_size = (table_area.height as usize).saturating_sub(2);
+    f.render_stateful_widget(table, table_area, &mut self.table);
+    f.render_widget(Paragraph::new(self.hint()).wrap(Wrap { trim: true }), hint_area);
+
+    if row_count > self.page_size {
+      let mut scrollbar_state = ScrollbarState::new(row_count.saturating_sub(1)).position(selected);
+      f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        rect.inner(&Margin { vertical: 1, horizontal: 0 }),
+        &mut scrollbar_state,
+      );
+    }
+  }
+}
+
+/// The actions giving `row` the new `keys`, after unbinding the bindings they clash with and the old keys.
+fn rebind(row: &BindingRow, keys: Vec<KeyEvent>, clashes: Vec<(Option<Mode>, Vec<KeyEvent>, Action)>) -> Action {
+  let unbind = |section, keys| EngineAction::SetBinding(section, keys, None).into();
+  let actions = clashes
+    .into_iter()
+    // Binding the keys replaces whatever they are bound to in the same section anyway.
+    .filter(|(section, bound, _)| *section != row.section || *bound != keys)
+    .map(|(section, bound, _)| unbind(section, bound))
+    .chain([
+      unbind(row.section, row.keys.clone()),
+      EngineAction::SetBinding(row.section, keys.clone(), Some(Box::new(row.action.clone()))).into(),
+    ])
+    .collect();
+  EngineAction::Batch(actions).into()
+}
+
+impl Navigable for KeybindingEditor {
+  fn len(&self) -> usize {
+    self.rows().len()
+  }
+
+  fn index(&self) -> usize {
+    self.table.selected().unwrap_or(0)
+  }
+
+  fn set_index(&mut self, index: usize) {
+    self.table.select(Some(index));
+  }
+
+  fn page_size(&self) -> usize {
+    self.page_size
+  }
+}
+
+impl Component for KeybindingEditor {
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    // A binding may have been removed from under the selection.
+    let last = self.len().saturating_sub(1);
+    if self.index() > last {
+      self.set_index(last);
+    }
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if !self.open {
+      return Ok(None);
+    }
+    // Only the key itself matters for bindings, not whatever else the terminal reports about it.
+    let key = KeyEvent::new(key.code, key.modifiers);
+    match &mut self.state {
+      EditState::Browsing => match key.code {
+        KeyCode::Esc => self.toggle(),
+        KeyCode::Enter if !self.is_empty() => self.state = EditState::Capturing { keys: Vec::new(), last: None },
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+          return Ok(Some(EngineAction::SaveKeybindings.into()));
+        },
+        KeyCode::Up | KeyCode::Char('k') => self.navigate(ListNavDirection::Up, 1),
+        KeyCode::Down | KeyCode::Char('j') => self.navigate(ListNavDirection::Down, 1),
+        KeyCode::PageUp => self.navigate(ListNavDirection::PageUp, 1),
+        KeyCode::PageDown => self.navigate(ListNavDirection::PageDown, 1),
+        KeyCode::Home => self.navigate(ListNavDirection::First, 1),
+        KeyCode::End => self.navigate(ListNavDirection::Last, 1),
+        _ => {},
+      },
+      EditState::Capturing { .. } if key.code == KeyCode::Esc => self.state = EditState::Browsing,
+      EditState::Capturing { keys, last } => {
+        keys.push(key);
+        *last = Some(Instant::now());
+      },
+      EditState::Confirming { keys, clashes } => match key.code {
+        KeyCode::Enter | KeyCode::Char('y') => {
+          let (keys, clashes) = (keys.clone(), clashes.clone());
+          self.state = EditState::Browsing;
+          return Ok(self.selected_row().map(|row| rebind(&row, keys, clashes)));
+        },
+        KeyCode::Esc | KeyCode::Char('n') => self.state = EditState::Browsing,
+        _ => {},
+      },
+    }
+    Ok(None)
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Engine(EngineAction::ToggleKeybindingEditor) => self.toggle(),
+      Action::Engine(EngineAction::Tick | EngineAction::Render) => return Ok(self.finish_capture(Instant::now())),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    Ok(())
+  }
+
+  fn captures_text_input(&self) -> bool {
+    self.open
+  }
+
+  fn captures_all_keys(&self) -> bool {
+    self.open
+  }
+
+  fn has_focus(&self) -> bool {
+    self.open
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    self.open.then_some(50)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if self.open {
+      self.draw_editor(f, rect);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{
+    actions::HomeAction,
+    config::{parse_key_sequence, KeyBindings},
+  };
+
+  fn editor() -> Result<KeybindingEditor> {
+    let mut config = Config::default();
+    config.keybindings = json5::from_str(
+      r#"{ "Global": { "<q>": "Engine.Quit" }, "Home": { "<x>": "Home.Save", "<g><g>": "Home.Undo" } }"#,
+    )?;
+    let mut editor = KeybindingEditor::new();
+    editor.register_config_handler(config)?;
+    editor.update(EngineAction::ToggleKeybindingEditor.into())?;
+    Ok(editor)
+  }
+
+  fn press(editor: &mut KeybindingEditor, keys: &str) -> Result<Option<Action>> {
+    let mut action = None;
+    for key in parse_key_sequence(keys).map_err(color_eyre::eyre::Error::msg)? {
+      action = editor.handle_key_events(key)?;
+    }
+    Ok(action)
+  }
+
+  fn later() -> Instant {
+    Instant::now() + Duration::from_secs(5)
+  }
+
+  fn set(section: Option<Mode>, keys: &str, action: Option<Action>) -> Action {
+    EngineAction::SetBinding(section, parse_key_sequence(keys).unwrap(), action.map(Box::new)).into()
+  }
+
+  #[test]
+  fn test_lists_every_section() -> Result<()> {
+    let editor = editor()?;
+    let rows: Vec<(String, String)> =
+      editor.rows().iter().map(|row| (section_name(row.section), key_sequence_display(&row.keys))).collect();
+    let row = |section: &str, keys: &str| (section.to_string(), keys.to_string());
+    assert_eq!(rows, vec![row(KeyBindings::GLOBAL, "q"), row("Home", "x"), row("Home", "g g")]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_captured_keys_replace_the_binding_once_typing_stops() -> Result<()> {
+    let mut editor = editor()?;
+    assert!(editor.captures_all_keys());
+    press(&mut editor, "<down><enter>")?;
+    assert_eq!(press(&mut editor, "<ctrl-o> z")?, None);
+    // Still within the chord timeout, more keys may follow.
+    assert_eq!(editor.finish_capture(Instant::now()), None);
+
+    assert_eq!(
+      editor.finish_capture(later()),
+      Some(
+        EngineAction::Batch(vec![
+          set(Some(Mode::Home), "<x>", None),
+          set(Some(Mode::Home), "<ctrl-o><z>", Some(HomeAction::Save.into())),
+        ])
+        .into()
+      )
+    );
+    assert_eq!(editor.state, EditState::Browsing);
+
+    // Esc gives up on the keys typed so far, and again closes the editor.
+    press(&mut editor, "<enter> a <esc>")?;
+    assert_eq!(editor.finish_capture(later()), None);
+    press(&mut editor, "<esc>")?;
+    assert!(!editor.is_open());
+    Ok(())
+  }
+
+  #[test]
+  fn test_clashing_keys_wait_for_confirmation() -> Result<()> {
+    let mut editor = editor()?;
+    press(&mut editor, "<down><enter> g")?;
+    assert_eq!(editor.finish_capture(later()), None);
+    assert!(matches!(editor.state, EditState::Confirming { .. }));
+    assert_eq!(press(&mut editor, "<esc>")?, None);
+    assert_eq!(editor.state, EditState::Browsing);
+
+    // Binding `q` in Home hides the global `q` there.
+    press(&mut editor, "<enter> q")?;
+    assert_eq!(editor.finish_capture(later()), None);
+    assert_eq!(
+      press(&mut editor, "<enter>")?,
+      Some(
+        EngineAction::Batch(vec![
+          set(Some(Mode::Home), "<x>", None),
+          set(Some(Mode::Home), "<q>", Some(HomeAction::Save.into()))
+        ])
+        .into()
+      )
+    );
+
+    press(&mut editor, "<enter> g")?;
+    editor.finish_capture(later());
+    assert_eq!(
+      press(&mut editor, "<enter>")?,
+      Some(
+        EngineAction::Batch(vec![
+          set(Some(Mode::Home), "<g><g>", None),
+          set(Some(Mode::Home), "<x>", None),
+          set(Some(Mode::Home), "<g>", Some(HomeAction::Save.into())),
+        ])
+        .into()
+      )
+    );
+    Ok(())
+  }
+}
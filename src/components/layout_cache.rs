@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use ratatui::layout::{Layout, Rect};
+
+/// Keeps the chunks a [`Layout`] last split an area into, so a component drawn into the same area every frame only
+/// splits it again once the area changes or the cache is invalidated, e.g. on [`EngineAction::Resize`].
+///
+/// Only the area is compared, so each cache belongs to one layout whose constraints don't change between draws.
+///
+/// [`EngineAction::Resize`]: crate::actions::EngineAction::Resize
+#[derive(Debug, Default)]
+pub struct LayoutCache {
+  cached: Option<(Rect, Rc<[Rect]>)>,
+  /// How many times the layout has actually been split.
+  splits: usize,
+}
+
+impl LayoutCache {
+  /// The chunks of `area` under `layout`, split afresh only when `area` isn't the one cached.
+  pub fn split(&mut self, layout: &Layout, area: Rect) -> Rc<[Rect]> {
+    match &self.cached {
+      Some((cached_area, chunks)) if *cached_area == area => chunks.clone(),
+      _ => {
+        let chunks = layout.split(area);
+        self.splits += 1;
+        self.cached = Some((area, chunks.clone()));
+        chunks
+      },
+    }
+  }
+
+  /// Forgets the cached chunks, so the next draw splits again.
+  pub fn invalidate(&mut self) {
+    self.cached = None;
+  }
+
+  pub fn splits(&self) -> usize {
+    self.splits
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use ratatui::layout::{Constraint, Direction};
+
+  use super::*;
+
+  #[test]
+  fn test_splits_again_only_for_a_new_area() {
+    let layout =
+      Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(1), Constraint::Min(0)]);
+    let mut cache = LayoutCache::default();
+    let area = Rect::new(0, 0, 80, 24);
+
+    let chunks = cache.split(&layout, area);
+    assert_eq!(*chunks, [Rect::new(0, 0, 80, 1), Rect::new(0, 1, 80, 23)]);
+    for _ in 0..100 {
+      assert_eq!(cache.split(&layout, area), chunks);
+    }
+    assert_eq!(cache.splits(), 1);
+
+    assert_eq!(cache.split(&layout, Rect::new(0, 0, 40, 10))[1], Rect::new(0, 1, 40, 9));
+    assert_eq!(cache.splits(), 2);
+
+    cache.invalidate();
+    cache.split(&layout, Rect::new(0, 0, 40, 10));
+    assert_eq!(cache.splits(), 3);
+  }
+}
@@ -0,0 +1,130 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::resources::ResourceUsage;
+
+/// A named area to outline on the layout debug overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outline {
+  pub label: String,
+  pub area: Rect,
+}
+
+impl Outline {
+  /// Labels `area` with `name`, its size and its position, e.g. `list 78x20 @ 1,2`.
+  pub fn new(name: &str, area: Rect) -> Self {
+    Self { label: format!("{name} {}x{} @ {},{}", area.width, area.height, area.x, area.y), area }
+  }
+
+  /// The top-left, top-right, bottom-left and bottom-right cells of the area, or `None` if it has no cells.
+  pub fn corners(&self) -> Option<[(u16, u16); 4]> {
+    if self.area.is_empty() {
+      return None;
+    }
+    let (left, top) = (self.area.left(), self.area.top());
+    let (right, bottom) = (self.area.right() - 1, self.area.bottom() - 1);
+    Some([(left, top), (right, top), (left, bottom), (right, bottom)])
+  }
+}
+
+/// Draws the border and label of every outline on top of whatever has already been rendered.
+pub struct LayoutDebug<'a>(pub &'a [Outline]);
+
+impl Widget for LayoutDebug<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    for outline in self.0 {
+      let rect = outline.area.intersection(area);
+      if rect.is_empty() {
+        continue;
+      }
+      Block::default()
+        .title(outline.label.as_str())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+        .render(rect, buf);
+    }
+  }
+}
+
+/// Draws a resource snapshot in the top-right corner, one row per reading.
+pub struct ResourcePanel<'a>(pub &'a ResourceUsage);
+
+impl Widget for ResourcePanel<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let rows = self.0.rows();
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let lines: Vec<Line> =
+      rows.iter().map(|(label, value)| Line::from(format!("{label:<label_width$}  {value}"))).collect();
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+    let rect = Rect::new(area.right().saturating_sub(width), area.y, width, lines.len() as u16 + 2).intersection(area);
+
+    Clear.render(rect, buf);
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .title("Diagnostics")
+          .borders(Borders::ALL)
+          .border_style(Style::default().fg(Color::Cyan))
+          .title_style(Style::default().fg(Color::Black).bg(Color::Cyan)),
+      )
+      .render(rect, buf);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::resources::Occupancy;
+
+  #[test]
+  fn test_outline_boundaries_for_split() {
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(3), Constraint::Min(0)])
+      .split(Rect::new(0, 0, 80, 24));
+    let header = Outline::new("header", chunks[0]);
+    let body = Outline::new("body", chunks[1]);
+
+    assert_eq!(header.label, "header 80x3 @ 0,0");
+    assert_eq!(header.corners(), Some([(0, 0), (79, 0), (0, 2), (79, 2)]));
+    assert_eq!(body.label, "body 80x21 @ 0,3");
+    assert_eq!(body.corners(), Some([(0, 3), (79, 3), (0, 23), (79, 23)]));
+    assert_eq!(Outline::new("empty", Rect::new(5, 5, 0, 3)).corners(), None);
+  }
+
+  #[test]
+  fn test_overlay_draws_label_on_border() {
+    let outlines = [Outline::new("tabs", Rect::new(1, 1, 20, 3))];
+    let mut buf = Buffer::empty(Rect::new(0, 0, 24, 6));
+    LayoutDebug(&outlines).render(buf.area, &mut buf);
+
+    assert_eq!(buf.get(1, 1).symbol, "┌");
+    assert_eq!(buf.get(20, 3).symbol, "┘");
+    let title: String = (2..17).map(|x| buf.get(x, 1).symbol.clone()).collect();
+    assert_eq!(title, "tabs 20x3 @ 1,1");
+  }
+
+  #[test]
+  fn test_resource_panel_draws_every_row() {
+    let usage = ResourceUsage {
+      resident_bytes: Some(12 * 1024 * 1024),
+      tasks: 2,
+      queued_actions: 0,
+      buffers: vec![Occupancy::new("action history", 40, Some(100)), Occupancy::new("todos", 7, None)],
+    };
+    let mut buf = Buffer::empty(Rect::new(0, 0, 40, 10));
+    ResourcePanel(&usage).render(buf.area, &mut buf);
+
+    let lines: Vec<String> =
+      (0..buf.area.height).map(|y| (0..buf.area.width).map(|x| buf.get(x, y).symbol.clone()).collect()).collect();
+    // The widest row, `memory` padded to the longest label and followed by `12.0 MiB`, is 24 cells.
+    assert_eq!(lines[0], format!("{}┌Diagnostics{}┐", " ".repeat(14), "─".repeat(13)));
+    for (row, (label, value)) in usage.rows().iter().enumerate() {
+      let line = &lines[row + 1];
+      assert!(line.contains(label.as_str()) && line.contains(value.as_str()), "row {row} missing from {line:?}");
+    }
+    assert_eq!(lines[6], format!("{}└{}┘", " ".repeat(14), "─".repeat(24)));
+  }
+}
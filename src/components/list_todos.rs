@@ -1,50 +1,284 @@
+use chrono::{Local, NaiveDate};
 use ratatui::{prelude::*, widgets::*};
 use unicode_width::UnicodeWidthStr;
 
-pub struct Todo {
-  id: u32,
-  title: &'static str,
-  is_completed: bool,
+use super::{home::SPINNER_FRAMES, navigable::Navigable};
+use crate::todos::{Priority, Todo, TodoSort, TAG_MARKER};
+
+/// A coloured bullet in front of a todo's title showing its priority, dimmed once the todo is completed.
+pub fn priority_marker(todo: &Todo) -> Span<'static> {
+  let color = match todo.priority {
+    Priority::Low => Color::Blue,
+    Priority::Medium => Color::Yellow,
+    Priority::High => Color::Red,
+  };
+  let style = Style::default().fg(color);
+  Span::styled("● ", if todo.is_completed { style.dim() } else { style })
+}
+
+/// The todo's tags as small spans to go after its title, each with a leading space.
+pub fn tag_spans(todo: &Todo) -> Vec<Span<'static>> {
+  todo.tags.iter().map(|tag| Span::styled(format!(" {TAG_MARKER}{tag}"), Style::default().cyan().italic())).collect()
 }
 
 #[derive(Default)]
 pub struct TodosLister {
   selected_index: usize,
+  todos: Vec<Todo>,
+  /// Only todos with this tag are listed, when set.
+  tag_filter: Option<String>,
+  /// How many todos were visible when last drawn.
+  page_size: usize,
+  /// Set until the todos are handed over with [`TodosLister::set_todos`].
+  loading: bool,
+  /// The spinner frame shown while loading.
+  spinner_index: usize,
 }
 
-const TODOS_LIST: &[Todo] = &[
-  Todo { id: 1, title: "Hello World!", is_completed: false },
-  Todo { id: 1, title: "Already completed", is_completed: true },
-];
+impl Navigable for TodosLister {
+  fn len(&self) -> usize {
+    self.visible().len()
+  }
+
+  fn index(&self) -> usize {
+    self.selected_index
+  }
+
+  fn set_index(&mut self, index: usize) {
+    self.selected_index = index;
+  }
+
+  fn page_size(&self) -> usize {
+    self.page_size
+  }
+}
 
 impl TodosLister {
+  /// An empty list that shows a spinner until its todos arrive with [`TodosLister::set_todos`].
   pub fn new(selected_index: usize) -> Self {
-    Self { selected_index }
+    Self { selected_index, loading: true, ..Self::default() }
+  }
+
+  pub fn from_todos(selected_index: usize, todos: Vec<Todo>) -> Self {
+    Self { selected_index, todos, ..Self::default() }
+  }
+
+  pub fn is_loading(&self) -> bool {
+    self.loading
+  }
+
+  /// Replaces the listed todos, keeping the tag filter, and stops showing the spinner.
+  pub fn set_todos(&mut self, todos: Vec<Todo>) {
+    self.todos = todos;
+    self.loading = false;
+    self.selected_index = self.selected_index.min(self.len().saturating_sub(1));
+  }
+
+  /// Moves the spinner on a frame, while loading.
+  pub fn advance_spinner(&mut self) {
+    if self.loading {
+      self.spinner_index = (self.spinner_index + 1) % SPINNER_FRAMES.len();
+    }
+  }
+
+  /// The todos that pass the tag filter, in order. The selected index counts through these.
+  pub fn visible(&self) -> Vec<&Todo> {
+    self.todos.iter().filter(|todo| self.tag_filter.as_ref().is_none_or(|tag| todo.has_tag(tag))).collect()
+  }
+
+  fn selected_id(&self) -> Option<u32> {
+    self.visible().get(self.selected_index).map(|todo| todo.id)
+  }
+
+  /// Selects the todo with the given id if it is listed, or else the first one.
+  fn select_id(&mut self, id: Option<u32>) {
+    self.selected_index = id.and_then(|id| self.visible().iter().position(|todo| todo.id == id)).unwrap_or(0);
+  }
+
+  /// Lists only the todos tagged `tag`, or every todo with `None`, keeping the selected todo selected if it is still
+  /// listed.
+  pub fn filter_by_tag(&mut self, tag: Option<String>) {
+    let selected = self.selected_id();
+    self.tag_filter = tag;
+    self.select_id(selected);
+  }
+
+  pub fn selected_index(&self) -> usize {
+    self.selected_index
+  }
+
+  /// Remembers how many todos fit in `area`, so a page moves the selection by a screenful.
+  pub fn set_page_size(&mut self, area: Rect) {
+    self.page_size = area.height as usize;
   }
 
-  pub fn todos_to_list(&self) -> List<'_> {
-    let title_width = TODOS_LIST.iter().map(|t| t.title.width()).max().unwrap_or_default();
+  /// Reorders the listed todos, keeping the same todo selected.
+  pub fn sort(&mut self, order: TodoSort) {
+    let selected = self.selected_id();
+    order.sort(&mut self.todos);
+    if selected.is_some() {
+      self.select_id(selected);
+    }
+  }
 
-    let todos_list_items: Vec<ListItem<'_>> = TODOS_LIST
+  /// The todos passing the tag filter as list items: a [priority marker](priority_marker), titles and
+  /// [tags](tag_spans) padded to the same width, then a right-aligned column of due dates, red for those overdue as
+  /// of `today`.
+  pub fn todos_to_list(&self, today: NaiveDate) -> List<'_> {
+    let todos = self.visible();
+    let labels: Vec<(Span<'_>, Vec<Span<'_>>)> = todos
       .iter()
       .map(|t| {
-        let title = format!("{:width$}", t.title, width = title_width).to_string();
-        ListItem::new(match t.is_completed {
-          true => Line::styled(title, Style::default().crossed_out()),
-          false => Line::raw(title),
-        })
+        let title = match t.is_completed {
+          true => Span::styled(t.title.as_str(), Style::default().crossed_out()),
+          false => Span::raw(t.title.as_str()),
+        };
+        (title, tag_spans(t))
+      })
+      .collect();
+    let label_width =
+      |(title, tags): &(Span<'_>, Vec<Span<'_>>)| title.width() + tags.iter().map(Span::width).sum::<usize>();
+    let title_width = labels.iter().map(label_width).max().unwrap_or_default();
+    let dues: Vec<String> = todos.iter().map(|t| t.due.map(|due| due.to_string()).unwrap_or_default()).collect();
+    let due_width = dues.iter().map(|due| due.width()).max().unwrap_or_default();
+
+    let todos_list_items: Vec<ListItem<'_>> = todos
+      .iter()
+      .zip(labels)
+      .zip(dues)
+      .map(|((t, label), due)| {
+        let padding = " ".repeat(title_width - label_width(&label));
+        let (title, tags) = label;
+        let mut spans = vec![priority_marker(t), title];
+        spans.extend(tags);
+        spans.push(padding.into());
+        if due_width > 0 {
+          let due = format!("  {due:>due_width$}");
+          spans.push(match t.is_overdue(today) {
+            true => Span::styled(due, Style::default().red()),
+            false => Span::styled(due, Style::default().dim()),
+          });
+        }
+        ListItem::new(Line::from(spans))
       })
       .collect();
 
-    List::new(todos_list_items)
+    List::new(todos_list_items).highlight_style(Style::default().reversed())
   }
 }
 
-impl Widget for TodosLister {
+impl Widget for &TodosLister {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let list = self.todos_to_list();
+    if self.loading {
+      Paragraph::new(format!("{} Loading todos…", SPINNER_FRAMES[self.spinner_index])).dim().render(area, buf);
+      return;
+    }
+    let list = self.todos_to_list(Local::now().date_naive());
     let mut state = ListState::default().with_selected(Some(self.selected_index));
 
     StatefulWidget::render(list, area, buf, &mut state);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use ratatui::{backend::TestBackend, Terminal};
+
+  use super::*;
+
+  #[test]
+  fn test_shows_a_spinner_until_the_todos_arrive() -> color_eyre::eyre::Result<()> {
+    let mut lister = TodosLister::new(5);
+    lister.advance_spinner();
+    let mut terminal = Terminal::new(TestBackend::new(20, 1))?;
+    terminal.draw(|f| f.render_widget(&lister, f.size()))?;
+    let row: String = terminal.backend().buffer().content().iter().map(|c| c.symbol.as_str()).collect();
+    assert_eq!(row, "⠙ Loading todos…    ");
+
+    let tagged = |id, tag: &str| Todo { tags: vec![tag.to_string()], ..Todo::new(id, format!("todo {id}")) };
+    let todos = vec![tagged(1, "work"), tagged(2, "home"), tagged(3, "work")];
+    lister.set_todos(todos.clone());
+    assert!(!lister.is_loading());
+    assert_eq!(lister.selected_index(), 2);
+
+    // A tag filter picked while loading still applies once the todos arrive.
+    let mut lister = TodosLister::new(0);
+    lister.filter_by_tag(Some("work".to_string()));
+    lister.set_todos(todos);
+    assert_eq!(lister.visible().iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 3]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_due_dates_are_right_aligned_and_overdue_ones_red() -> color_eyre::eyre::Result<()> {
+    let date = |day| NaiveDate::from_ymd_opt(2024, 1, day);
+    let mut todos = vec![Todo::new(1, "milk"), Todo::new(2, "tax return"), Todo::new(3, "ｗｉｄｅ")];
+    todos[0].due = date(5);
+    todos[2].due = date(20);
+    let mut lister = TodosLister::from_todos(0, todos);
+
+    let mut terminal = Terminal::new(TestBackend::new(30, 3))?;
+    terminal.draw(|f| f.render_widget(lister.todos_to_list(date(10).unwrap()), f.size()))?;
+    let buffer = terminal.backend().buffer();
+    let row = |y| (0..30).map(|x| buffer.get(x, y).symbol.clone()).collect::<String>();
+    assert_eq!(row(0), "● milk        2024-01-05      ");
+    assert_eq!(row(1), "● tax return                  ");
+    assert_eq!(buffer.get(14, 0).fg, Color::Red);
+    assert_ne!(buffer.get(14, 2).fg, Color::Red);
+
+    lister.sort(TodoSort::Due);
+    assert_eq!(lister.todos.iter().map(|t| t.id).collect::<Vec<_>>(), [1, 3, 2]);
+    assert_eq!(lister.selected_index(), 0);
+    Ok(())
+  }
+
+  #[test]
+  fn test_priority_markers_are_coloured_and_dimmed_once_done() {
+    let mut todos = vec![Todo::new(1, "someday"), Todo::new(2, "urgent")];
+    todos[0].priority = Priority::Low;
+    todos[1].priority = Priority::High;
+    assert_eq!(priority_marker(&todos[0]).style.fg, Some(Color::Blue));
+    assert_eq!(priority_marker(&todos[1]).style.fg, Some(Color::Red));
+    assert!(!priority_marker(&todos[1]).style.add_modifier.contains(Modifier::DIM));
+
+    todos[1].is_completed = true;
+    assert!(priority_marker(&todos[1]).style.add_modifier.contains(Modifier::DIM));
+
+    let mut lister = TodosLister::from_todos(0, todos);
+    lister.sort(TodoSort::Priority);
+    assert_eq!(lister.todos.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 1]);
+    assert_eq!(lister.selected_index(), 1);
+  }
+
+  #[test]
+  fn test_filters_by_tag_and_shows_tags_after_titles() -> color_eyre::eyre::Result<()> {
+    let mut todos = vec![Todo::new(1, "milk"), Todo::new(2, "report"), Todo::new(3, "slides")];
+    todos[0].tags = vec!["errands".to_string()];
+    todos[1].tags = vec!["work".to_string()];
+    todos[2].tags = vec!["Work".to_string(), "urgent".to_string()];
+    todos[2].due = NaiveDate::from_ymd_opt(2024, 1, 5);
+    let mut lister = TodosLister::from_todos(2, todos);
+
+    lister.filter_by_tag(Some("work".to_string()));
+    assert_eq!(lister.visible().iter().map(|t| t.id).collect::<Vec<_>>(), [2, 3]);
+    assert_eq!((lister.len(), lister.selected_index()), (2, 1));
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 3))?;
+    terminal.draw(|f| f.render_widget(lister.todos_to_list(NaiveDate::MIN), f.size()))?;
+    let buffer = terminal.backend().buffer();
+    let row = |y| (0..40).map(|x| buffer.get(x, y).symbol.clone()).collect::<String>();
+    assert_eq!(row(0), "● report #work                          ");
+    assert_eq!(row(1), "● slides #Work #urgent  2024-01-05      ");
+    assert_eq!(row(2), " ".repeat(40));
+    assert_eq!(buffer.get(9, 0).fg, Color::Cyan);
+
+    // The selected todo is filtered out, so the first listed one is selected instead.
+    lister.select_first();
+    lister.filter_by_tag(Some("errands".to_string()));
+    assert_eq!(lister.selected_index(), 0);
+    lister.filter_by_tag(None);
+    assert_eq!(lister.len(), 3);
+    Ok(())
+  }
+}
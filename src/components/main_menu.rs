@@ -1,44 +1,75 @@
 use std::{collections::HashMap, time::Duration};
 
 use color_eyre::eyre::Result;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use lazy_static::lazy_static;
 use log::error;
 use ratatui::{prelude::*, widgets::*};
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::trace;
 use tui_input::{backend::crossterm::EventHandler, Input};
+use unicode_width::UnicodeWidthStr;
 
-use super::{list_todos::TodosLister, Component, Frame};
+use super::{
+  area_contains, focused_border_style, key_hint, layout_cache::LayoutCache, list_todos::TodosLister,
+  navigable::Navigable, Applicability, Component, Frame,
+};
 use crate::{
-  actions::{Action, HomeAction, ListNavDirection},
-  config::{key_event_to_string, KeyBindings},
+  actions::{Action, EngineAction, HomeAction, ListNavDirection, MainMenuAction},
+  app,
+  config::{key_event_to_string, Config, KeyBindings, TabsConfig},
 };
 
-const TODO_TABS: [&str; 4] = ["List", "Add", "Edit", "Delete"];
-
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone)]
 struct MainMenuTabs {
   pub item_index: usize,
   pub is_item_selected: bool,
+  pub tabs: TabsConfig,
+}
+
+impl Navigable for MainMenuTabs {
+  fn len(&self) -> usize {
+    self.tabs.len()
+  }
+
+  fn index(&self) -> usize {
+    self.item_index
+  }
+
+  fn set_index(&mut self, index: usize) {
+    self.item_index = index;
+  }
+
+  fn wraps(&self) -> bool {
+    true
+  }
 }
 
 impl MainMenuTabs {
-  pub fn navigate_list(&mut self, dir: ListNavDirection) {
-    match (dir, self.item_index) {
-      (ListNavDirection::Left, 0) => self.item_index = TODO_TABS.len() - 1,
-      (ListNavDirection::Left, _) => self.item_index -= 1,
-      (ListNavDirection::Right, _) => {
-        self.item_index = if self.item_index == TODO_TABS.len() - 1 { 0 } else { self.item_index + 1 }
-      },
-      _ => {},
+  /// The action configured for the selected tab.
+  pub fn action(&self) -> Option<Action> {
+    self.tabs.get(self.item_index).map(|tab| tab.action.clone())
+  }
+
+  /// Returns the tab drawn under `column` when the tabs are rendered into `area`.
+  pub fn tab_at(&self, area: Rect, column: u16) -> Option<usize> {
+    let divider_width = symbols::DOT.width() as u16;
+    let mut x = area.left();
+    for (i, tab) in self.tabs.iter().enumerate() {
+      // Each title is padded by a space on either side, followed by the divider.
+      let end = x + 1 + tab.label.width() as u16 + 1;
+      if (x..end).contains(&column) {
+        return Some(i);
+      }
+      x = end + divider_width;
     }
+    None
   }
 }
 
-impl Widget for MainMenuTabs {
+impl Widget for &MainMenuTabs {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    Tabs::new(TODO_TABS.to_vec())
+    Tabs::new(self.tabs.iter().map(|tab| tab.label.as_str()).collect())
       .style(Style::default().white())
       .highlight_style(Style::default().yellow().on_blue().underlined())
       .select(self.item_index)
@@ -52,12 +83,22 @@ pub struct MainMenu {
   pub show_help: bool,
   pub action_tx: Option<UnboundedSender<Action>>,
   pub keymap: HashMap<Vec<KeyEvent>, Action>,
+  pub config: Config,
   main_menu_tabs: MainMenuTabs,
+  todos_lister: TodosLister,
+  /// Where the tabs and the todos list were last drawn, for hit-testing mouse events.
+  tabs_area: Rect,
+  list_area: Rect,
+  layout: LayoutCache,
+  focused: bool,
 }
 
 impl MainMenu {
+  const NAVIGATE_LEFT: Action = Action::MainMenu(MainMenuAction::NavigateTabs(ListNavDirection::Left, 1));
+  const NAVIGATE_RIGHT: Action = Action::MainMenu(MainMenuAction::NavigateTabs(ListNavDirection::Right, 1));
+
   pub fn new() -> Self {
-    Self::default()
+    Self { todos_lister: TodosLister::new(0), ..Self::default() }
   }
 
   pub fn set_keymap(&mut self, keymap: HashMap<Vec<KeyEvent>, Action>) {
@@ -65,9 +106,22 @@ impl MainMenu {
   }
 
   pub fn render_main_menu_border(&mut self, buf: &mut Buffer, area: Rect) {
-    Block::default().title("Main Menu").border_set(symbols::border::DOUBLE).borders(Borders::ALL).render(area, buf);
+    let border_style =
+      if self.focused { focused_border_style(&self.config, app::Mode::MainMenu) } else { Style::default() };
     Block::default()
-      .title("Use 🞀 / 🞂 to navigate the top menu tabs.")
+      .title("Main Menu")
+      .border_set(symbols::border::DOUBLE)
+      .borders(Borders::ALL)
+      .border_style(border_style)
+      .render(area, buf);
+    Block::default()
+      .title(Line::from(vec![
+        "Use ".into(),
+        key_hint(self.config.binding_for(app::Mode::MainMenu, &Self::NAVIGATE_LEFT), Style::default()),
+        " / ".into(),
+        key_hint(self.config.binding_for(app::Mode::MainMenu, &Self::NAVIGATE_RIGHT), Style::default()),
+        " to navigate the top menu tabs.".into(),
+      ]))
       .title_alignment(Alignment::Left)
       .title_position(block::Position::Bottom)
       .render(area.inner(&Margin::new(1, 0)), buf);
@@ -76,7 +130,10 @@ impl MainMenu {
 
 impl Component for MainMenu {
   fn register_config_handler(&mut self, config: crate::config::Config) -> Result<()> {
-    self.set_keymap(config.keybindings.get(&crate::app::Mode::MainMenu).unwrap().clone());
+    self.set_keymap(config.keymap(crate::app::Mode::MainMenu));
+    self.main_menu_tabs.tabs = config.tabs.clone();
+    self.main_menu_tabs.item_index = self.main_menu_tabs.item_index.min(config.tabs.len().saturating_sub(1));
+    self.config = config;
 
     Ok(())
   }
@@ -86,28 +143,233 @@ impl Component for MainMenu {
     Ok(())
   }
 
+  fn handle_mouse_events(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+    let (column, row) = (mouse.column, mouse.row);
+    match mouse.kind {
+      MouseEventKind::Down(MouseButton::Left) if area_contains(self.tabs_area, column, row) => {
+        if let Some(index) = self.main_menu_tabs.tab_at(self.tabs_area, column) {
+          self.main_menu_tabs.item_index = index;
+        }
+      },
+      MouseEventKind::ScrollUp if area_contains(self.list_area, column, row) => self.todos_lister.select_prev(1),
+      MouseEventKind::ScrollDown if area_contains(self.list_area, column, row) => self.todos_lister.select_next(1),
+      _ => {},
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
-    if let Action::Home(HomeAction::NavigateList(dir)) = action {
-      self.main_menu_tabs.navigate_list(dir);
+    match action {
+      Action::MainMenu(MainMenuAction::NavigateTabs(
+        dir @ (ListNavDirection::Left | ListNavDirection::Right | ListNavDirection::First | ListNavDirection::Last),
+        count,
+      )) => self.main_menu_tabs.navigate(dir, count),
+      Action::Home(HomeAction::NavigateList(dir, count))
+        if !matches!(dir, ListNavDirection::Left | ListNavDirection::Right) =>
+      {
+        self.todos_lister.navigate(dir, count)
+      },
+      Action::MainMenu(MainMenuAction::ActivateTab) => return Ok(self.main_menu_tabs.action()),
+      Action::Engine(EngineAction::Resize(..) | EngineAction::Refresh) => self.layout.invalidate(),
+      Action::Engine(EngineAction::Render) => self.todos_lister.advance_spinner(),
+      Action::Home(HomeAction::TodosLoaded(todos)) => self.todos_lister.set_todos(todos.todos().to_vec()),
+      Action::Home(HomeAction::SortBy(order)) => self.todos_lister.sort(order),
+      Action::Home(HomeAction::FilterByTag(tag)) => self.todos_lister.filter_by_tag(tag),
+      _ => {},
     }
     Ok(None)
   }
 
+  fn hinted_actions(&self) -> Vec<(app::Mode, Action)> {
+    vec![(app::Mode::MainMenu, Self::NAVIGATE_LEFT), (app::Mode::MainMenu, Self::NAVIGATE_RIGHT)]
+  }
+
+  fn can_handle(&self, action: &Action) -> Applicability {
+    match action {
+      Action::MainMenu(_) => Applicability::Handled,
+      Action::Home(HomeAction::NavigateList(ListNavDirection::Left | ListNavDirection::Right, _)) => {
+        Applicability::NotApplicable("The todos list only moves up and down".to_string())
+      },
+      Action::Home(HomeAction::NavigateList(..) | HomeAction::SortBy(_)) if self.todos_lister.is_loading() => {
+        Applicability::NotApplicable("Still loading the todos".to_string())
+      },
+      Action::Home(HomeAction::NavigateList(..)) if self.todos_lister.is_empty() => {
+        Applicability::NotApplicable("No todos to select".to_string())
+      },
+      Action::Home(HomeAction::NavigateList(..) | HomeAction::SortBy(_) | HomeAction::FilterByTag(_)) => {
+        Applicability::Handled
+      },
+      _ => Applicability::Ignored,
+    }
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    Some(0)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn layout_areas(&self) -> Vec<(&'static str, Rect)> {
+    vec![("tabs", self.tabs_area), ("list", self.list_area)]
+  }
+
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
     let main_menu_inner = rect.inner(&Margin::new(1, 1));
-    let chunks = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Length(1), Constraint::Min(0)])
-      .split(main_menu_inner);
+    let chunks = self.layout.split(
+      &Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(1), Constraint::Min(0)]),
+      main_menu_inner,
+    );
+
+    self.tabs_area = chunks[0];
+    self.list_area = chunks[1];
+    self.todos_lister.set_page_size(chunks[1]);
 
     self.render_main_menu_border(f.buffer_mut(), rect);
-    f.render_widget(self.main_menu_tabs, chunks[0]);
+    f.render_widget(&self.main_menu_tabs, chunks[0]);
 
     match self.main_menu_tabs.item_index {
-      0 => f.render_widget(TodosLister::new(0), chunks[1]),
-      _ => unreachable!(),
+      0 => f.render_widget(&self.todos_lister, chunks[1]),
+      // Only the list tab has content so far.
+      _ => f.render_widget(Paragraph::new("Nothing here yet.").dim(), chunks[1]),
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+  use pretty_assertions::assert_eq;
+  use ratatui::{backend::TestBackend, Terminal};
+
+  use super::*;
+  use crate::{config::TabConfig, todos::Todo};
+
+  fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent { kind, column, row, modifiers: KeyModifiers::empty() }
+  }
+
+  fn drawn_main_menu() -> Result<MainMenu> {
+    let todos = (1..=3).map(|id| Todo::new(id, format!("todo {id}"))).collect();
+    let mut main_menu = MainMenu { todos_lister: TodosLister::from_todos(0, todos), ..MainMenu::default() };
+    let mut terminal = Terminal::new(TestBackend::new(60, 10))?;
+    terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
+    Ok(main_menu)
+  }
+
+  #[test]
+  fn test_clicking_a_tab_selects_it() -> Result<()> {
+    let mut main_menu = drawn_main_menu()?;
+    let (x, y) = (main_menu.tabs_area.x, main_menu.tabs_area.y);
+
+    // " List " takes 6 columns, then the divider, then " Add ".
+    main_menu.handle_mouse_events(mouse(MouseEventKind::Down(MouseButton::Left), x + 8, y))?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, 1);
+
+    main_menu.handle_mouse_events(mouse(MouseEventKind::Down(MouseButton::Left), x + 2, y + 1))?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, 1);
+    Ok(())
+  }
+
+  #[test]
+  fn test_navigate_tabs_moves_selected_tab() -> Result<()> {
+    let mut main_menu = MainMenu::new();
+    main_menu.register_config_handler(Config::default())?;
+
+    main_menu.update(MainMenuAction::NavigateTabs(ListNavDirection::Right, 1).into())?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, 1);
+    main_menu.update(MainMenuAction::NavigateTabs(ListNavDirection::Left, 1).into())?;
+    main_menu.update(MainMenuAction::NavigateTabs(ListNavDirection::Left, 1).into())?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, main_menu.main_menu_tabs.len() - 1);
+    // Counts wrap round as many times as they need to.
+    main_menu
+      .update(MainMenuAction::NavigateTabs(ListNavDirection::Right, main_menu.main_menu_tabs.len() * 2 + 2).into())?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, 1);
+    Ok(())
+  }
+
+  #[test]
+  fn test_configured_extra_tab_renders_and_activates() -> Result<()> {
+    let mut config = Config::default();
+    config.tabs.0.push(TabConfig { label: "Archive".to_string(), action: EngineAction::Refresh.into() });
+    let mut main_menu = MainMenu::new();
+    main_menu.register_config_handler(config)?;
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 10))?;
+    terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
+    let row: String = (0..60).map(|x| terminal.backend().buffer().get(x, 1).symbol.clone()).collect();
+    assert!(row.contains("Archive"), "{row}");
+
+    main_menu.update(MainMenuAction::NavigateTabs(ListNavDirection::Left, 1).into())?;
+    assert_eq!(main_menu.update(MainMenuAction::ActivateTab.into())?, Some(EngineAction::Refresh.into()));
+    Ok(())
+  }
+
+  #[test]
+  fn test_wheel_over_list_moves_selection() -> Result<()> {
+    let mut main_menu = drawn_main_menu()?;
+    let (x, y) = (main_menu.list_area.x, main_menu.list_area.y);
+
+    for _ in 0..5 {
+      main_menu.handle_mouse_events(mouse(MouseEventKind::ScrollDown, x, y))?;
+    }
+    assert_eq!(main_menu.todos_lister.selected_index(), 2);
+
+    main_menu.handle_mouse_events(mouse(MouseEventKind::ScrollUp, x, y))?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 1);
+
+    main_menu.handle_mouse_events(mouse(MouseEventKind::ScrollUp, x, 0))?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 1);
+    Ok(())
+  }
+
+  #[test]
+  fn test_list_pages_by_its_drawn_height() -> Result<()> {
+    let todos = (1..=20).map(|id| Todo::new(id, format!("todo {id}"))).collect();
+    let mut main_menu = MainMenu { todos_lister: TodosLister::from_todos(0, todos), ..MainMenu::default() };
+    let mut terminal = Terminal::new(TestBackend::new(60, 10))?;
+    terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
+    // 10 rows less the borders and the tabs leave 7 for the list.
+    assert_eq!(main_menu.todos_lister.page_size(), 7);
+
+    main_menu.update(HomeAction::NavigateList(ListNavDirection::PageDown, 1).into())?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 7);
+    main_menu.update(HomeAction::NavigateList(ListNavDirection::PageDown, 2).into())?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 19);
+    main_menu.update(HomeAction::NavigateList(ListNavDirection::PageUp, 1).into())?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 12);
+    main_menu.update(HomeAction::NavigateList(ListNavDirection::First, 1).into())?;
+    assert_eq!(main_menu.todos_lister.selected_index(), 0);
+
+    main_menu.update(MainMenuAction::NavigateTabs(ListNavDirection::Last, 1).into())?;
+    assert_eq!(main_menu.main_menu_tabs.item_index, main_menu.main_menu_tabs.len() - 1);
+    Ok(())
+  }
+
+  #[test]
+  fn test_redraws_reuse_the_layout_until_resized_or_refreshed() -> Result<()> {
+    let mut main_menu = MainMenu::new();
+    let mut terminal = Terminal::new(TestBackend::new(60, 10))?;
+    for _ in 0..100 {
+      terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
+    }
+    assert_eq!(main_menu.layout.splits(), 1);
+
+    main_menu.update(EngineAction::Resize(40, 8).into())?;
+    terminal.backend_mut().resize(40, 8);
+    for _ in 0..100 {
+      terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
     }
+    assert_eq!(main_menu.layout.splits(), 2);
+    assert_eq!(main_menu.list_area, Rect::new(1, 2, 38, 5));
 
+    // A refresh rebuilds the layout even though the area is the same.
+    main_menu.update(EngineAction::Refresh.into())?;
+    terminal.draw(|f| main_menu.draw(f, f.size()).unwrap())?;
+    assert_eq!(main_menu.layout.splits(), 3);
     Ok(())
   }
 }
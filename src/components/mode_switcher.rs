@@ -2,12 +2,13 @@ use std::collections::HashMap;
 
 use crate::actions::Action;
 use crate::actions::EngineAction;
-use crate::actions::HomeAction;
 use crate::actions::ListNavDirection;
 use crate::app::Mode;
 
-use super::Component;
+use super::{centered_rect, focused_border_style, navigable::Navigable, popup_block, Applicability, Component};
+use crate::config::Config;
 use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
 use lazy_static::lazy_static;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
@@ -19,49 +20,80 @@ lazy_static! {
 #[derive(Default)]
 pub struct ModeSwitcher {
   show_menu: bool,
+  /// Index of the mode that is actually active.
   current_index: usize,
+  /// Index of the highlighted entry, which only becomes active once confirmed.
+  pending_index: usize,
   mode_list_state: ListState,
+  config: Config,
+  focused: bool,
+}
+
+impl Navigable for ModeSwitcher {
+  fn len(&self) -> usize {
+    MODES.len()
+  }
+
+  fn index(&self) -> usize {
+    self.pending_index
+  }
+
+  fn set_index(&mut self, index: usize) {
+    self.pending_index = index;
+    self.mode_list_state.select(Some(index));
+  }
 }
 
 impl ModeSwitcher {
   pub fn new(active_mode: Mode) -> Self {
     let index = MODES.iter().map(|(s, m)| m).enumerate().find(|(i, m)| **m == active_mode).map(|(i, m)| i).unwrap();
 
-    Self { show_menu: false, current_index: index, mode_list_state: ListState::default().with_selected(Some(index)) }
+    Self {
+      show_menu: false,
+      current_index: index,
+      pending_index: index,
+      mode_list_state: ListState::default().with_selected(Some(index)),
+      ..Self::default()
+    }
   }
 
-  fn select_mode(&mut self, offset: isize) -> Option<Action> {
-    let new_index_option = self.current_index.checked_add_signed(offset).map(|ni| ni.clamp(0, MODES.len() - 1));
-
-    match new_index_option {
-      Some(ni) => self.current_index = ni,
-      None => return None,
-    };
+  /// Makes the highlighted mode the active one and closes the menu.
+  fn confirm(&mut self) -> Option<Action> {
+    self.show_menu = false;
+    self.current_index = self.pending_index;
+    MODES.get(self.current_index).map(|(s, m)| EngineAction::ChangeMode(*m).into())
+  }
 
+  /// Closes the menu, moving the highlight back to the active mode.
+  fn cancel(&mut self) {
+    self.show_menu = false;
+    self.pending_index = self.current_index;
     self.mode_list_state.select(Some(self.current_index));
-
-    MODES.get(self.current_index).map(|(s, m)| EngineAction::ChangeMode(*m).into())
   }
 
   fn draw_menu(&mut self, f: &mut Frame, rect: Rect) {
-    let location = Layout::default()
-      .direction(Direction::Horizontal)
-      .constraints([Constraint::Percentage(20), Constraint::Ratio(1, 5), Constraint::Min(0)])
-      .split(rect.inner(&Margin::new(1, 1)))[0];
-    let location = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Percentage(10), Constraint::Min(MODES.len() as u16 + 5)])
-      .split(location)[0];
-
-    let background = Block::new()
-      .light_blue()
-      .on_black()
-      .title("Select Mode")
-      .borders(Borders::ALL)
-      .title_alignment(Alignment::Left)
-      .title_position(block::Position::Top);
-
-    let mode_listitems: Vec<ListItem> = MODES.iter().map(|(s, m)| ListItem::new(*s)).collect();
+    let location = centered_rect(30, 40, rect);
+
+    let border_style = if self.focused {
+      focused_border_style(&self.config, MODES[self.current_index].1)
+    } else {
+      self.config.theme.border_style()
+    };
+    let background = popup_block("Select Mode").style(self.config.theme.border_style()).border_style(border_style);
+    let background = match self.config.theme.monochrome {
+      true => background,
+      false => background.on_black(),
+    };
+
+    // Each mode is listed with any key that jumps straight to it, so the menu teaches the shortcut.
+    let active_mode = MODES[self.current_index].1;
+    let mode_listitems: Vec<ListItem> = MODES
+      .iter()
+      .map(|(s, m)| match self.config.binding_for(active_mode, &EngineAction::ChangeMode(*m).into()) {
+        Some(keys) => ListItem::new(Line::from(vec![Span::raw(*s), format!(" ({keys})").dim()])),
+        None => ListItem::new(*s),
+      })
+      .collect();
     let list = List::new(mode_listitems)
       .style(Style::default())
       .highlight_style(Style::default().underlined())
@@ -74,21 +106,73 @@ impl ModeSwitcher {
 }
 
 impl Component for ModeSwitcher {
-  fn update(&mut self, action: crate::actions::Action) -> Result<Option<crate::actions::Action>> {
-    let new_action = match action {
-      Action::Home(h) => match h {
-        HomeAction::NavigateList(ListNavDirection::Up) => self.select_mode(-1),
-        HomeAction::NavigateList(ListNavDirection::Down) => self.select_mode(1),
-        _ => None,
+  /// Enter, Esc and Backspace answer the open menu whatever they are bound to, and the arrow keys move through it on
+  /// top of the keys bound to [`EngineAction::ModeSwitcherNav`].
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    match key.code {
+      KeyCode::Up if self.show_menu => Ok(Some(EngineAction::ModeSwitcherNav(ListNavDirection::Up).into())),
+      KeyCode::Down if self.show_menu => Ok(Some(EngineAction::ModeSwitcherNav(ListNavDirection::Down).into())),
+      KeyCode::Enter if self.show_menu => Ok(self.confirm()),
+      KeyCode::Esc if self.show_menu => {
+        self.cancel();
+        Ok(None)
+      },
+      KeyCode::Backspace if self.show_menu => {
+        self.cancel();
+        Ok(Some(EngineAction::PreviousMode.into()))
       },
-      Action::Engine(EngineAction::ToggleShowModeSwitcher) => {
-        self.show_menu = !self.show_menu;
-        None
+      _ => Ok(None),
+    }
+  }
+
+  fn can_handle(&self, action: &Action) -> Applicability {
+    match action {
+      Action::Engine(EngineAction::ModeSwitcherNav(_)) if self.show_menu => Applicability::Handled,
+      Action::Engine(EngineAction::ModeSwitcherNav(_)) => {
+        Applicability::NotApplicable("The mode switcher isn't open".to_string())
       },
-      _ => None,
+      _ => Applicability::Ignored,
+    }
+  }
+
+  fn update(&mut self, action: crate::actions::Action) -> Result<Option<crate::actions::Action>> {
+    match action {
+      Action::Engine(EngineAction::ModeSwitcherNav(dir)) if self.show_menu => self.navigate(dir, 1),
+      Action::Engine(EngineAction::ToggleShowModeSwitcher) if self.show_menu => self.cancel(),
+      Action::Engine(EngineAction::ToggleShowModeSwitcher) => self.show_menu = true,
+      _ => {},
     };
 
-    Ok(new_action)
+    Ok(None)
+  }
+
+  /// Keeps the list in step with mode changes that didn't come from the menu, e.g. going back a mode or a key bound
+  /// to [`EngineAction::ChangeMode`]. The menu closes, since the choice it was open for has been made.
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.show_menu = false;
+    if let Some(index) = MODES.iter().position(|(_, m)| *m == to) {
+      self.current_index = index;
+      self.pending_index = index;
+      self.mode_list_state.select(Some(index));
+    }
+    Ok(())
+  }
+
+  fn has_focus(&self) -> bool {
+    self.show_menu
+  }
+
+  fn focus_order(&self) -> Option<u8> {
+    self.show_menu.then_some(30)
+  }
+
+  fn set_focused(&mut self, focused: bool) {
+    self.focused = focused;
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    Ok(())
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
@@ -99,3 +183,88 @@ impl Component for ModeSwitcher {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_navigation_only_changes_mode_on_confirm() -> Result<()> {
+    let mut switcher = ModeSwitcher::new(Mode::MainMenu);
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+
+    assert_eq!(switcher.update(EngineAction::ModeSwitcherNav(ListNavDirection::Down).into())?, None);
+    switcher.update(EngineAction::ModeSwitcherNav(ListNavDirection::First).into())?;
+    assert_eq!(switcher.pending_index, 0);
+    switcher.update(EngineAction::ModeSwitcherNav(ListNavDirection::PageDown).into())?;
+    assert_eq!(switcher.pending_index, 1);
+    assert_eq!(
+      switcher.handle_key_events(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()))?,
+      Some(EngineAction::ChangeMode(Mode::Home).into())
+    );
+    assert!(!switcher.show_menu);
+    Ok(())
+  }
+
+  #[test]
+  fn test_closing_without_confirm_restores_highlight() -> Result<()> {
+    let mut switcher = ModeSwitcher::new(Mode::MainMenu);
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+    switcher.update(EngineAction::ModeSwitcherNav(ListNavDirection::Down).into())?;
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+
+    assert_eq!(switcher.mode_list_state.selected(), Some(0));
+    assert_eq!(switcher.pending_index, 0);
+    Ok(())
+  }
+
+  #[test]
+  fn test_direct_jump_syncs_and_closes_the_menu() -> Result<()> {
+    let mut switcher = ModeSwitcher::new(Mode::MainMenu);
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+    switcher.on_mode_change(Mode::MainMenu, Mode::Home)?;
+
+    assert!(!switcher.has_focus());
+    assert_eq!((switcher.current_index, switcher.pending_index), (1, 1));
+    assert_eq!(switcher.mode_list_state.selected(), Some(1));
+    // Reopened, the menu starts from the mode that was jumped to.
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+    assert_eq!(switcher.mode_list_state.selected(), Some(1));
+    Ok(())
+  }
+
+  #[test]
+  fn test_navigation_ignored_while_hidden() -> Result<()> {
+    let mut switcher = ModeSwitcher::new(Mode::Home);
+    assert!(!switcher.has_focus());
+
+    let up: Action = EngineAction::ModeSwitcherNav(ListNavDirection::Up).into();
+    assert_eq!(switcher.update(up.clone())?, None);
+    assert_eq!(switcher.pending_index, 1);
+    assert_eq!(switcher.can_handle(&up), Applicability::NotApplicable("The mode switcher isn't open".to_string()));
+    assert_eq!(switcher.handle_key_events(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()))?, None);
+    assert_eq!(switcher.handle_key_events(KeyEvent::new(KeyCode::Up, KeyModifiers::empty()))?, None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_home_navigation_no_longer_moves_the_menu() -> Result<()> {
+    let mut switcher = ModeSwitcher::new(Mode::MainMenu);
+    switcher.update(EngineAction::ToggleShowModeSwitcher.into())?;
+
+    let home_down: Action = crate::actions::HomeAction::NavigateList(ListNavDirection::Down, 1).into();
+    switcher.update(home_down.clone())?;
+    assert_eq!(switcher.can_handle(&home_down), Applicability::Ignored);
+    assert_eq!(switcher.pending_index, 0);
+    // The arrow keys still move through the open menu, by way of its own action.
+    assert_eq!(
+      switcher.handle_key_events(KeyEvent::new(KeyCode::Down, KeyModifiers::empty()))?,
+      Some(EngineAction::ModeSwitcherNav(ListNavDirection::Down).into())
+    );
+    Ok(())
+  }
+}
@@ -0,0 +1,259 @@
+use crate::actions::ListNavDirection;
+
+/// Something with a selected position in a list of `len()` items, moved through with [`ListNavDirection`]s.
+///
+/// Left and Up move back, Right and Down forward, by `count` steps. First and Last jump to either end, and PageUp and
+/// PageDown move `count` pages of [`Navigable::page_size`] items. Nothing moves while the list is empty.
+///
+/// Whether stepping off one end comes round to the other is up to [`Navigable::wraps`], so every way of moving through
+/// the same list agrees on it.
+pub trait Navigable {
+  fn len(&self) -> usize;
+
+  fn index(&self) -> usize;
+
+  fn set_index(&mut self, index: usize);
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// How many items PageUp and PageDown move past, usually however many fit on screen.
+  fn page_size(&self) -> usize {
+    1
+  }
+
+  /// Whether stepping off either end comes round to the other one, rather than stopping there.
+  fn wraps(&self) -> bool {
+    false
+  }
+
+  /// Moves the way the list [wraps](Navigable::wraps).
+  fn navigate(&mut self, dir: ListNavDirection, count: usize) {
+    match self.wraps() {
+      true => self.navigate_wrapping(dir, count),
+      false => self.navigate_clamped(dir, count),
+    }
+  }
+
+  fn select_next(&mut self, count: usize) {
+    self.navigate(ListNavDirection::Down, count);
+  }
+
+  fn select_prev(&mut self, count: usize) {
+    self.navigate(ListNavDirection::Up, count);
+  }
+
+  fn select_first(&mut self) {
+    self.navigate(ListNavDirection::First, 1);
+  }
+
+  fn select_last(&mut self) {
+    self.navigate(ListNavDirection::Last, 1);
+  }
+
+  /// Moves `pages` pages forward, or back if negative.
+  fn select_page(&mut self, pages: isize) {
+    match pages < 0 {
+      true => self.navigate(ListNavDirection::PageUp, pages.unsigned_abs()),
+      false => self.navigate(ListNavDirection::PageDown, pages.unsigned_abs()),
+    }
+  }
+
+  /// Moves, going round to the other end when stepping off either one. Jumps and pages stop at the ends instead.
+  fn navigate_wrapping(&mut self, dir: ListNavDirection, count: usize) {
+    let len = self.len();
+    if len == 0 {
+      return;
+    }
+    let index = self.index().min(len - 1);
+    let steps = count % len;
+    match dir {
+      ListNavDirection::Left | ListNavDirection::Up => self.set_index((index + len - steps) % len),
+      ListNavDirection::Right | ListNavDirection::Down => self.set_index((index + steps) % len),
+      _ => self.navigate_clamped(dir, count),
+    }
+  }
+
+  /// Moves, stopping at either end.
+  fn navigate_clamped(&mut self, dir: ListNavDirection, count: usize) {
+    let len = self.len();
+    if len == 0 {
+      return;
+    }
+    let index = self.index().min(len - 1);
+    let pages = self.page_size().max(1).saturating_mul(count);
+    self.set_index(match dir {
+      ListNavDirection::Left | ListNavDirection::Up => index.saturating_sub(count),
+      ListNavDirection::Right | ListNavDirection::Down => index.saturating_add(count).min(len - 1),
+      ListNavDirection::First => 0,
+      ListNavDirection::Last => len - 1,
+      ListNavDirection::PageUp => index.saturating_sub(pages),
+      ListNavDirection::PageDown => index.saturating_add(pages).min(len - 1),
+    });
+  }
+}
+
+/// A [`Navigable`] over an index stored elsewhere, for components that keep more than one selection.
+pub struct ListCursor<'a> {
+  pub index: &'a mut usize,
+  pub len: usize,
+  pub wraps: bool,
+}
+
+impl Navigable for ListCursor<'_> {
+  fn len(&self) -> usize {
+    self.len
+  }
+
+  fn index(&self) -> usize {
+    *self.index
+  }
+
+  fn set_index(&mut self, index: usize) {
+    *self.index = index;
+  }
+
+  fn wraps(&self) -> bool {
+    self.wraps
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn steps(index: usize, len: usize, dir: ListNavDirection, count: usize, wrap: bool) -> usize {
+    let mut index = index;
+    ListCursor { index: &mut index, len, wraps: wrap }.navigate(dir, count);
+    index
+  }
+
+  fn step(index: usize, len: usize, dir: ListNavDirection, wrap: bool) -> usize {
+    steps(index, len, dir, 1, wrap)
+  }
+
+  #[test]
+  fn test_edges_wrap_or_clamp() {
+    use ListNavDirection::*;
+
+    assert_eq!(step(0, 4, Left, true), 3);
+    assert_eq!(step(0, 4, Up, true), 3);
+    assert_eq!(step(3, 4, Right, true), 0);
+    assert_eq!(step(3, 4, Down, true), 0);
+    assert_eq!(step(1, 4, Right, true), 2);
+
+    assert_eq!(step(0, 4, Up, false), 0);
+    assert_eq!(step(3, 4, Down, false), 3);
+    assert_eq!(step(2, 4, Up, false), 1);
+
+    // A single item stays selected, and an empty list leaves the index alone.
+    assert_eq!(step(0, 1, Right, true), 0);
+    assert_eq!(step(5, 0, Left, true), 5);
+    assert_eq!(step(5, 0, Down, false), 5);
+  }
+
+  #[test]
+  fn test_counts_wrap_or_clamp() {
+    use ListNavDirection::*;
+
+    assert_eq!(steps(1, 4, Right, 3, true), 0);
+    assert_eq!(steps(1, 4, Left, 3, true), 2);
+    assert_eq!(steps(1, 4, Left, 9, true), 0);
+    assert_eq!(steps(1, 4, Right, 0, true), 1);
+
+    assert_eq!(steps(1, 4, Down, 3, false), 3);
+    assert_eq!(steps(1, 4, Up, 3, false), 0);
+    assert_eq!(steps(1, 4, Down, usize::MAX, false), 3);
+  }
+
+  struct Paged {
+    index: usize,
+    len: usize,
+    page_size: usize,
+    wraps: bool,
+  }
+
+  impl Navigable for Paged {
+    fn len(&self) -> usize {
+      self.len
+    }
+
+    fn index(&self) -> usize {
+      self.index
+    }
+
+    fn set_index(&mut self, index: usize) {
+      self.index = index;
+    }
+
+    fn page_size(&self) -> usize {
+      self.page_size
+    }
+
+    fn wraps(&self) -> bool {
+      self.wraps
+    }
+  }
+
+  #[test]
+  fn test_jumps_and_pages_stop_at_the_ends() {
+    use ListNavDirection::*;
+
+    let paged = |index, len, dir, count, wraps| {
+      let mut list = Paged { index, len, page_size: 3, wraps };
+      list.navigate(dir, count);
+      list.index
+    };
+
+    assert_eq!(paged(2, 5, First, 1, false), 0);
+    assert_eq!(paged(2, 5, Last, 1, true), 4);
+    assert_eq!(paged(0, 10, PageDown, 2, false), 6);
+    assert_eq!(paged(7, 10, PageUp, 1, false), 4);
+    // Paging past either end of a short list stops there rather than wrapping round.
+    assert_eq!(paged(3, 5, PageDown, 1, true), 4);
+    assert_eq!(paged(1, 5, PageUp, 1, true), 0);
+    assert_eq!(paged(1, 2, PageDown, usize::MAX, false), 1);
+    // Without a page size of their own, pages are single steps.
+    assert_eq!(steps(1, 4, PageDown, 1, false), 2);
+    assert_eq!(steps(0, 0, Last, 1, false), 0);
+  }
+
+  #[test]
+  fn test_selection_helpers_follow_the_wrap_policy() {
+    for wraps in [false, true] {
+      let mut list = Paged { index: 0, len: 10, page_size: 4, wraps };
+      list.select_prev(1);
+      assert_eq!(list.index, if wraps { 9 } else { 0 }, "wraps: {wraps}");
+      list.select_next(1);
+      assert_eq!(list.index, if wraps { 0 } else { 1 }, "wraps: {wraps}");
+      list.select_last();
+      assert_eq!(list.index, 9);
+      list.select_page(-2);
+      assert_eq!(list.index, 1);
+      list.select_page(1);
+      assert_eq!(list.index, 5);
+      list.select_page(5);
+      assert_eq!(list.index, 9);
+      list.select_first();
+      assert_eq!(list.index, 0);
+
+      // A single item stays selected whichever way it is moved.
+      let mut single = Paged { index: 0, len: 1, page_size: 4, wraps };
+      single.select_next(3);
+      single.select_prev(1);
+      single.select_page(-1);
+      single.select_last();
+      assert_eq!(single.index, 0);
+
+      // An empty list leaves the index where it was.
+      let mut empty = Paged { index: 2, len: 0, page_size: 4, wraps };
+      empty.select_next(1);
+      empty.select_first();
+      empty.select_page(1);
+      assert_eq!(empty.index, 2);
+    }
+  }
+}
@@ -0,0 +1,88 @@
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::prelude::Rect;
+
+use super::{confirm_dialog::ConfirmDialog, Component, Frame};
+use crate::actions::{Action, EngineAction};
+
+/// Asks whether to quit anyway when some component couldn't save its changes, opened by
+/// [`EngineAction::ConfirmQuit`].
+#[derive(Debug, Default)]
+pub struct QuitPrompt {
+  dialog: Option<ConfirmDialog>,
+}
+
+impl QuitPrompt {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_open(&self) -> bool {
+    self.dialog.is_some()
+  }
+}
+
+impl Component for QuitPrompt {
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if action == EngineAction::ConfirmQuit.into() {
+      self.dialog = Some(ConfirmDialog::new(
+        "Quit without saving? (y/n)",
+        EngineAction::ForceQuit.into(),
+        EngineAction::Notify("Not quitting".to_string()).into(),
+      ));
+    }
+    Ok(None)
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    let Some(dialog) = &mut self.dialog else {
+      return Ok(None);
+    };
+    let answer = dialog.handle_key_events(key)?;
+    if answer.is_some() {
+      self.dialog = None;
+    }
+    Ok(answer)
+  }
+
+  fn captures_text_input(&self) -> bool {
+    self.is_open()
+  }
+
+  fn has_focus(&self) -> bool {
+    self.is_open()
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if let Some(dialog) = &mut self.dialog {
+      dialog.draw(f, rect)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::{KeyCode, KeyModifiers};
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_asks_before_quitting() -> Result<()> {
+    let mut prompt = QuitPrompt::new();
+    let press =
+      |prompt: &mut QuitPrompt, c| prompt.handle_key_events(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    assert_eq!(press(&mut prompt, 'y')?, None);
+
+    prompt.update(EngineAction::ConfirmQuit.into())?;
+    assert!(prompt.captures_text_input());
+    assert_eq!(press(&mut prompt, 'n')?, Some(EngineAction::Notify("Not quitting".to_string()).into()));
+    assert!(!prompt.is_open());
+
+    prompt.update(EngineAction::ConfirmQuit.into())?;
+    assert_eq!(press(&mut prompt, 'y')?, Some(EngineAction::ForceQuit.into()));
+    assert!(!prompt.is_open());
+    Ok(())
+  }
+}
@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::{
+  actions::{Action, EngineAction},
+  config::{key_sequence_display, Config, Theme},
+  tui::Frame,
+};
+
+/// Shows the most recent [`EngineAction::Error`] or [`EngineAction::Notify`] message in a bar along the bottom of the
+/// screen until it times out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusBar {
+  timeout: Duration,
+  message: Option<(String, Color, Instant)>,
+  error_color: Color,
+  /// Whether the theme is [monochrome](Theme::monochrome), in which case the bar is drawn reversed instead of in color.
+  monochrome: bool,
+  /// Shown as a permanent marker at the end of the bar.
+  read_only: bool,
+  /// The count typed ahead of a navigation key or operator, shown next to the read-only marker until it is used.
+  pending_count: usize,
+  /// The keys of a combination in progress, such as the `d` of `3dd`, shown after the count.
+  pending_keys: Vec<KeyEvent>,
+}
+
+impl Default for StatusBar {
+  fn default() -> Self {
+    Self::new(Duration::from_secs(crate::config::StatusBarConfig::default().error_timeout_secs))
+  }
+}
+
+impl StatusBar {
+  pub fn new(timeout: Duration) -> Self {
+    Self {
+      timeout,
+      message: None,
+      error_color: Theme::default().error,
+      monochrome: false,
+      read_only: false,
+      pending_count: 0,
+      pending_keys: Vec::new(),
+    }
+  }
+
+  /// The pending count and operator keys as they are shown, e.g. `3d`, or `None` when nothing is pending.
+  pub fn pending(&self) -> Option<String> {
+    let count = Some(self.pending_count).filter(|count| *count > 0).map(|count| count.to_string());
+    let keys = key_sequence_display(&self.pending_keys);
+    (count.is_some() || !keys.is_empty()).then(|| format!("{}{keys}", count.unwrap_or_default()))
+  }
+
+  pub fn show_error(&mut self, message: String, now: Instant) {
+    self.message = Some((message, self.error_color, now));
+  }
+
+  pub fn show_info(&mut self, message: String, now: Instant) {
+    self.message = Some((message, Color::Green, now));
+  }
+
+  /// Clears the message once it has been on screen for longer than the timeout.
+  pub fn expire(&mut self, now: Instant) {
+    if self.message.as_ref().is_some_and(|(_, _, shown_at)| now.saturating_duration_since(*shown_at) >= self.timeout) {
+      self.message = None;
+    }
+  }
+
+  pub fn message(&self) -> Option<&str> {
+    self.message.as_ref().map(|(message, ..)| message.as_str())
+  }
+}
+
+impl Component for StatusBar {
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.timeout = Duration::from_secs(config.status_bar.error_timeout_secs);
+    self.error_color = config.theme.error;
+    self.monochrome = config.theme.monochrome;
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::Engine(EngineAction::Error(message)) => self.show_error(message, Instant::now()),
+      Action::Engine(EngineAction::Notify(message)) => self.show_info(message, Instant::now()),
+      Action::Engine(EngineAction::StorageReadOnly(read_only)) => self.read_only = read_only,
+      Action::Engine(EngineAction::PendingCount(count)) => self.pending_count = count,
+      Action::Engine(EngineAction::PendingKeys(keys)) => self.pending_keys = keys,
+      Action::Engine(EngineAction::Tick) => self.expire(Instant::now()),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let bar = Rect { y: rect.bottom().saturating_sub(1), height: rect.height.min(1), ..rect };
+    if let Some((message, color, _)) = &self.message {
+      // Keep the bar on a single line, however the message was formatted.
+      let message = message.lines().collect::<Vec<_>>().join(" ");
+      f.render_widget(Clear, bar);
+      let style = match self.monochrome {
+        true => Style::default().add_modifier(Modifier::REVERSED),
+        false => Style::default().fg(Color::White).bg(*color),
+      };
+      f.render_widget(Paragraph::new(format!(" {message}")).style(style), bar);
+    }
+    let mut right = bar.right();
+    if self.read_only {
+      const MARKER: &str = " READ-ONLY ";
+      let width = (MARKER.len() as u16).min(right - bar.x);
+      let marker = Rect { x: right - width, width, ..bar };
+      let style = match self.monochrome {
+        true => Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+        false => Style::default().fg(Color::Black).bg(Color::Yellow),
+      };
+      f.render_widget(Paragraph::new(MARKER).style(style), marker);
+      right -= width;
+    }
+    if let Some(pending) = self.pending() {
+      let pending = format!(" {pending} ");
+      let width = (pending.len() as u16).min(right - bar.x);
+      let marker = Rect { x: right - width, width, ..bar };
+      f.render_widget(Paragraph::new(pending).style(Style::default().add_modifier(Modifier::REVERSED)), marker);
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_error_clears_after_timeout() {
+    let start = Instant::now();
+    let mut status_bar = StatusBar::new(Duration::from_secs(5));
+    status_bar.show_error("Failed to draw".to_string(), start);
+
+    status_bar.expire(start + Duration::from_secs(4));
+    assert_eq!(status_bar.message(), Some("Failed to draw"));
+
+    status_bar.expire(start + Duration::from_secs(5));
+    assert_eq!(status_bar.message(), None);
+  }
+
+  #[test]
+  fn test_newer_error_restarts_timeout() {
+    let start = Instant::now();
+    let mut status_bar = StatusBar::new(Duration::from_secs(5));
+    status_bar.show_error("first".to_string(), start);
+    status_bar.show_error("second".to_string(), start + Duration::from_secs(3));
+
+    status_bar.expire(start + Duration::from_secs(6));
+    assert_eq!(status_bar.message(), Some("second"));
+  }
+
+  #[test]
+  fn test_shows_the_pending_count_and_operator() -> Result<()> {
+    let mut status_bar = StatusBar::default();
+    assert_eq!(status_bar.pending(), None);
+
+    status_bar.update(EngineAction::PendingCount(3).into())?;
+    assert_eq!(status_bar.pending().as_deref(), Some("3"));
+    status_bar.update(EngineAction::PendingKeys(vec![KeyEvent::from(crossterm::event::KeyCode::Char('d'))]).into())?;
+    assert_eq!(status_bar.pending().as_deref(), Some("3d"));
+
+    status_bar.update(EngineAction::PendingCount(0).into())?;
+    status_bar.update(EngineAction::PendingKeys(Vec::new()).into())?;
+    assert_eq!(status_bar.pending(), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_notification_replaces_error() {
+    let start = Instant::now();
+    let mut status_bar = StatusBar::new(Duration::from_secs(5));
+    status_bar.show_error("Failed to draw".to_string(), start);
+    status_bar.show_info("Saved".to_string(), start);
+
+    assert_eq!(status_bar.message, Some(("Saved".to_string(), Color::Green, start)));
+  }
+}
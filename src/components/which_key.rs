@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::{
+  actions::{Action, EngineAction},
+  app::Mode,
+  config::{key_sequence_to_string, Config},
+  tui::Frame,
+};
+
+/// Lists the ways a partly typed multi-key binding can be completed, once it has been pending for a short while.
+#[derive(Debug, Default)]
+pub struct WhichKey {
+  mode: Mode,
+  config: Config,
+  /// The keys typed so far, and when the first of them was pressed.
+  pending: Vec<KeyEvent>,
+  since: Option<Instant>,
+  delay: Duration,
+}
+
+impl WhichKey {
+  pub fn new(mode: Mode) -> Self {
+    Self { mode, ..Self::default() }
+  }
+
+  pub fn set_pending(&mut self, keys: Vec<KeyEvent>, now: Instant) {
+    if keys.is_empty() {
+      self.since = None;
+    } else if self.pending.is_empty() {
+      self.since = Some(now);
+    }
+    self.pending = keys;
+  }
+
+  /// The rest of each binding that starts with the pending keys, next to the action it runs, sorted by keys.
+  pub fn completions(&self) -> Vec<(String, String)> {
+    let mut completions: Vec<(String, String)> = self
+      .config
+      .keybindings
+      .merged(self.mode)
+      .iter()
+      .filter(|(keys, _)| keys.len() > self.pending.len() && keys.starts_with(&self.pending))
+      .map(|(keys, action)| (key_sequence_to_string(&keys[self.pending.len()..]), action.to_string()))
+      .collect();
+    completions.sort();
+    completions
+  }
+
+  /// Whether the popup should be on screen at `now`.
+  pub fn is_visible(&self, now: Instant) -> bool {
+    self.since.is_some_and(|since| now.saturating_duration_since(since) >= self.delay)
+  }
+}
+
+impl Component for WhichKey {
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.delay = Duration::from_millis(config.chords.popup_delay_ms);
+    self.config = config;
+    Ok(())
+  }
+
+  fn on_mode_change(&mut self, from: Mode, to: Mode) -> Result<()> {
+    self.mode = to;
+    self.set_pending(Vec::new(), Instant::now());
+    Ok(())
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if let Action::Engine(EngineAction::PendingKeys(keys)) = action {
+      self.set_pending(keys, Instant::now());
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if !self.is_visible(Instant::now()) {
+      return Ok(());
+    }
+    let completions = self.completions();
+    if completions.is_empty() {
+      return Ok(());
+    }
+
+    let keys_width = completions.iter().map(|(keys, _)| keys.len()).max().unwrap_or(0) as u16;
+    let action_width = completions.iter().map(|(_, action)| action.len()).max().unwrap_or(0) as u16;
+    let width = (keys_width + action_width + 5).min(rect.width);
+    let height = (completions.len() as u16 + 2).min(rect.height);
+    // Sit just above the status bar in the bottom-right corner.
+    let area = Rect::new(
+      rect.right().saturating_sub(width),
+      rect.bottom().saturating_sub(height + 1).max(rect.y),
+      width,
+      height,
+    );
+
+    let rows = completions.into_iter().map(|(keys, action)| Row::new(vec![keys, action]));
+    let title = key_sequence_to_string(&self.pending);
+    let widths = [Constraint::Length(keys_width), Constraint::Min(0)];
+    let table = Table::new(rows)
+      .widths(&widths)
+      .block(Block::default().title(title).borders(Borders::ALL).border_style(Style::default().fg(Color::Cyan)));
+    f.render_widget(Clear, area);
+    f.render_widget(table, area);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::config::parse_key_sequence;
+
+  #[test]
+  fn test_lists_completions_after_delay() -> Result<()> {
+    let mut config = Config::default();
+    config.chords.popup_delay_ms = 300;
+    config.keybindings.insert(
+      Mode::Home,
+      [("<g><g>", EngineAction::Refresh), ("<g><t><x>", EngineAction::Quit), ("<t>", EngineAction::FocusNext)]
+        .into_iter()
+        .map(|(keys, action)| (parse_key_sequence(keys).unwrap(), action.into()))
+        .collect(),
+    );
+    let mut which_key = WhichKey::new(Mode::Home);
+    which_key.register_config_handler(config)?;
+    let start = Instant::now();
+
+    which_key.set_pending(parse_key_sequence("<g>").unwrap(), start);
+    assert!(!which_key.is_visible(start + Duration::from_millis(100)));
+    assert!(which_key.is_visible(start + Duration::from_millis(300)));
+    assert_eq!(
+      which_key.completions(),
+      vec![("<g>".to_string(), "Engine.Refresh".to_string()), ("<t><x>".to_string(), "Engine.Quit".to_string())]
+    );
+
+    // More keys narrow the list without restarting the delay.
+    which_key.set_pending(parse_key_sequence("<g><t>").unwrap(), start + Duration::from_millis(350));
+    assert!(which_key.is_visible(start + Duration::from_millis(350)));
+    assert_eq!(which_key.completions(), vec![("<x>".to_string(), "Engine.Quit".to_string())]);
+
+    which_key.set_pending(Vec::new(), start + Duration::from_millis(400));
+    assert!(!which_key.is_visible(start + Duration::from_millis(400)));
+    Ok(())
+  }
+}
@@ -1,20 +1,61 @@
-use std::{collections::HashMap, fmt, path::PathBuf};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::SystemTime,
+};
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use config::Value;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use derive_deref::{Deref, DerefMut};
 use ratatui::style::{Color, Modifier, Style};
 use serde::{
   de::{self, Deserializer, MapAccess, Visitor},
-  Deserialize, Serialize,
+  ser::SerializeMap,
+  Deserialize, Serialize, Serializer,
 };
 use serde_json::Value as JsonValue;
 
-use crate::{actions::Action, app::Mode};
+use crate::{
+  actions::{Action, HomeAction},
+  app::Mode,
+  utils::ACTION_TRACE_ENV,
+};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
+/// Ticks per second when neither the config nor the command line sets a rate.
+pub const DEFAULT_TICK_RATE: f64 = 1.0;
+/// Frames per second when neither the config nor the command line sets a rate.
+pub const DEFAULT_FRAME_RATE: f64 = 60.0;
+
+/// Bindings the vim preset lays over the built-in ones.
+const VIM_PRESET: &str = r#"{
+  "Home": {
+    "<h>": "Home.NavigateList(Left)",
+    "<j>": "Home.NavigateList(Down)",
+    "<k>": "Home.NavigateList(Up)",
+    "<l>": "Home.NavigateList(Right)",
+    "<i>": "Home.EnterInsert",
+  },
+  "MainMenu": {
+    "<h>": "MainMenu.NavigateTabs(Left)",
+    "<j>": "Home.NavigateList(Down)",
+    "<k>": "Home.NavigateList(Up)",
+    "<l>": "MainMenu.NavigateTabs(Right)",
+  },
+}"#;
+
+const CONFIG_FILES: [(&str, config::FileFormat); 5] = [
+  ("config.json5", config::FileFormat::Json5),
+  ("config.json", config::FileFormat::Json),
+  ("config.yaml", config::FileFormat::Yaml),
+  ("config.toml", config::FileFormat::Toml),
+  ("config.ini", config::FileFormat::Ini),
+];
+
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct AppConfig {
   #[serde(default)]
@@ -23,6 +64,376 @@ pub struct AppConfig {
   pub _config_dir: PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct IdleConfig {
+  /// Seconds without any input before the app goes idle.
+  pub timeout_secs: u64,
+  /// Frame rate to fall back to while idle.
+  pub frame_rate: f64,
+  /// Hide everything behind a "press any key" screen while idle.
+  pub blank_screen: bool,
+}
+
+impl Default for IdleConfig {
+  fn default() -> Self {
+    Self { timeout_secs: 600, frame_rate: 1.0, blank_screen: false }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LineNumberConfig {
+  /// Start with numbers relative to the selected line instead of absolute ones.
+  pub relative: bool,
+  /// In relative mode, show the selected line's absolute number rather than 0.
+  pub current_line_absolute: bool,
+}
+
+impl Default for LineNumberConfig {
+  fn default() -> Self {
+    Self { relative: false, current_line_absolute: true }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ModeHistoryConfig {
+  /// How many previous modes are remembered for going back.
+  pub depth: usize,
+}
+
+impl Default for ModeHistoryConfig {
+  fn default() -> Self {
+    Self { depth: 16 }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FpsConfig {
+  /// Number of render ticks the frame rate is averaged over.
+  pub window: usize,
+}
+
+impl Default for FpsConfig {
+  fn default() -> Self {
+    Self { window: crate::components::fps::DEFAULT_WINDOW }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct StatusBarConfig {
+  /// Seconds an error stays in the status bar.
+  pub error_timeout_secs: u64,
+}
+
+/// A tab on the todo menus, with the action it runs when activated.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TabConfig {
+  pub label: String,
+  pub action: Action,
+}
+
+/// The tabs shown by the MainMenu and Home, in order.
+#[derive(Clone, Debug, PartialEq, Eq, Deref, Deserialize)]
+pub struct TabsConfig(pub Vec<TabConfig>);
+
+impl Default for TabsConfig {
+  fn default() -> Self {
+    let tab = |label: &str, action: HomeAction| TabConfig { label: label.to_string(), action: action.into() };
+    Self(vec![
+      tab("List", HomeAction::ScheduleIncrement(1)),
+      tab("Add", HomeAction::ScheduleDecrement(1)),
+      tab("Edit", HomeAction::ScheduleIncrement(1)),
+      tab("Delete", HomeAction::DeleteTodo),
+    ])
+  }
+}
+
+impl TabsConfig {
+  /// Checks there is at least one tab and that every label is non-empty and unique.
+  pub fn validate(&self) -> Result<(), String> {
+    if self.is_empty() {
+      return Err("At least one tab must be configured".to_string());
+    }
+    for (i, tab) in self.iter().enumerate() {
+      if tab.label.trim().is_empty() {
+        return Err(format!("Tab {} has an empty label", i + 1));
+      }
+      if self[..i].iter().any(|other| other.label == tab.label) {
+        return Err(format!("Tab label `{}` is used more than once", tab.label));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// A bundle of key bindings applied on top of the built-in ones, beneath anything the user binds themselves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Preset {
+  #[default]
+  Default,
+  Vim,
+}
+
+impl Preset {
+  /// The bindings the preset adds or replaces.
+  pub fn keybindings(self) -> KeyBindings {
+    match self {
+      Self::Default => KeyBindings::default(),
+      Self::Vim => json5::from_str(VIM_PRESET).unwrap(),
+    }
+  }
+}
+
+impl FromStr for Preset {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Default" => Ok(Self::Default),
+      "Vim" => Ok(Self::Vim),
+      _ => Err(format!("Unknown preset: {s}")),
+    }
+  }
+}
+
+/// Which layer of the config a key binding comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingSource {
+  BuiltIn,
+  Preset(Preset),
+  User,
+}
+
+impl fmt::Display for BindingSource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::BuiltIn => write!(f, "built-in default"),
+      Self::Preset(preset) => write!(f, "{preset:?} preset"),
+      Self::User => write!(f, "user config"),
+    }
+  }
+}
+
+/// The keys `<leader>` stands for in key bindings, so a whole family of bindings can be moved by changing one setting.
+/// Space unless the config sets `leader`.
+#[derive(Clone, Debug, PartialEq, Eq, Deref)]
+pub struct Leader(pub Vec<KeyEvent>);
+
+impl Default for Leader {
+  fn default() -> Self {
+    Self(vec![KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)])
+  }
+}
+
+impl<'de> Deserialize<'de> for Leader {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let raw = String::deserialize(deserializer)?;
+    let keys = parse_key_sequence(&raw).map_err(de::Error::custom)?;
+    if keys.contains(&LEADER) {
+      return Err(de::Error::custom(format!("The leader can't be written with `<leader>`: `{raw}`")));
+    }
+    Ok(Self(keys))
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ChordConfig {
+  /// Milliseconds allowed between the keys of a multi-key binding before the partial sequence is dropped.
+  pub timeout_ms: u64,
+  /// Milliseconds a multi-key binding has to be pending before its possible completions are shown.
+  pub popup_delay_ms: u64,
+  /// Only warn, rather than refuse to load, when a binding is the start of a longer one and so always wins over it.
+  pub allow_prefix_shadowing: bool,
+}
+
+impl Default for ChordConfig {
+  fn default() -> Self {
+    Self { timeout_ms: 750, popup_delay_ms: 300, allow_prefix_shadowing: false }
+  }
+}
+
+/// The colors components draw with, by the role they play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+  /// Body text.
+  pub primary: Color,
+  /// Key hints.
+  pub accent: Color,
+  /// Whatever is being worked on: the input being typed into, a busy pane's border, emphasised words.
+  pub highlight: Color,
+  /// Error messages.
+  pub error: Color,
+  /// Borders of popups that don't have focus.
+  pub border: Color,
+  /// Text that stays in the background, like usage hints.
+  pub dim: Color,
+  /// Set for terminals without color support, where the `*_style` methods set bold or underline to stand in for
+  /// colors.
+  pub monochrome: bool,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Self {
+      primary: Color::Cyan,
+      accent: Color::Red,
+      highlight: Color::Yellow,
+      error: Color::Red,
+      border: Color::LightBlue,
+      dim: Color::DarkGray,
+      monochrome: false,
+    }
+  }
+}
+
+impl Theme {
+  /// The names the colors go by in the config.
+  const SLOTS: [&'static str; 6] = ["primary", "accent", "highlight", "error", "border", "dim"];
+
+  /// The built-in palettes, in the order [`EngineAction::CycleTheme`](crate::actions::EngineAction::CycleTheme) goes
+  /// through them. `default` is the one from the config's `theme` section.
+  pub const NAMES: [&'static str; 3] = ["default", "high-contrast", "monochrome"];
+
+  /// The built-in palette called `name`, with `default` being the colors the config starts from.
+  pub fn builtin(name: &str) -> Option<Self> {
+    match name {
+      "default" => Some(Self::default()),
+      "high-contrast" => Some(Self {
+        primary: Color::White,
+        accent: Color::LightYellow,
+        highlight: Color::LightCyan,
+        error: Color::LightRed,
+        border: Color::White,
+        dim: Color::Gray,
+        monochrome: false,
+      }),
+      "monochrome" => Some(Self {
+        primary: Color::Reset,
+        accent: Color::Reset,
+        highlight: Color::Reset,
+        error: Color::Reset,
+        border: Color::Reset,
+        dim: Color::Reset,
+        monochrome: true,
+      }),
+      _ => None,
+    }
+  }
+
+  /// `color`, or `modifier` in its place on a monochrome theme.
+  fn style(&self, color: Color, modifier: Modifier) -> Style {
+    match self.monochrome {
+      true => Style::default().add_modifier(modifier),
+      false => Style::default().fg(color),
+    }
+  }
+
+  pub fn primary_style(&self) -> Style {
+    self.style(self.primary, Modifier::empty())
+  }
+
+  pub fn accent_style(&self) -> Style {
+    self.style(self.accent, Modifier::BOLD)
+  }
+
+  pub fn highlight_style(&self) -> Style {
+    self.style(self.highlight, Modifier::UNDERLINED)
+  }
+
+  pub fn error_style(&self) -> Style {
+    self.style(self.error, Modifier::BOLD | Modifier::UNDERLINED)
+  }
+
+  pub fn border_style(&self) -> Style {
+    self.style(self.border, Modifier::empty())
+  }
+
+  pub fn dim_style(&self) -> Style {
+    self.style(self.dim, Modifier::DIM)
+  }
+
+  fn slot_mut(&mut self, slot: &str) -> Option<&mut Color> {
+    match slot {
+      "primary" => Some(&mut self.primary),
+      "accent" => Some(&mut self.accent),
+      "highlight" => Some(&mut self.highlight),
+      "error" => Some(&mut self.error),
+      "border" => Some(&mut self.border),
+      "dim" => Some(&mut self.dim),
+      _ => None,
+    }
+  }
+}
+
+/// Reads the theme a color at a time, so a bad one is reported along with the key it was given for. Colors left out
+/// keep their defaults.
+impl<'de> Deserialize<'de> for Theme {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let mut slots: Vec<(String, JsonValue)> =
+      HashMap::<String, JsonValue>::deserialize(deserializer)?.into_iter().collect();
+    slots.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut theme = Theme::default();
+    for (slot, value) in slots {
+      if slot == "monochrome" {
+        theme.monochrome = value
+          .as_bool()
+          .ok_or_else(|| de::Error::custom(format!("Invalid theme.monochrome `{value}`, expected a bool")))?;
+        continue;
+      }
+      let color = parse_theme_color(&value).map_err(|e| de::Error::custom(format!("{e} for theme.{slot}")))?;
+      match theme.slot_mut(&slot) {
+        Some(bound) => *bound = color,
+        None => log::warn!("Ignoring unknown theme color `{slot}`, expected one of {}", Theme::SLOTS.join(", ")),
+      }
+    }
+    Ok(theme)
+  }
+}
+
+/// Writes the theme the way the config spells it, so the output can be pasted back into the `theme` section.
+impl Serialize for Theme {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let colors = [self.primary, self.accent, self.highlight, self.error, self.border, self.dim];
+    let mut map = serializer.serialize_map(Some(Self::SLOTS.len() + 1))?;
+    for (slot, color) in Self::SLOTS.into_iter().zip(colors) {
+      map.serialize_entry(slot, &color.to_string())?;
+    }
+    map.serialize_entry("monochrome", &self.monochrome)?;
+    map.end()
+  }
+}
+
+/// Reads a color name such as `light-red`, a `#rrggbb` hex color, or an index into the 256-color palette, which may
+/// be written as a number or a string.
+fn parse_theme_color(value: &JsonValue) -> Result<Color, String> {
+  let invalid = |raw: &dyn fmt::Display| format!("Invalid color `{raw}`, expected a color name, #rrggbb or 0-255");
+  match value {
+    JsonValue::String(raw) => raw.parse().map_err(|_| invalid(raw)),
+    JsonValue::Number(n) => n.as_u64().and_then(|n| u8::try_from(n).ok()).map(Color::Indexed).ok_or_else(|| invalid(n)),
+    other => Err(invalid(other)),
+  }
+}
+
+impl Default for StatusBarConfig {
+  fn default() -> Self {
+    Self { error_timeout_secs: 5 }
+  }
+}
+
 #[derive(Clone, Debug, Default, Deserialize)]
 pub struct Config {
   #[serde(default, flatten)]
@@ -30,27 +441,82 @@ pub struct Config {
   #[serde(default)]
   pub keybindings: KeyBindings,
   #[serde(default)]
+  pub mousebindings: MouseBindings,
+  #[serde(default)]
   pub styles: Styles,
+  #[serde(default)]
+  pub theme: Theme,
+  #[serde(default)]
+  pub idle: IdleConfig,
+  /// Upper bound on frames drawn per second, regardless of how often renders are requested.
+  #[serde(default)]
+  pub max_fps: Option<f64>,
+  /// Ticks per second, [`DEFAULT_TICK_RATE`] if unset.
+  #[serde(default)]
+  pub tick_rate: Option<f64>,
+  /// Render requests per second, [`DEFAULT_FRAME_RATE`] if unset.
+  #[serde(default)]
+  pub frame_rate: Option<f64>,
+  #[serde(default)]
+  pub chords: ChordConfig,
+  #[serde(default)]
+  pub leader: Leader,
+  #[serde(default)]
+  pub tabs: TabsConfig,
+  #[serde(default)]
+  pub line_numbers: LineNumberConfig,
+  #[serde(default)]
+  pub mode_history: ModeHistoryConfig,
+  #[serde(default)]
+  pub fps: FpsConfig,
+  #[serde(default)]
+  pub status_bar: StatusBarConfig,
+  /// The most characters that can be typed into Home's input, unlimited if unset.
+  #[serde(default)]
+  pub max_input_len: Option<usize>,
+  /// A file to write every dispatched action to, relative to the data directory. See [`Config::action_trace_path`].
+  #[serde(default)]
+  pub action_trace: Option<PathBuf>,
+  #[serde(default)]
+  pub preset: Preset,
+  /// The bindings `keybindings` is layered from, kept so a different preset can be applied later.
+  #[serde(skip)]
+  builtin_keybindings: KeyBindings,
+  #[serde(skip)]
+  user_keybindings: KeyBindings,
+  /// Bindings changed with [`Config::set_binding`] since the config was loaded or last saved, in the order they were
+  /// made.
+  #[serde(skip)]
+  unsaved_bindings: Vec<(Option<Mode>, Vec<KeyEvent>, Option<Action>)>,
+  /// The theme the config files describe, which the `default` palette stands for.
+  #[serde(skip)]
+  configured_theme: Theme,
+  /// Which of [`Theme::NAMES`] `theme` currently is.
+  #[serde(skip)]
+  theme_index: usize,
 }
 
+/// The config sections that are keyed by mode.
+const MODE_SECTIONS: [&str; 3] = ["keybindings", "mousebindings", "styles"];
+
 impl Config {
   pub fn new() -> Result<Self, config::ConfigError> {
+    Self::load(&crate::utils::get_config_dir())
+  }
+
+  /// Reads the config files in `config_dir` on top of the built-in defaults.
+  ///
+  /// Mode sections that don't name a known mode are all reported together in one error. Modes the files leave out
+  /// fall back to the built-in bindings, with a warning.
+  pub fn load(config_dir: &Path) -> Result<Self, config::ConfigError> {
     let default_config: Config = json5::from_str(CONFIG).unwrap();
     let data_dir = crate::utils::get_data_dir();
-    let config_dir = crate::utils::get_config_dir();
     let mut builder = config::Config::builder()
       .set_default("_data_dir", data_dir.to_str().unwrap())?
       .set_default("_config_dir", config_dir.to_str().unwrap())?;
 
-    let config_files = [
-      ("config.json5", config::FileFormat::Json5),
-      ("config.json", config::FileFormat::Json),
-      ("config.yaml", config::FileFormat::Yaml),
-      ("config.toml", config::FileFormat::Toml),
-      ("config.ini", config::FileFormat::Ini),
-    ];
     let mut found_config = false;
-    for (file, format) in &config_files {
+    for (file, format) in &CONFIG_FILES {
       builder = builder.add_source(config::File::from(config_dir.join(file)).format(*format).required(false));
       if config_dir.join(file).exists() {
         found_config = true
@@ -60,80 +526,649 @@ impl Config {
       log::error!("No configuration file found. Application may not behave as expected");
     }
 
-    let mut cfg: Self = builder.build()?.try_deserialize()?;
+    let built = builder.build()?;
+    let unknown = unknown_mode_sections(&built);
+    if !unknown.is_empty() {
+      return Err(config::ConfigError::Message(format!(
+        "Unknown modes in config sections {} (expected one of {:?})",
+        unknown.join(", "),
+        Mode::ALL
+      )));
+    }
+    if found_config {
+      let user_modes = mode_sections(&built, "keybindings");
+      let missing: Vec<String> =
+        Mode::ALL.iter().map(|mode| format!("{mode:?}")).filter(|mode| !user_modes.contains(mode)).collect();
+      if !missing.is_empty() {
+        log::warn!("No keybindings for {} in the config, using the built-in ones", missing.join(", "));
+      }
+    }
+
+    let mut cfg: Self = built.try_deserialize()?;
+    cfg.configured_theme = cfg.theme;
+    cfg.tabs.validate().map_err(config::ConfigError::Message)?;
+    cfg.validate_rates().map_err(config::ConfigError::Message)?;
 
-    for (mode, default_bindings) in default_config.keybindings.iter() {
-      let user_bindings = cfg.keybindings.entry(*mode).or_default();
-      for (key, cmd) in default_bindings.iter() {
-        user_bindings.entry(key.clone()).or_insert_with(|| cmd.clone());
+    cfg.builtin_keybindings = default_config.keybindings;
+    cfg.builtin_keybindings.expand_leader(&cfg.leader);
+    cfg.user_keybindings = std::mem::take(&mut cfg.keybindings);
+    cfg.user_keybindings.expand_leader(&cfg.leader);
+    cfg.apply_preset(cfg.preset);
+    let shadowing = cfg.keybindings.prefix_conflicts();
+    if !shadowing.is_empty() {
+      let message = format!("Key bindings shadow longer ones: {}", shadowing.join("; "));
+      if !cfg.chords.allow_prefix_shadowing {
+        return Err(config::ConfigError::Message(format!(
+          "{message} (set chords.allow_prefix_shadowing to allow this)"
+        )));
       }
+      log::warn!("{message}");
     }
-    for (mode, default_styles) in default_config.styles.iter() {
-      let user_styles = cfg.styles.entry(*mode).or_default();
-      for (style_key, style) in default_styles.iter() {
-        user_styles.entry(style_key.clone()).or_insert_with(|| *style);
+    for (mode, default_bindings) in default_config.mousebindings.iter() {
+      let user_bindings = cfg.mousebindings.entry(*mode).or_default();
+      for (button, cmd) in default_bindings.iter() {
+        user_bindings.entry(*button).or_insert_with(|| cmd.clone());
       }
     }
+    cfg.styles.fill_in(&default_config.styles);
 
     Ok(cfg)
   }
+
+  pub fn tick_rate(&self) -> f64 {
+    self.tick_rate.unwrap_or(DEFAULT_TICK_RATE)
+  }
+
+  pub fn frame_rate(&self) -> f64 {
+    self.frame_rate.unwrap_or(DEFAULT_FRAME_RATE)
+  }
+
+  /// Checks that any configured tick and frame rates are positive.
+  pub fn validate_rates(&self) -> Result<(), String> {
+    for (name, rate) in [("tick_rate", self.tick_rate), ("frame_rate", self.frame_rate)] {
+      if let Some(rate) = rate {
+        positive_rate(rate).map_err(|e| format!("Invalid {name}: {e}"))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Rebuilds the key bindings from the built-in ones, then `preset`'s, then the user's, each overriding the last.
+  ///
+  /// Returns how many bindings changed.
+  pub fn apply_preset(&mut self, preset: Preset) -> usize {
+    let mut keybindings = self.builtin_keybindings.clone();
+    for layer in [self.preset_keybindings(preset), self.user_keybindings.clone()] {
+      keybindings.extend(layer);
+    }
+
+    let changed = Mode::ALL
+      .iter()
+      .map(|mode| {
+        let (old, new) = (self.keybindings.merged(*mode), keybindings.merged(*mode));
+        old.iter().filter(|(keys, action)| new.get(*keys) != Some(action)).count()
+          + new.keys().filter(|keys| !old.contains_key(*keys)).count()
+      })
+      .sum();
+    self.preset = preset;
+    self.keybindings = keybindings;
+    changed
+  }
+
+  /// Where to trace every dispatched action to, if anywhere: the path in the [`ACTION_TRACE_ENV`] environment
+  /// variable, or else the one in the config. Relative paths are taken from the data directory.
+  pub fn action_trace_path(&self) -> Option<PathBuf> {
+    let path = std::env::var_os(&*ACTION_TRACE_ENV).map(PathBuf::from).or_else(|| self.action_trace.clone())?;
+    Some(self.config._data_dir.join(path))
+  }
+
+  /// The name of the palette in use, one of [`Theme::NAMES`].
+  pub fn theme_name(&self) -> &'static str {
+    Theme::NAMES[self.theme_index]
+  }
+
+  /// Switches to the built-in palette called `name` until the config is reloaded, returning its name.
+  pub fn set_theme(&mut self, name: &str) -> Result<&'static str, String> {
+    let index = Theme::NAMES
+      .iter()
+      .position(|known| *known == name)
+      .ok_or_else(|| format!("Unknown theme `{name}`, expected one of {}", Theme::NAMES.join(", ")))?;
+    self.theme_index = index;
+    self.theme = match index {
+      0 => self.configured_theme,
+      _ => Theme::builtin(name).unwrap_or_default(),
+    };
+    Ok(self.theme_name())
+  }
+
+  /// Switches to the palette after the current one, coming round to the first after the last.
+  pub fn cycle_theme(&mut self) -> &'static str {
+    let next = Theme::NAMES[(self.theme_index + 1) % Theme::NAMES.len()];
+    self.set_theme(next).unwrap_or(next)
+  }
+
+  /// Binds `keys` to `action` in `section`, `None` being the global one, or unbinds them if there is no action, as if
+  /// the user's config said so. The change lasts until the config is reloaded, unless it is saved with
+  /// [`Config::save_keybindings`] first.
+  pub fn set_binding(&mut self, section: Option<Mode>, keys: Vec<KeyEvent>, action: Option<Action>) {
+    self.user_keybindings.set(section, keys.clone(), action.clone());
+    self.unsaved_bindings.push((section, keys, action));
+    self.apply_preset(self.preset);
+  }
+
+  /// Whether any bindings were changed with [`Config::set_binding`] and not saved since.
+  pub fn has_unsaved_bindings(&self) -> bool {
+    !self.unsaved_bindings.is_empty()
+  }
+
+  /// Writes the bindings changed with [`Config::set_binding`] into the user's config file, returning its path.
+  ///
+  /// They go into whichever config file takes precedence, or a new `config.json5` if there are none. Only JSON and
+  /// JSON5 files can be written back, and the file is rewritten as plain JSON, so any comments in it are lost.
+  pub fn save_keybindings(&mut self) -> Result<PathBuf> {
+    let config_dir = &self.config._config_dir;
+    // Later files override earlier ones, so the last one that exists is where the bindings have to go.
+    let path = match CONFIG_FILES.iter().rev().find(|(file, _)| config_dir.join(file).exists()) {
+      None => config_dir.join(CONFIG_FILES[0].0),
+      Some((file, config::FileFormat::Json5 | config::FileFormat::Json)) => config_dir.join(file),
+      Some((file, _)) => return Err(eyre!("Can only save key bindings to a JSON or JSON5 config, not {file}")),
+    };
+    let mut root = match path.exists() {
+      true => json5::from_str(&std::fs::read_to_string(&path)?)?,
+      false => JsonValue::Object(Default::default()),
+    };
+    let not_an_object = |what: &str| eyre!("The {what} in {} isn't an object", path.display());
+    let keybindings = root
+      .as_object_mut()
+      .ok_or_else(|| not_an_object("config"))?
+      .entry("keybindings")
+      .or_insert_with(|| JsonValue::Object(Default::default()));
+    for (section, keys, action) in &self.unsaved_bindings {
+      let name = section_name(*section);
+      let entries = keybindings
+        .as_object_mut()
+        .ok_or_else(|| not_an_object("keybindings section"))?
+        .entry(name.clone())
+        .or_insert_with(|| JsonValue::Object(Default::default()))
+        .as_object_mut()
+        .ok_or_else(|| not_an_object(&format!("keybindings.{name} section")))?;
+      // Any other spelling of the same keys would conflict with the new entry.
+      entries.retain(|raw, _| {
+        parse_key_sequence(raw).map_or(true, |written| expand_leader_keys(written, &self.leader) != *keys)
+      });
+      let action = action.as_ref().map_or(KeyBindings::UNBIND.to_string(), Action::to_string);
+      entries.insert(key_sequence_to_string(keys), JsonValue::String(action));
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&root)? + "\n")?;
+    self.unsaved_bindings.clear();
+    Ok(path)
+  }
+
+  /// The bindings `preset` adds, with `<leader>` standing for the configured leader like in the other layers.
+  fn preset_keybindings(&self, preset: Preset) -> KeyBindings {
+    let mut keybindings = preset.keybindings();
+    keybindings.expand_leader(&self.leader);
+    keybindings
+  }
+
+  /// Which layer the binding for `keys` in `mode` comes from, if it is bound at all.
+  pub fn binding_source(&self, mode: Mode, keys: &[KeyEvent]) -> Option<BindingSource> {
+    let binds = |layer: &KeyBindings| layer.binds(mode, keys);
+    if binds(&self.user_keybindings) {
+      Some(BindingSource::User)
+    } else if binds(&self.preset_keybindings(self.preset)) {
+      Some(BindingSource::Preset(self.preset))
+    } else if binds(&self.builtin_keybindings) {
+      Some(BindingSource::BuiltIn)
+    } else {
+      None
+    }
+  }
+
+  /// The key bindings in effect in `mode`, including the global ones. Warns if the config has no section for it.
+  pub fn keymap(&self, mode: Mode) -> Keymap {
+    if !self.keybindings.contains_key(&mode) {
+      log::warn!("No keybindings configured for {mode:?}");
+    }
+    self.keybindings.merged(mode)
+  }
+
+  /// Returns the key sequence bound to `action` in `mode`, formatted for display in UI hints.
+  ///
+  /// When several sequences trigger the action, the shortest one is picked to keep hints compact.
+  pub fn binding_for(&self, mode: Mode, action: &Action) -> Option<String> {
+    self
+      .keybindings
+      .merged(mode)
+      .iter()
+      .filter(|(_, bound)| *bound == action)
+      .map(|(keys, _)| key_sequence_display(keys))
+      .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+  }
+
+  /// Returns the action bound to a mouse button press in `mode`, if any.
+  pub fn mouse_action(&self, mode: Mode, mouse: &MouseEvent) -> Option<&Action> {
+    self.mousebindings.get(&mode)?.get(&MouseBinding::from_event(mouse)?)
+  }
+
+  /// Re-reads only the `styles` and `theme` sections of the config files, leaving keybindings untouched.
+  ///
+  /// The current styles and theme are kept if the files can't be parsed.
+  pub fn reload_styles(&mut self) -> Result<(), config::ConfigError> {
+    #[derive(Deserialize)]
+    struct StylesOnly {
+      #[serde(default)]
+      styles: Styles,
+      #[serde(default)]
+      theme: Theme,
+    }
+
+    let mut builder = config::Config::builder();
+    for (file, format) in &CONFIG_FILES {
+      builder =
+        builder.add_source(config::File::from(self.config._config_dir.join(file)).format(*format).required(false));
+    }
+    let reloaded: StylesOnly = builder.build()?.try_deserialize()?;
+
+    let mut styles = reloaded.styles;
+    styles.fill_in(&json5::from_str::<Config>(CONFIG).unwrap().styles);
+    self.styles = styles;
+    self.configured_theme = reloaded.theme;
+    // A built-in palette picked at runtime stays in use until switched back to the default.
+    if self.theme_index == 0 {
+      self.theme = reloaded.theme;
+    }
+    Ok(())
+  }
 }
 
-#[derive(Clone, Debug, Default, Deref, DerefMut)]
-pub struct KeyBindings(pub HashMap<Mode, HashMap<Vec<KeyEvent>, Action>>);
+/// Accepts `rate` if it is a positive, finite number of events per second.
+pub fn positive_rate(rate: f64) -> Result<f64, String> {
+  if rate.is_finite() && rate > 0.0 {
+    Ok(rate)
+  } else {
+    Err(format!("expected a positive number of events per second, got {rate}"))
+  }
+}
+
+/// When each of the config files in `config_dir` was last modified, `None` for those that don't exist.
+///
+/// Comparing two of these tells whether the config has changed on disk in between.
+pub fn config_file_stamps(config_dir: &Path) -> Vec<Option<SystemTime>> {
+  CONFIG_FILES
+    .iter()
+    .map(|(file, _)| std::fs::metadata(config_dir.join(file)).and_then(|metadata| metadata.modified()).ok())
+    .collect()
+}
+
+/// The names of the entries in a section keyed by mode, e.g. `keybindings`.
+fn mode_sections(config: &config::Config, section: &str) -> Vec<String> {
+  config.get_table(section).map(|table| table.into_keys().collect()).unwrap_or_default()
+}
+
+/// Every `section.mode` entry, across all the mode sections, whose mode doesn't exist.
+fn unknown_mode_sections(config: &config::Config) -> Vec<String> {
+  let mut unknown: Vec<String> = MODE_SECTIONS
+    .iter()
+    .flat_map(|section| {
+      mode_sections(config, section)
+        .into_iter()
+        .filter(|mode| mode.parse::<Mode>().is_err())
+        .filter(|mode| !(*section == "keybindings" && mode == KeyBindings::GLOBAL))
+        .map(move |mode| format!("{section}.{mode}"))
+    })
+    .collect();
+  unknown.sort();
+  unknown
+}
+
+/// The name of a section of `keybindings`: the mode's, or [`KeyBindings::GLOBAL`] for `None`.
+pub fn section_name(section: Option<Mode>) -> String {
+  section.map_or(KeyBindings::GLOBAL.to_string(), |mode| format!("{mode:?}"))
+}
+
+/// The section of `keybindings` called `name`, the inverse of [`section_name`].
+pub fn parse_section(name: &str) -> Result<Option<Mode>, String> {
+  match name {
+    KeyBindings::GLOBAL => Ok(None),
+    mode => mode.parse().map(Some),
+  }
+}
+
+/// Key sequences and the actions they trigger.
+pub type Keymap = HashMap<Vec<KeyEvent>, Action>;
+
+/// The bindings of `keymap` as `(keys, action)` pairs, in the order the help screen and the cheatsheet list them: by
+/// action, so an action's bindings and those of its namespace sit together, then by keys.
+pub fn sorted_bindings(keymap: &Keymap) -> Vec<(String, &Action)> {
+  let mut bindings: Vec<_> = keymap.iter().map(|(keys, action)| (key_sequence_display(keys), action)).collect();
+  bindings.sort_by_cached_key(|(keys, action)| (action.to_string(), keys.clone()));
+  bindings
+}
+
+/// The key bindings of each mode, plus the `Global` ones. Dereferences to the per-mode bindings.
+#[derive(Clone, Debug, Default)]
+pub struct KeyBindings {
+  modes: HashMap<Mode, Keymap>,
+  /// Bindings that apply in every mode, unless the mode binds the same keys itself.
+  pub global: Keymap,
+  /// Keys set to `null` or `"unbind"`, which remove a binding made by a layer beneath, by section: `None` for the
+  /// global one. Keys unbound in a mode's section stay unbound there even if they are bound globally.
+  unbound: HashMap<Option<Mode>, HashSet<Vec<KeyEvent>>>,
+}
+
+impl KeyBindings {
+  /// The section of `keybindings` holding the global bindings.
+  pub const GLOBAL: &'static str = "Global";
+
+  /// The value that removes a binding, besides `null`.
+  pub const UNBIND: &'static str = "unbind";
+
+  /// The bindings in effect in `mode`: the global ones, overridden by the mode's own.
+  pub fn merged(&self, mode: Mode) -> Keymap {
+    let unbound = self.unbound.get(&Some(mode));
+    let mut keymap: Keymap = self
+      .global
+      .iter()
+      .filter(|(keys, _)| !unbound.is_some_and(|unbound| unbound.contains(*keys)))
+      .map(|(keys, action)| (keys.clone(), action.clone()))
+      .collect();
+    keymap.extend(self.modes.get(&mode).into_iter().flatten().map(|(keys, action)| (keys.clone(), action.clone())));
+    keymap
+  }
+
+  /// Whether `keys` are bound in `mode`, either by the mode itself or globally.
+  pub fn binds(&self, mode: Mode, keys: &[KeyEvent]) -> bool {
+    let unbound = self.unbound.get(&Some(mode)).is_some_and(|unbound| unbound.contains(keys));
+    (self.global.contains_key(keys) && !unbound)
+      || self.modes.get(&mode).is_some_and(|bindings| bindings.contains_key(keys))
+  }
+
+  /// Every binding that is the start of a longer one in the same mode, which makes the longer one unreachable.
+  pub fn prefix_conflicts(&self) -> Vec<String> {
+    let mut conflicts: Vec<String> = Mode::ALL
+      .iter()
+      .flat_map(|mode| {
+        let keymap = self.merged(*mode);
+        keymap
+          .iter()
+          .flat_map(|(keys, action)| {
+            keymap.iter().filter(|(longer, _)| longer.len() > keys.len() && longer.starts_with(keys)).map(
+              move |(longer, shadowed)| {
+                format!(
+                  "in {mode:?}, {} ({action}) shadows {} ({shadowed})",
+                  key_sequence_to_string(keys),
+                  key_sequence_to_string(longer)
+                )
+              },
+            )
+          })
+          .collect::<Vec<_>>()
+      })
+      .collect();
+    conflicts.sort();
+    conflicts
+  }
+
+  /// The bindings that stand in the way of binding `keys` in `section`, `None` being the global one: those bound to
+  /// the same keys, to the start of them, or to something they are the start of, in any mode the section applies to.
+  ///
+  /// Each comes with the section to unbind it in to make way. Clashes within a mode are unbound in that mode only, so
+  /// a global binding clashing with a mode's is left alone elsewhere.
+  pub fn clashes(&self, section: Option<Mode>, keys: &[KeyEvent]) -> Vec<(Option<Mode>, Vec<KeyEvent>, Action)> {
+    let modes = section.map_or(Mode::ALL.to_vec(), |mode| vec![mode]);
+    let mut clashes: Vec<(Option<Mode>, Vec<KeyEvent>, Action)> = Vec::new();
+    for mode in modes {
+      for (bound, action) in self.merged(mode) {
+        if !bound.starts_with(keys) && !keys.starts_with(&bound) {
+          continue;
+        }
+        let own = self.modes.get(&mode).is_some_and(|keymap| keymap.contains_key(&bound));
+        let clash = (section.or(own.then_some(mode)), bound, action);
+        if !clashes.contains(&clash) {
+          clashes.push(clash);
+        }
+      }
+    }
+    clashes.sort_by_cached_key(|(section, keys, _)| (section_name(*section), key_sequence_to_string(keys)));
+    clashes
+  }
+
+  /// Binds `keys` to `action` in `section`, `None` being the global one, or unbinds them there if there is no action,
+  /// so they also stay unbound when laid over other bindings with [`KeyBindings::extend`].
+  pub fn set(&mut self, section: Option<Mode>, keys: Vec<KeyEvent>, action: Option<Action>) {
+    let keymap = match section {
+      None => &mut self.global,
+      Some(mode) => self.modes.entry(mode).or_default(),
+    };
+    match action {
+      Some(action) => {
+        keymap.insert(keys.clone(), action);
+        if let Some(unbound) = self.unbound.get_mut(&section) {
+          unbound.remove(&keys);
+        }
+      },
+      None => {
+        keymap.remove(&keys);
+        self.unbound.entry(section).or_default().insert(keys);
+      },
+    }
+  }
+
+  /// Adds `other`'s bindings, replacing any of these bound to the same keys in the same section, and removes those
+  /// `other` unbinds.
+  pub fn extend(&mut self, other: KeyBindings) {
+    for (section, keys) in other.unbound {
+      let keymap = match section {
+        None => Some(&mut self.global),
+        Some(mode) => self.modes.get_mut(&mode),
+      };
+      if let Some(keymap) = keymap {
+        keymap.retain(|bound, _| !keys.contains(bound));
+      }
+      self.unbound.entry(section).or_default().extend(keys);
+    }
+    self.global.extend(other.global);
+    for (mode, bindings) in other.modes {
+      self.modes.entry(mode).or_default().extend(bindings);
+    }
+  }
+
+  /// Replaces every `<leader>` in the bound and unbound keys with `leader`.
+  pub fn expand_leader(&mut self, leader: &[KeyEvent]) {
+    let expand = |keys| expand_leader_keys(keys, leader);
+    for keymap in self.modes.values_mut().chain([&mut self.global]) {
+      *keymap = std::mem::take(keymap).into_iter().map(|(keys, action)| (expand(keys), action)).collect();
+    }
+    for keys in self.unbound.values_mut() {
+      *keys = std::mem::take(keys).into_iter().map(expand).collect();
+    }
+  }
+}
+
+impl std::ops::Deref for KeyBindings {
+  type Target = HashMap<Mode, Keymap>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.modes
+  }
+}
+
+impl std::ops::DerefMut for KeyBindings {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.modes
+  }
+}
 
 impl<'de> Deserialize<'de> for KeyBindings {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let parsed_map = HashMap::<String, HashMap<String, Option<JsonValue>>>::deserialize(deserializer)?;
+
+    let mut keybindings = KeyBindings::default();
+    let mut conflicts = Vec::new();
+    for (section, inner_map) in parsed_map {
+      // Different spellings of the same keys, e.g. `<Ctrl-a>` and `<ctrl-a>`, must not quietly replace each other.
+      let mut inner_map: Vec<(String, Option<JsonValue>)> = inner_map.into_iter().collect();
+      inner_map.sort_by(|(a, _), (b, _)| a.cmp(b));
+      let mut converted_inner_map = HashMap::<Vec<KeyEvent>, Option<Action>>::new();
+      for (key_str, cmd) in inner_map {
+        let keys = parse_key_sequence(&key_str).map_err(de::Error::custom)?;
+        let cmd = match cmd {
+          None | Some(JsonValue::Null) => None,
+          Some(JsonValue::String(unbind)) if unbind == KeyBindings::UNBIND => None,
+          Some(cmd) => Some(serde_json::from_value::<Action>(cmd).map_err(de::Error::custom)?),
+        };
+        match converted_inner_map.get(&keys) {
+          Some(existing) if *existing != cmd => {
+            let describe =
+              |cmd: &Option<Action>| cmd.as_ref().map_or(KeyBindings::UNBIND.to_string(), Action::to_string);
+            conflicts.push(format!(
+              "in {section}, {} is bound to both {} and {}",
+              key_sequence_to_string(&keys),
+              describe(existing),
+              describe(&cmd)
+            ))
+          },
+          _ => {
+            converted_inner_map.insert(keys, cmd);
+          },
+        }
+      }
+      let section = parse_section(&section).map_err(de::Error::custom)?;
+      let mut keymap = Keymap::new();
+      for (keys, cmd) in converted_inner_map {
+        match cmd {
+          Some(cmd) => {
+            keymap.insert(keys, cmd);
+          },
+          None => {
+            keybindings.unbound.entry(section).or_default().insert(keys);
+          },
+        }
+      }
+      match section {
+        None => keybindings.global = keymap,
+        Some(mode) => {
+          keybindings.insert(mode, keymap);
+        },
+      }
+    }
+    if !conflicts.is_empty() {
+      conflicts.sort();
+      return Err(de::Error::custom(format!("Conflicting key bindings: {}", conflicts.join("; "))));
+    }
+
+    Ok(keybindings)
+  }
+}
+
+/// A mouse button press together with the modifier keys held at the time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MouseBinding {
+  pub button: MouseButton,
+  pub modifiers: KeyModifiers,
+}
+
+impl MouseBinding {
+  /// The binding a mouse event would trigger. Only button presses can be bound.
+  pub fn from_event(mouse: &MouseEvent) -> Option<Self> {
+    match mouse.kind {
+      MouseEventKind::Down(button) => Some(Self { button, modifiers: mouse.modifiers }),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default, Deref, DerefMut)]
+pub struct MouseBindings(pub HashMap<Mode, HashMap<MouseBinding, Action>>);
+
+impl<'de> Deserialize<'de> for MouseBindings {
   fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
     D: Deserializer<'de>,
   {
     let parsed_map = HashMap::<Mode, HashMap<String, Action>>::deserialize(deserializer)?;
 
-    let keybindings = parsed_map
+    let mousebindings = parsed_map
       .into_iter()
       .map(|(mode, inner_map)| {
-        let converted_inner_map =
-          inner_map.into_iter().map(|(key_str, cmd)| (parse_key_sequence(&key_str).unwrap(), cmd)).collect();
-        (mode, converted_inner_map)
+        let converted_inner_map = inner_map
+          .into_iter()
+          .map(|(button_str, cmd)| parse_mouse_binding(&button_str).map(|button| (button, cmd)))
+          .collect::<Result<_, _>>()
+          .map_err(de::Error::custom)?;
+        Ok((mode, converted_inner_map))
       })
-      .collect();
+      .collect::<Result<_, D::Error>>()?;
 
-    Ok(KeyBindings(keybindings))
+    Ok(MouseBindings(mousebindings))
   }
 }
 
-fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
+/// Parses a mouse binding such as `<Middle>` or `<Ctrl-Left>`, using the same modifier prefixes as key bindings.
+pub fn parse_mouse_binding(raw: &str) -> Result<MouseBinding, String> {
+  let inner =
+    raw.strip_prefix('<').and_then(|r| r.strip_suffix('>')).ok_or_else(|| format!("Unable to parse `{}`", raw))?;
+  let raw_lower = inner.to_ascii_lowercase();
+  let (remaining, modifiers) = extract_modifiers(&raw_lower);
+  let button = match remaining {
+    "left" => MouseButton::Left,
+    "right" => MouseButton::Right,
+    "middle" => MouseButton::Middle,
+    _ => return Err(format!("Unable to parse mouse button `{}`", raw)),
+  };
+  Ok(MouseBinding { button, modifiers })
+}
+
+/// Stands in for `leader` in parsed bindings until [`KeyBindings::expand_leader`] swaps in the configured keys.
+pub const LEADER: KeyEvent = KeyEvent::new(KeyCode::Null, KeyModifiers::NONE);
+
+/// `keys` with every [`LEADER`] replaced by `leader`.
+fn expand_leader_keys(keys: Vec<KeyEvent>, leader: &[KeyEvent]) -> Vec<KeyEvent> {
+  keys.into_iter().flat_map(|key| if key == LEADER { leader.to_vec() } else { vec![key] }).collect()
+}
+
+/// Parses a single key as written inside the `<>` of a binding, e.g. `ctrl-c` or `f5`: any `ctrl-`, `alt-` and `shift-`
+/// prefixes, or their short forms `c-`, `a-` and `s-`, in any order and any case, then a key name or a single
+/// character. `leader` is the [`LEADER`] placeholder. The inverse of [`key_event_to_string`].
+pub fn string_to_key_event(raw: &str) -> Result<KeyEvent, String> {
+  if raw.eq_ignore_ascii_case("leader") {
+    return Ok(LEADER);
+  }
   let raw_lower = raw.to_ascii_lowercase();
   let (remaining, modifiers) = extract_modifiers(&raw_lower);
+  // Lowercasing ASCII keeps every byte where it was, so the key can be named the way it was written.
   parse_key_code_with_modifiers(remaining, modifiers)
+    .map_err(|_| format!("Unknown key `{}`", &raw[raw.len() - remaining.len()..]))
 }
 
 fn extract_modifiers(raw: &str) -> (&str, KeyModifiers) {
+  const PREFIXES: [(&str, KeyModifiers); 6] = [
+    ("ctrl-", KeyModifiers::CONTROL),
+    ("alt-", KeyModifiers::ALT),
+    ("shift-", KeyModifiers::SHIFT),
+    ("c-", KeyModifiers::CONTROL),
+    ("a-", KeyModifiers::ALT),
+    ("s-", KeyModifiers::SHIFT),
+  ];
   let mut modifiers = KeyModifiers::empty();
   let mut current = raw;
 
-  loop {
-    match current {
-      rest if rest.starts_with("ctrl-") => {
-        modifiers.insert(KeyModifiers::CONTROL);
-        current = &rest[5..];
-      },
-      rest if rest.starts_with("alt-") => {
-        modifiers.insert(KeyModifiers::ALT);
-        current = &rest[4..];
-      },
-      rest if rest.starts_with("shift-") => {
-        modifiers.insert(KeyModifiers::SHIFT);
-        current = &rest[6..];
-      },
-      _ => break, // break out of the loop if no known prefix is detected
-    };
+  // A prefix with nothing after it is the key itself, like the `a` of `ctrl-a-`.
+  while let Some((rest, modifier)) = PREFIXES.iter().find_map(|(prefix, modifier)| {
+    current.strip_prefix(prefix).filter(|rest| !rest.is_empty()).map(|rest| (rest, *modifier))
+  }) {
+    modifiers.insert(modifier);
+    current = rest;
   }
 
   (current, modifiers)
 }
 
-fn parse_key_code_with_modifiers(raw: &str, mut modifiers: KeyModifiers) -> Result<KeyEvent, String> {
+fn parse_key_code_with_modifiers(raw: &str, mut modifiers: KeyModifiers) -> Result<KeyEvent, ()> {
   let c = match raw {
     "esc" => KeyCode::Esc,
     "enter" => KeyCode::Enter,
@@ -152,30 +1187,26 @@ fn parse_key_code_with_modifiers(raw: &str, mut modifiers: KeyModifiers) -> Resu
     "backspace" => KeyCode::Backspace,
     "delete" => KeyCode::Delete,
     "insert" => KeyCode::Insert,
-    "f1" => KeyCode::F(1),
-    "f2" => KeyCode::F(2),
-    "f3" => KeyCode::F(3),
-    "f4" => KeyCode::F(4),
-    "f5" => KeyCode::F(5),
-    "f6" => KeyCode::F(6),
-    "f7" => KeyCode::F(7),
-    "f8" => KeyCode::F(8),
-    "f9" => KeyCode::F(9),
-    "f10" => KeyCode::F(10),
-    "f11" => KeyCode::F(11),
-    "f12" => KeyCode::F(12),
+    f if f.len() > 1 && f.starts_with('f') => match f[1..].parse() {
+      Ok(n) if (1..=24).contains(&n) => KeyCode::F(n),
+      _ => return Err(()),
+    },
     "space" => KeyCode::Char(' '),
     "hyphen" => KeyCode::Char('-'),
     "minus" => KeyCode::Char('-'),
     "tab" => KeyCode::Tab,
-    c if c.len() == 1 => {
+    c if c.chars().count() == 1 => {
       let mut c = c.chars().next().unwrap();
       if modifiers.contains(KeyModifiers::SHIFT) {
-        c = c.to_ascii_uppercase();
+        let mut upper = c.to_uppercase();
+        // Some characters, like `ß`, have no single uppercase form, and stay as they are.
+        if let (Some(u), None) = (upper.next(), upper.next()) {
+          c = u;
+        }
       }
       KeyCode::Char(c)
     },
-    _ => return Err(format!("Unable to parse {raw}")),
+    _ => return Err(()),
   };
   Ok(KeyEvent::new(c, modifiers))
 }
@@ -198,7 +1229,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
     KeyCode::Delete => "delete",
     KeyCode::Insert => "insert",
     KeyCode::F(c) => {
-      char = format!("f({c})");
+      char = format!("f{c}");
       &char
     },
     KeyCode::Char(' ') => "space",
@@ -207,7 +1238,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
       &char
     },
     KeyCode::Esc => "esc",
-    KeyCode::Null => "",
+    KeyCode::Null => "leader",
     KeyCode::CapsLock => "",
     KeyCode::Menu => "",
     KeyCode::ScrollLock => "",
@@ -243,36 +1274,61 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
   key
 }
 
+/// Formats `keys` the way they are written in the config, e.g. `<g><ctrl-x>`.
+pub fn key_sequence_to_string(keys: &[KeyEvent]) -> String {
+  keys.iter().map(|key| format!("<{}>", key_event_to_string(key))).collect()
+}
+
+/// Keys as the UI shows them, separated by spaces, e.g. `g ctrl-x`.
+pub fn key_sequence_display(keys: &[KeyEvent]) -> String {
+  keys.iter().map(key_event_to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// Parses the keys of a binding, split on whitespace.
+///
+/// A part written without `<` is a single key, as [`string_to_key_event`] reads it, so `ctrl-shift-p` and `g g` work
+/// as they read. A part with `<` in it is written the way [`key_sequence_to_string`] writes keys: each `<...>` is a
+/// key, and any character outside the brackets is a key by itself, so `<g><g>`, `<c>a` and `<ctrl-w>gg` all work too.
+/// Errors name the key that couldn't be read.
 pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
-  if raw.chars().filter(|c| *c == '>').count() != raw.chars().filter(|c| *c == '<').count() {
-    return Err(format!("Unable to parse `{}`", raw));
+  let in_raw = |e: String| format!("{e} in `{raw}`");
+  let mut keys = Vec::new();
+  for part in raw.split_whitespace() {
+    if !part.contains('<') {
+      keys.push(string_to_key_event(part).map_err(in_raw)?);
+      continue;
+    }
+    let mut rest = part;
+    while let Some(c) = rest.chars().next() {
+      let (key, after) = match c {
+        '<' => rest[1..].split_once('>').ok_or_else(|| in_raw("Unclosed `<`".to_string()))?,
+        c => rest.split_at(c.len_utf8()),
+      };
+      keys.push(string_to_key_event(key).map_err(in_raw)?);
+      rest = after;
+    }
+  }
+  match keys.is_empty() {
+    true => Err(format!("No keys in `{raw}`")),
+    false => Ok(keys),
   }
-  let raw = if !raw.contains("><") {
-    let raw = raw.strip_prefix('<').unwrap_or(raw);
-    let raw = raw.strip_prefix('>').unwrap_or(raw);
-    raw
-  } else {
-    raw
-  };
-  let sequences = raw
-    .split("><")
-    .map(|seq| {
-      if let Some(s) = seq.strip_prefix('<') {
-        s
-      } else if let Some(s) = seq.strip_suffix('>') {
-        s
-      } else {
-        seq
-      }
-    })
-    .collect::<Vec<_>>();
-
-  sequences.into_iter().map(parse_key_event).collect()
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
 pub struct Styles(pub HashMap<Mode, HashMap<String, Style>>);
 
+impl Styles {
+  /// Adds the styles in `defaults` that aren't set here, mode by mode.
+  fn fill_in(&mut self, defaults: &Styles) {
+    for (mode, default_styles) in defaults.iter() {
+      let styles = self.entry(*mode).or_default();
+      for (style_key, style) in default_styles.iter() {
+        styles.entry(style_key.clone()).or_insert_with(|| *style);
+      }
+    }
+  }
+}
+
 impl<'de> Deserialize<'de> for Styles {
   fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
@@ -390,7 +1446,7 @@ fn parse_color(s: &str) -> Option<Color> {
 mod tests {
   use pretty_assertions::assert_eq;
 
-  use crate::actions::engine_actions::EngineAction;
+  use crate::actions::{engine_actions::EngineAction, ListNavDirection};
 
   use super::*;
 
@@ -441,43 +1497,511 @@ mod tests {
     assert_eq!(color, None);
   }
 
+  #[test]
+  fn test_mouse_binding_resolves_to_action() -> Result<()> {
+    let c: Config = json5::from_str(
+      r#"{ "mousebindings": { "Home": { "<Middle>": "Engine.Refresh", "<Ctrl-Left>": "Engine.ToggleShowHelp" } } }"#,
+    )?;
+    let click = |button, modifiers| MouseEvent { kind: MouseEventKind::Down(button), column: 0, row: 0, modifiers };
+
+    assert_eq!(
+      c.mouse_action(Mode::Home, &click(MouseButton::Middle, KeyModifiers::empty())),
+      Some(&EngineAction::Refresh.into())
+    );
+    assert_eq!(
+      c.mouse_action(Mode::Home, &click(MouseButton::Left, KeyModifiers::CONTROL)),
+      Some(&EngineAction::ToggleShowHelp.into())
+    );
+    assert_eq!(c.mouse_action(Mode::Home, &click(MouseButton::Left, KeyModifiers::empty())), None);
+    assert_eq!(c.mouse_action(Mode::MainMenu, &click(MouseButton::Middle, KeyModifiers::empty())), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_mouse_binding() {
+    assert!(parse_mouse_binding("<Wheel>").is_err());
+    assert!(parse_mouse_binding("Middle").is_err());
+    assert!(json5::from_str::<Config>(r#"{ "mousebindings": { "Home": { "<Fourth>": "Engine.Quit" } } }"#).is_err());
+  }
+
   #[test]
   fn test_config() -> Result<()> {
     let c = Config::new()?;
     assert_eq!(
-      c.keybindings.get(&Mode::Home).unwrap().get(&parse_key_sequence("<ctrl-c>").unwrap_or_default()).unwrap(),
+      c.keymap(Mode::Home).get(&parse_key_sequence("<ctrl-c>").unwrap_or_default()).unwrap(),
       &EngineAction::Quit.into()
     );
+    assert_eq!(
+      c.keymap(Mode::Home).get(&parse_key_sequence("<ctrl-w>").unwrap_or_default()).unwrap(),
+      &EngineAction::Batch(vec![HomeAction::Save.into(), EngineAction::ChangeMode(Mode::MainMenu).into()]).into()
+    );
+    Ok(())
+  }
+
+  fn config_dir_with(name: &str, contents: &str) -> Result<PathBuf> {
+    let config_dir =
+      std::env::temp_dir().join(format!("{}-config-{name}-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    std::fs::create_dir_all(&config_dir)?;
+    std::fs::write(config_dir.join("config.json5"), contents)?;
+    Ok(config_dir)
+  }
+
+  #[test]
+  fn test_config_with_only_home_bindings() -> Result<()> {
+    let config_dir = config_dir_with("home-only", r#"{ "keybindings": { "Home": { "<q>": "Engine.Quit" } } }"#)?;
+    let c = Config::load(&config_dir)?;
+
+    let home = c.keymap(Mode::Home);
+    assert_eq!(home.get(&parse_key_sequence("<q>").unwrap()), Some(&EngineAction::Quit.into()));
+    assert_eq!(home.get(&parse_key_sequence("<ctrl-c>").unwrap()), Some(&EngineAction::Quit.into()));
+    assert!(!c.keymap(Mode::MainMenu).is_empty());
+
+    // Components must cope with a config that has no sections at all.
+    use crate::components::{home::Home, main_menu::MainMenu, Component};
+    Home::new().register_config_handler(Config::default())?;
+    MainMenu::new().register_config_handler(Config::default())?;
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_global_bindings_apply_in_every_mode() -> Result<()> {
+    let config_dir = config_dir_with(
+      "global",
+      r#"{ "keybindings": { "Global": { "<q>": "Engine.Quit", "<g><g>": "Engine.Refresh" }, "Home": { "<q>": "Home.Save" } } }"#,
+    )?;
+    let c = Config::load(&config_dir)?;
+    let key = |keys: &str| parse_key_sequence(keys).unwrap();
+
+    assert_eq!(c.keymap(Mode::MainMenu).get(&key("<q>")), Some(&EngineAction::Quit.into()));
+    assert_eq!(c.keymap(Mode::Home).get(&key("<q>")), Some(&HomeAction::Save.into()));
+    assert_eq!(c.keymap(Mode::Home).get(&key("<g><g>")), Some(&EngineAction::Refresh.into()));
+    // The built-in global bindings are still there underneath the user's.
+    assert_eq!(c.keymap(Mode::Home).get(&key("<ctrl-c>")), Some(&EngineAction::Quit.into()));
+    assert_eq!(c.binding_for(Mode::MainMenu, &EngineAction::ExportActionLog.into()), Some("ctrl-e".to_string()));
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_theme_colors_by_name_or_hex() -> Result<()> {
+    let config_dir = config_dir_with(
+      "theme",
+      r##"{ "theme": { "primary": "#ff8800", "accent": "light-red", "dim": 244, "border": "17", "shade": "red" } }"##,
+    )?;
+    let c = Config::load(&config_dir)?;
+    assert_eq!(
+      c.theme,
+      Theme {
+        primary: Color::Rgb(0xff, 0x88, 0x00),
+        accent: Color::LightRed,
+        dim: Color::Indexed(244),
+        border: Color::Indexed(17),
+        ..Theme::default()
+      }
+    );
+    std::fs::remove_dir_all(config_dir)?;
+
+    for (name, theme, bad) in
+      [("bad-theme", r##"{ "error": "#ff88" }"##, "`#ff88`"), ("bad-theme-index", r##"{ "dim": 256 }"##, "`256`")]
+    {
+      let config_dir = config_dir_with(name, &format!(r#"{{ "theme": {theme} }}"#))?;
+      let error = Config::load(&config_dir).unwrap_err().to_string();
+      let key = theme.split('"').nth(1).unwrap();
+      assert!(
+        error.contains(&format!("Invalid color {bad}, expected a color name, #rrggbb or 0-255 for theme.{key}")),
+        "{error}"
+      );
+      std::fs::remove_dir_all(config_dir)?;
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn test_built_in_themes_switch_and_come_back_to_the_configured_one() -> Result<()> {
+    let config_dir = config_dir_with("switch-theme", r#"{ "theme": { "primary": "green" } }"#)?;
+    let mut c = Config::load(&config_dir)?;
+    let configured = c.theme;
+    assert_eq!(c.theme_name(), "default");
+
+    assert_eq!(c.cycle_theme(), "high-contrast");
+    assert_eq!(Some(c.theme), Theme::builtin("high-contrast"));
+    // Reloading the styles keeps a palette picked at runtime.
+    c.reload_styles()?;
+    assert_eq!(c.theme_name(), "high-contrast");
+    assert_eq!(c.cycle_theme(), "monochrome");
+    assert!(c.theme.monochrome);
+    assert_eq!(c.cycle_theme(), "default");
+    assert_eq!(c.theme, configured);
+
+    assert_eq!(c.set_theme("monochrome"), Ok("monochrome"));
+    assert_eq!(
+      c.set_theme("neon"),
+      Err("Unknown theme `neon`, expected one of default, high-contrast, monochrome".to_string())
+    );
+    assert_eq!(c.theme_name(), "monochrome");
+    assert_eq!(c.set_theme("default"), Ok("default"));
+    assert_eq!(c.theme.primary, Color::Green);
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_action_trace_paths_are_taken_from_the_data_dir() -> Result<()> {
+    let mut c: Config = json5::from_str(r#"{ "_data_dir": "/data", "action_trace": "trace.jsonl" }"#)?;
+    if std::env::var_os(&*ACTION_TRACE_ENV).is_none() {
+      assert_eq!(c.action_trace_path(), Some(PathBuf::from("/data/trace.jsonl")));
+      c.action_trace = Some(PathBuf::from("/tmp/trace.jsonl"));
+      assert_eq!(c.action_trace_path(), Some(PathBuf::from("/tmp/trace.jsonl")));
+      c.action_trace = None;
+      assert_eq!(c.action_trace_path(), None);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn test_monochrome_styles_use_modifiers_instead_of_colors() {
+    let theme = Theme::builtin("monochrome").unwrap();
+    assert_eq!(theme.accent_style(), Style::default().add_modifier(Modifier::BOLD));
+    assert_eq!(theme.highlight_style(), Style::default().add_modifier(Modifier::UNDERLINED));
+    assert_eq!(theme.error_style(), Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+    assert_eq!(theme.dim_style(), Style::default().add_modifier(Modifier::DIM));
+    assert_eq!(theme.primary_style(), Style::default());
+    assert_eq!(Theme::default().error_style(), Style::default().fg(Color::Red));
+
+    let configured: Theme = json5::from_str(r#"{ "monochrome": true, "accent": "red" }"#).unwrap();
+    assert_eq!(configured.accent_style(), Style::default().add_modifier(Modifier::BOLD));
+    assert!(json5::from_str::<Theme>(r#"{ "monochrome": "yes" }"#).is_err());
+  }
+
+  #[test]
+  fn test_conflicting_bindings_are_reported() -> Result<()> {
+    let config_dir = config_dir_with(
+      "duplicates",
+      r#"{ "keybindings": { "Home": { "<Ctrl-a>": "Engine.Quit", "<ctrl-a>": "Engine.Refresh", "<q>": "Engine.Quit" } } }"#,
+    )?;
+    let error = Config::load(&config_dir).unwrap_err().to_string();
+    assert_eq!(error, "Conflicting key bindings: in Home, <ctrl-a> is bound to both Engine.Quit and Engine.Refresh");
+    std::fs::remove_dir_all(config_dir)?;
+
+    let shadowing = r#""keybindings": { "Home": { "<z>": "Engine.Quit", "<z><z>": "Engine.Refresh" } }"#;
+    let config_dir = config_dir_with("shadowing", &format!("{{ {shadowing} }}"))?;
+    let error = Config::load(&config_dir).unwrap_err().to_string();
+    assert!(error.contains("in Home, <z> (Engine.Quit) shadows <z><z> (Engine.Refresh)"), "{error}");
+    std::fs::remove_dir_all(config_dir)?;
+
+    let config_dir = config_dir_with(
+      "shadowing-allowed",
+      &format!(r#"{{ {shadowing}, "chords": {{ "allow_prefix_shadowing": true }} }}"#),
+    )?;
+    let c = Config::load(&config_dir)?;
+    assert_eq!(c.keymap(Mode::Home).get(&parse_key_sequence("<z>").unwrap()), Some(&EngineAction::Quit.into()));
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_unknown_mode_sections_reported_together() -> Result<()> {
+    let config_dir = config_dir_with(
+      "unknown-modes",
+      r#"{
+        "keybindings": { "Home": {}, "Settings": { "<q>": "Engine.Quit" } },
+        "mousebindings": { "Nowhere": {} },
+      }"#,
+    )?;
+    let error = Config::load(&config_dir).unwrap_err().to_string();
+
+    assert!(error.contains("keybindings.Settings, mousebindings.Nowhere"), "{error}");
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_switching_presets_keeps_user_bindings() -> Result<()> {
+    let config_dir = config_dir_with("presets", r#"{ "keybindings": { "Home": { "<j>": "Engine.Quit" } } }"#)?;
+    let mut c = Config::load(&config_dir)?;
+    let key = |keys: &str| parse_key_sequence(keys).unwrap();
+    let home = |c: &Config, keys: &str| c.keymap(Mode::Home).get(&key(keys)).cloned();
+
+    assert_eq!(c.preset, Preset::Default);
+    assert_eq!(home(&c, "<l>"), Some(EngineAction::ToggleShowHelp.into()));
+    assert_eq!(c.binding_source(Mode::Home, &key("<l>")), Some(BindingSource::BuiltIn));
+
+    // Everything the preset binds changes, apart from the user's own j.
+    assert_eq!(c.apply_preset(Preset::Vim), 8);
+    assert_eq!(home(&c, "<j>"), Some(EngineAction::Quit.into()));
+    assert_eq!(c.binding_source(Mode::Home, &key("<j>")), Some(BindingSource::User));
+    assert_eq!(home(&c, "<k>"), Some(HomeAction::NavigateList(ListNavDirection::Up, 1).into()));
+    assert_eq!(c.binding_source(Mode::Home, &key("<k>")), Some(BindingSource::Preset(Preset::Vim)));
+    assert_eq!(home(&c, "<ctrl-c>"), Some(EngineAction::Quit.into()));
+    assert_eq!(c.binding_source(Mode::Home, &key("<ctrl-c>")), Some(BindingSource::BuiltIn));
+
+    c.apply_preset(Preset::Default);
+    assert_eq!(home(&c, "<k>"), Some(HomeAction::ScheduleDecrement(1).into()));
+    assert_eq!(home(&c, "<h>"), None);
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_user_bindings_override_add_and_unbind_defaults() -> Result<()> {
+    let config_dir = config_dir_with(
+      "unbind",
+      r#"{ "keybindings": {
+        "Global": { "<ctrl-d>": null },
+        "Home": { "<j>": "Engine.Quit", "<x>": "Home.Save", "<d>": "unbind", "<ctrl-c>": "unbind" },
+      } }"#,
+    )?;
+    let c = Config::load(&config_dir)?;
+    let key = |keys: &str| parse_key_sequence(keys).unwrap();
+    let bound = |mode: Mode, keys: &str| c.keymap(mode).get(&key(keys)).cloned();
+
+    // Overridden, added, and left to the defaults.
+    assert_eq!(bound(Mode::Home, "<j>"), Some(EngineAction::Quit.into()));
+    assert_eq!(bound(Mode::Home, "<x>"), Some(HomeAction::Save.into()));
+    assert_eq!(bound(Mode::Home, "<k>"), Some(HomeAction::ScheduleDecrement(1).into()));
+    // Unbound, globally or in one mode only.
+    assert_eq!(bound(Mode::Home, "<d>"), None);
+    assert_eq!(bound(Mode::MainMenu, "<ctrl-d>"), None);
+    assert_eq!(bound(Mode::Home, "<ctrl-c>"), None);
+    assert!(!c.keybindings.binds(Mode::Home, &key("<ctrl-c>")));
+    assert_eq!(bound(Mode::MainMenu, "<ctrl-c>"), Some(EngineAction::Quit.into()));
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_leader_stands_for_the_configured_keys() -> Result<()> {
+    let bindings = r#""keybindings": { "Home": { "<leader> f": "Home.Save", "<Leader><leader>": "Engine.Refresh" } }"#;
+    let key = |keys: &str| parse_key_sequence(keys).unwrap();
+
+    let config_dir = config_dir_with("leader-default", &format!("{{ {bindings} }}"))?;
+    let c = Config::load(&config_dir)?;
+    assert_eq!(c.keymap(Mode::Home).get(&key("space f")), Some(&HomeAction::Save.into()));
+    std::fs::remove_dir_all(config_dir)?;
+
+    let config_dir = config_dir_with("leader-ctrl-space", &format!(r#"{{ "leader": "ctrl-space", {bindings} }}"#))?;
+    let c = Config::load(&config_dir)?;
+    let home = c.keymap(Mode::Home);
+    assert_eq!(home.get(&key("ctrl-space f")), Some(&HomeAction::Save.into()));
+    assert_eq!(home.get(&key("ctrl-space ctrl-space")), Some(&EngineAction::Refresh.into()));
+    assert_eq!(home.get(&key("space f")), None);
+    assert_eq!(c.binding_source(Mode::Home, &key("ctrl-space f")), Some(BindingSource::User));
+    std::fs::remove_dir_all(config_dir)?;
+
+    let config_dir = config_dir_with("leader-itself", r#"{ "leader": "<leader>" }"#)?;
+    assert!(Config::load(&config_dir).is_err());
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_changed_bindings_apply_at_once_and_save_to_the_config() -> Result<()> {
+    let config_dir = config_dir_with(
+      "save-bindings",
+      r#"{ "keybindings": { "Home": { "<Ctrl-x>": "Home.Save", "<leader>u": "Home.Undo" } } } // comments are lost"#,
+    )?;
+    let key = |keys: &str| parse_key_sequence(keys).unwrap();
+    let mut c = Config::load(&config_dir)?;
+    assert_eq!(
+      c.keybindings.clashes(Some(Mode::Home), &key("ctrl-x y")),
+      vec![(Some(Mode::Home), key("ctrl-x"), HomeAction::Save.into())]
+    );
+
+    c.set_binding(Some(Mode::Home), key("ctrl-x"), None);
+    c.set_binding(Some(Mode::Home), key("ctrl-x y"), Some(HomeAction::Save.into()));
+    c.set_binding(Some(Mode::Home), key("space u"), Some(HomeAction::Redo.into()));
+    c.set_binding(None, key("ctrl-d"), None);
+    assert!(c.has_unsaved_bindings());
+    let home = c.keymap(Mode::Home);
+    assert_eq!(home.get(&key("ctrl-x")), None);
+    assert_eq!(home.get(&key("ctrl-x y")), Some(&HomeAction::Save.into()));
+    assert_eq!(c.binding_source(Mode::Home, &key("ctrl-x y")), Some(BindingSource::User));
+    assert_eq!(c.keymap(Mode::MainMenu).get(&key("ctrl-d")), None);
+
+    assert_eq!(c.save_keybindings()?, config_dir.join("config.json5"));
+    assert!(!c.has_unsaved_bindings());
+    let saved = Config::load(&config_dir)?;
+    for mode in Mode::ALL {
+      assert_eq!(saved.keymap(mode), c.keymap(mode), "{mode:?}");
+    }
+    // The leader spelling of the rebound keys made way for the new entry.
+    let written = std::fs::read_to_string(config_dir.join("config.json5"))?;
+    assert!(!written.contains("<leader>u"), "{written}");
+    std::fs::remove_dir_all(config_dir)?;
+
+    let config_dir = config_dir_with("save-bindings-yaml", "{}")?;
+    std::fs::write(config_dir.join("config.yaml"), "tick_rate: 2\n")?;
+    let mut c = Config::load(&config_dir)?;
+    c.set_binding(None, key("ctrl-d"), None);
+    assert!(c.save_keybindings().is_err());
+    std::fs::remove_dir_all(config_dir)?;
     Ok(())
   }
 
+  #[test]
+  fn test_rates_default_and_reject_non_positive() -> Result<()> {
+    let c: Config = json5::from_str(r#"{ "tick_rate": 4 }"#)?;
+    assert_eq!((c.tick_rate(), c.frame_rate()), (4.0, DEFAULT_FRAME_RATE));
+    assert_eq!(c.validate_rates(), Ok(()));
+
+    let c: Config = json5::from_str(r#"{ "frame_rate": 0 }"#)?;
+    assert_eq!(
+      c.validate_rates(),
+      Err("Invalid frame_rate: expected a positive number of events per second, got 0".to_string())
+    );
+
+    let config_dir = config_dir_with("rates", r#"{ "tick_rate": -1 }"#)?;
+    let error = Config::load(&config_dir).unwrap_err().to_string();
+    assert!(error.contains("Invalid tick_rate"), "{error}");
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_tabs_validation() -> Result<()> {
+    assert_eq!(TabsConfig::default().validate(), Ok(()));
+    assert!(TabsConfig(Vec::new()).validate().is_err());
+
+    let c: Config = json5::from_str(
+      r#"{ "tabs": [{ "label": "List", "action": "Engine.Refresh" }, { "label": "List", "action": "Engine.Quit" }] }"#,
+    )?;
+    assert_eq!(c.tabs.validate(), Err("Tab label `List` is used more than once".to_string()));
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_key_is_an_error() {
+    assert!(json5::from_str::<Config>(r#"{ "keybindings": { "Home": { "<NotAKey>": "Engine.Quit" } } }"#).is_err());
+  }
+
+  #[test]
+  fn test_reload_styles() -> Result<()> {
+    let config_dir =
+      std::env::temp_dir().join(format!("{}-reload-styles-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    std::fs::create_dir_all(&config_dir)?;
+    let config_file = config_dir.join("config.json5");
+    let mut c =
+      Config { config: AppConfig { _config_dir: config_dir.clone(), ..Default::default() }, ..Default::default() };
+
+    std::fs::write(&config_file, r#"{ "styles": { "Home": { "accent": "red" } } }"#)?;
+    c.reload_styles()?;
+    assert_eq!(c.styles.get(&Mode::Home).unwrap().get("accent").unwrap().fg, Some(Color::Indexed(1)));
+
+    std::fs::write(&config_file, r#"{ "styles": { "Home": { "accent": "blue" } } }"#)?;
+    c.reload_styles()?;
+    assert_eq!(c.styles.get(&Mode::Home).unwrap().get("accent").unwrap().fg, Some(Color::Indexed(4)));
+    // The built-in styles the file leaves out are still there.
+    let defaults = json5::from_str::<Config>(CONFIG)?.styles;
+    assert_eq!(c.styles[&Mode::Home].get("focused_border"), defaults[&Mode::Home].get("focused_border"));
+    assert!(c.styles[&Mode::Home].contains_key("focused_border"));
+
+    // A malformed file leaves the active styles alone.
+    std::fs::write(&config_file, r#"{ "styles": { "Home": "#)?;
+    assert!(c.reload_styles().is_err());
+    assert_eq!(c.styles.get(&Mode::Home).unwrap().get("accent").unwrap().fg, Some(Color::Indexed(4)));
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_default_config_binds_mode_switcher_everywhere() {
+    let c: Config = json5::from_str(CONFIG).unwrap();
+    for mode in [Mode::Home, Mode::MainMenu] {
+      assert!(c.binding_for(mode, &EngineAction::ToggleShowModeSwitcher.into()).is_some(), "{mode:?}");
+    }
+  }
+
+  #[test]
+  fn test_default_bindings_are_described() {
+    let c: Config = json5::from_str(CONFIG).unwrap();
+    let keymaps = c.keybindings.values().chain([&c.keybindings.global]);
+    for action in keymaps.flat_map(|keymap| keymap.values()) {
+      if !matches!(action, Action::Engine(EngineAction::Batch(_))) {
+        assert_ne!(action.description(), action.to_string());
+      }
+    }
+  }
+
+  #[test]
+  fn test_bindings_sort_by_action_then_keys() {
+    let keymap: Keymap = [
+      ("<q>", EngineAction::Quit.into()),
+      ("<down>", HomeAction::NavigateList(ListNavDirection::Down, 1).into()),
+      ("<ctrl-c>", EngineAction::Quit.into()),
+      ("<j>", HomeAction::NavigateList(ListNavDirection::Down, 1).into()),
+      ("<d>", HomeAction::DeleteTodo.into()),
+      ("<g><d>", HomeAction::DeleteTodo.into()),
+      ("<l>", EngineAction::ToggleShowHelp.into()),
+    ]
+    .into_iter()
+    .map(|(keys, action): (&str, Action)| (parse_key_sequence(keys).unwrap(), action))
+    .collect();
+
+    let order: Vec<_> = sorted_bindings(&keymap).into_iter().map(|(keys, action)| format!("{keys} {action}")).collect();
+    assert_eq!(
+      order,
+      [
+        "ctrl-c Engine.Quit",
+        "q Engine.Quit",
+        "l Engine.ToggleShowHelp",
+        "d Home.DeleteTodo",
+        "g d Home.DeleteTodo",
+        "down Home.NavigateList(Down)",
+        "j Home.NavigateList(Down)",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_binding_for() {
+    let mut c = Config::default();
+    c.keybindings.insert(
+      Mode::Home,
+      HashMap::from([
+        (parse_key_sequence("<ctrl-d>").unwrap(), EngineAction::Quit.into()),
+        (parse_key_sequence("<q>").unwrap(), EngineAction::Quit.into()),
+      ]),
+    );
+
+    assert_eq!(c.binding_for(Mode::Home, &EngineAction::Quit.into()), Some("q".to_string()));
+    assert_eq!(c.binding_for(Mode::Home, &EngineAction::Suspend.into()), None);
+    assert_eq!(c.binding_for(Mode::MainMenu, &EngineAction::Quit.into()), None);
+  }
+
   #[test]
   fn test_simple_keys() {
-    assert_eq!(parse_key_event("a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
+    assert_eq!(string_to_key_event("a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
 
-    assert_eq!(parse_key_event("enter").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+    assert_eq!(string_to_key_event("enter").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
 
-    assert_eq!(parse_key_event("esc").unwrap(), KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+    assert_eq!(string_to_key_event("esc").unwrap(), KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
   }
 
   #[test]
   fn test_with_modifiers() {
-    assert_eq!(parse_key_event("ctrl-a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+    assert_eq!(string_to_key_event("ctrl-a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
 
-    assert_eq!(parse_key_event("alt-enter").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
+    assert_eq!(string_to_key_event("alt-enter").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
 
-    assert_eq!(parse_key_event("shift-esc").unwrap(), KeyEvent::new(KeyCode::Esc, KeyModifiers::SHIFT));
+    assert_eq!(string_to_key_event("shift-esc").unwrap(), KeyEvent::new(KeyCode::Esc, KeyModifiers::SHIFT));
   }
 
   #[test]
   fn test_multiple_modifiers() {
     assert_eq!(
-      parse_key_event("ctrl-alt-a").unwrap(),
+      string_to_key_event("ctrl-alt-a").unwrap(),
       KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL | KeyModifiers::ALT)
     );
 
     assert_eq!(
-      parse_key_event("ctrl-shift-enter").unwrap(),
+      string_to_key_event("ctrl-shift-enter").unwrap(),
       KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::SHIFT)
     );
   }
@@ -490,16 +2014,91 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_key_strings_round_trip() {
+    let keys = [
+      KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+      KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT),
+      KeyEvent::new(KeyCode::Char('-'), KeyModifiers::ALT),
+      KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL | KeyModifiers::SHIFT | KeyModifiers::ALT),
+      KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT),
+      KeyEvent::new(KeyCode::Backspace, KeyModifiers::ALT),
+      KeyEvent::new(KeyCode::Left, KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::PageDown, KeyModifiers::CONTROL),
+      KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::F(12), KeyModifiers::SHIFT),
+      KeyEvent::new(KeyCode::F(5), KeyModifiers::CONTROL | KeyModifiers::ALT),
+      KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('Ж'), KeyModifiers::SHIFT),
+      KeyEvent::new(KeyCode::Char('ß'), KeyModifiers::SHIFT),
+      KeyEvent::new(KeyCode::Char('日'), KeyModifiers::ALT),
+    ];
+    for key in keys {
+      let string = key_event_to_string(&key);
+      assert_eq!(string_to_key_event(&string), Ok(key), "{string}");
+    }
+    assert_eq!(parse_key_sequence(&key_sequence_to_string(&keys)), Ok(keys.to_vec()));
+    assert_eq!(key_event_to_string(&KeyEvent::new(KeyCode::F(7), KeyModifiers::CONTROL)), "ctrl-f7");
+    assert!(string_to_key_event("f0").is_err());
+    assert!(string_to_key_event("f25").is_err());
+    assert_eq!(string_to_key_event("f").unwrap(), KeyEvent::new(KeyCode::Char('f'), KeyModifiers::empty()));
+  }
+
   #[test]
   fn test_invalid_keys() {
-    assert!(parse_key_event("invalid-key").is_err());
-    assert!(parse_key_event("ctrl-invalid-key").is_err());
+    assert!(string_to_key_event("invalid-key").is_err());
+    assert_eq!(string_to_key_event("ctrl-Invalid-key"), Err("Unknown key `Invalid-key`".to_string()));
+    assert_eq!(parse_key_sequence("g <lead>gg"), Err("Unknown key `lead` in `g <lead>gg`".to_string()));
+    assert_eq!(parse_key_sequence("<ctrl-x"), Err("Unclosed `<` in `<ctrl-x`".to_string()));
+    assert_eq!(parse_key_sequence("  "), Err("No keys in `  `".to_string()));
+    assert_eq!(parse_key_sequence("<>"), Err("Unknown key `` in `<>`".to_string()));
+  }
+
+  #[test]
+  fn test_human_readable_key_sequences() {
+    let key = |code, modifiers| KeyEvent::new(code, modifiers);
+    let ctrl_shift_p = key(KeyCode::Char('P'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+    assert_eq!(parse_key_sequence("ctrl-shift-p"), Ok(vec![ctrl_shift_p]));
+    assert_eq!(parse_key_sequence("S-C-p"), Ok(vec![ctrl_shift_p]));
+    assert_eq!(parse_key_sequence("C-A-x"), parse_key_sequence("<alt-ctrl-x>"));
+
+    let g = key(KeyCode::Char('g'), KeyModifiers::NONE);
+    assert_eq!(parse_key_sequence("g g"), Ok(vec![g, g]));
+    assert_eq!(parse_key_sequence("<g><g>"), Ok(vec![g, g]));
+    assert_eq!(parse_key_sequence("<ctrl-w>gg"), Ok(vec![key(KeyCode::Char('w'), KeyModifiers::CONTROL), g, g]));
+    assert_eq!(
+      parse_key_sequence(" esc\tenter  f5 space tab backspace "),
+      Ok(vec![
+        key(KeyCode::Esc, KeyModifiers::NONE),
+        key(KeyCode::Enter, KeyModifiers::NONE),
+        key(KeyCode::F(5), KeyModifiers::NONE),
+        key(KeyCode::Char(' '), KeyModifiers::NONE),
+        key(KeyCode::Tab, KeyModifiers::NONE),
+        key(KeyCode::Backspace, KeyModifiers::NONE),
+      ])
+    );
+    // A modifier prefix with nothing after it is the key itself.
+    assert_eq!(parse_key_sequence("ctrl-a-"), Err("Unknown key `a-` in `ctrl-a-`".to_string()));
+    assert_eq!(
+      parse_key_sequence("c -"),
+      Ok(vec![key(KeyCode::Char('c'), KeyModifiers::NONE), key(KeyCode::Char('-'), KeyModifiers::NONE)])
+    );
+  }
+
+  #[test]
+  fn test_bad_key_is_named_in_the_config_error() {
+    let error =
+      json5::from_str::<Config>(r#"{ "keybindings": { "Home": { "<Ctrl-Nope>": "Engine.Quit" } } }"#).unwrap_err();
+    assert!(error.to_string().contains("Unknown key `Nope` in `<Ctrl-Nope>`"), "{error}");
   }
 
   #[test]
   fn test_case_insensitivity() {
-    assert_eq!(parse_key_event("CTRL-a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+    assert_eq!(string_to_key_event("CTRL-a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
 
-    assert_eq!(parse_key_event("AlT-eNtEr").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
+    assert_eq!(string_to_key_event("AlT-eNtEr").unwrap(), KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT));
   }
 }
@@ -0,0 +1,104 @@
+//! Notices edits to the config files while the app is running, so they can be picked up without a restart.
+
+use std::{
+  path::PathBuf,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{
+  actions::{Action, EngineAction},
+  config::config_file_stamps,
+};
+
+/// Polls the config files' modification times and sends [`EngineAction::ReloadConfig`] whenever they change.
+///
+/// The polling is skipped while the watcher is paused, and stops when the watcher is dropped.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+  task: JoinHandle<()>,
+  paused: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+  pub fn spawn(config_dir: PathBuf, interval: Duration, action_tx: UnboundedSender<Action>) -> Self {
+    let paused = Arc::new(AtomicBool::new(false));
+    let task_paused = paused.clone();
+    // Read up front rather than in the task, so edits made before it first runs still count as changes.
+    let mut stamps = config_file_stamps(&config_dir);
+    let task = tokio::spawn(async move {
+      let mut interval = tokio::time::interval(interval);
+      loop {
+        interval.tick().await;
+        if task_paused.load(Ordering::Relaxed) {
+          continue;
+        }
+        let latest = config_file_stamps(&config_dir);
+        if latest != stamps {
+          stamps = latest;
+          log::info!("Config in {} changed, reloading", config_dir.display());
+          if action_tx.send(EngineAction::ReloadConfig.into()).is_err() {
+            break;
+          }
+        }
+      }
+    });
+    Self { task, paused }
+  }
+
+  /// Stops checking the config files until [`Self::resume`] is called.
+  pub fn pause(&self) {
+    self.paused.store(true, Ordering::Relaxed);
+  }
+
+  /// Starts checking the config files again. Edits made while paused are picked up on the next poll.
+  pub fn resume(&self) {
+    self.paused.store(false, Ordering::Relaxed);
+  }
+}
+
+impl Drop for ConfigWatcher {
+  fn drop(&mut self) {
+    self.task.abort();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::SystemTime;
+
+  use color_eyre::eyre::Result;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_sends_reload_when_config_changes() -> Result<()> {
+    let config_dir = std::env::temp_dir().join(format!("ratatui-experimentation-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&config_dir)?;
+    let config_file = config_dir.join("config.json5");
+    std::fs::write(&config_file, "{}")?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let _watcher = ConfigWatcher::spawn(config_dir.clone(), Duration::from_millis(10), tx);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert!(rx.try_recv().is_err());
+
+    std::fs::write(&config_file, r#"{ "tick_rate": 2 }"#)?;
+    // Pushed forward explicitly, in case the filesystem's timestamps are too coarse to tell the writes apart.
+    std::fs::File::options()
+      .write(true)
+      .open(&config_file)?
+      .set_modified(SystemTime::now() + Duration::from_secs(5))?;
+    let action = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await?;
+    assert_eq!(action, Some(EngineAction::ReloadConfig.into()));
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+}
@@ -0,0 +1,201 @@
+//! Resolves short natural-language phrases such as `@tomorrow` or `@in 3 days` into due dates.
+//!
+//! The grammar, matched case-insensitively on whitespace-separated words, is:
+//!
+//! ```text
+//! phrase  := "today" | "tomorrow" | weekday | "next" weekday | "in" N unit
+//! unit    := "day" | "days" | "week" | "weeks"
+//! weekday := "monday" | "mon" | ... | "sunday" | "sun"
+//! ```
+//!
+//! A bare weekday is the soonest such day after today (so `@friday` on a Friday is a week away), and `next <weekday>`
+//! is the one a week after that. All words come from a [`Grammar`], so other languages can be added alongside
+//! [`ENGLISH`] without touching the parser.
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+/// Marks the start of a due-date phrase in todo input.
+pub const DUE_DATE_MARKER: char = '@';
+
+/// The most words a phrase can span, e.g. `in 3 days`.
+const MAX_PHRASE_WORDS: usize = 3;
+
+/// The vocabulary of the due-date grammar. Every word must be lowercase.
+pub struct Grammar {
+  pub today: &'static [&'static str],
+  pub tomorrow: &'static [&'static str],
+  pub next: &'static [&'static str],
+  pub in_: &'static [&'static str],
+  pub days: &'static [&'static str],
+  pub weeks: &'static [&'static str],
+  pub weekdays: [(Weekday, &'static [&'static str]); 7],
+}
+
+pub const ENGLISH: Grammar = Grammar {
+  today: &["today"],
+  tomorrow: &["tomorrow"],
+  next: &["next"],
+  in_: &["in"],
+  days: &["day", "days"],
+  weeks: &["week", "weeks"],
+  weekdays: [
+    (Weekday::Mon, &["monday", "mon"]),
+    (Weekday::Tue, &["tuesday", "tue"]),
+    (Weekday::Wed, &["wednesday", "wed"]),
+    (Weekday::Thu, &["thursday", "thu"]),
+    (Weekday::Fri, &["friday", "fri"]),
+    (Weekday::Sat, &["saturday", "sat"]),
+    (Weekday::Sun, &["sunday", "sun"]),
+  ],
+};
+
+impl Grammar {
+  /// Resolves a phrase (without the leading marker) relative to `today`, or `None` if it isn't part of the grammar.
+  pub fn parse(&self, phrase: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let words: Vec<String> = phrase.split_whitespace().map(str::to_lowercase).collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    match words.as_slice() {
+      [w] if self.today.contains(w) => Some(today),
+      [w] if self.tomorrow.contains(w) => today.checked_add_days(Days::new(1)),
+      [w] => self.weekday(w).and_then(|day| upcoming(today, day)),
+      [next, w] if self.next.contains(next) => {
+        self.weekday(w).and_then(|day| upcoming(today, day)).and_then(|date| date.checked_add_days(Days::new(7)))
+      },
+      [in_, n, unit] if self.in_.contains(in_) => {
+        let n: u64 = n.parse().ok()?;
+        let days = if self.days.contains(unit) {
+          n
+        } else if self.weeks.contains(unit) {
+          n.checked_mul(7)?
+        } else {
+          return None;
+        };
+        today.checked_add_days(Days::new(days))
+      },
+      _ => None,
+    }
+  }
+
+  fn weekday(&self, word: &str) -> Option<Weekday> {
+    self.weekdays.iter().find(|(_, names)| names.contains(&word)).map(|(day, _)| *day)
+  }
+
+  /// Pulls the first resolvable `@phrase` out of `input`, returning the remaining text and the date it resolved to.
+  ///
+  /// The longest phrase that resolves wins, so `@in 3 days` isn't cut short. Anything that doesn't resolve, including
+  /// markers in the middle of a word like `me@example.com`, is left in the text as typed.
+  pub fn extract(&self, input: &str, today: NaiveDate) -> (String, Option<NaiveDate>) {
+    let words: Vec<&str> = input.split_whitespace().collect();
+
+    for start in 0..words.len() {
+      let Some(first) = words[start].strip_prefix(DUE_DATE_MARKER) else {
+        continue;
+      };
+      for len in (1..=MAX_PHRASE_WORDS.min(words.len() - start)).rev() {
+        let phrase = std::iter::once(first).chain(words[start + 1..start + len].iter().copied()).collect::<Vec<_>>();
+        if let Some(date) = self.parse(&phrase.join(" "), today) {
+          let rest: Vec<&str> = words[..start].iter().chain(&words[start + len..]).copied().collect();
+          return (rest.join(" "), Some(date));
+        }
+      }
+    }
+
+    (input.to_string(), None)
+  }
+}
+
+/// The first `day` strictly after `today`.
+fn upcoming(today: NaiveDate, day: Weekday) -> Option<NaiveDate> {
+  let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+  today.checked_add_days(Days::new(if ahead == 0 { 7 } else { ahead.into() }))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  /// A Wednesday.
+  fn now() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()
+  }
+
+  fn date(day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(2024, 1, day)
+  }
+
+  #[test]
+  fn test_today_and_tomorrow() {
+    assert_eq!(ENGLISH.parse("today", now()), date(10));
+    assert_eq!(ENGLISH.parse("tomorrow", now()), date(11));
+  }
+
+  #[test]
+  fn test_weekdays() {
+    assert_eq!(ENGLISH.parse("thursday", now()), date(11));
+    assert_eq!(ENGLISH.parse("fri", now()), date(12));
+    assert_eq!(ENGLISH.parse("monday", now()), date(15));
+    // The current weekday means a week from now, never today.
+    assert_eq!(ENGLISH.parse("wednesday", now()), date(17));
+  }
+
+  #[test]
+  fn test_next_weekday() {
+    assert_eq!(ENGLISH.parse("next friday", now()), date(19));
+    assert_eq!(ENGLISH.parse("next wed", now()), date(24));
+  }
+
+  #[test]
+  fn test_in_days_and_weeks() {
+    assert_eq!(ENGLISH.parse("in 3 days", now()), date(13));
+    assert_eq!(ENGLISH.parse("in 1 day", now()), date(11));
+    assert_eq!(ENGLISH.parse("in 0 days", now()), date(10));
+    assert_eq!(ENGLISH.parse("in 2 weeks", now()), date(24));
+    assert_eq!(ENGLISH.parse("in 3 weeks", now()), NaiveDate::from_ymd_opt(2024, 1, 31));
+  }
+
+  #[test]
+  fn test_case_insensitive() {
+    assert_eq!(ENGLISH.parse("ToMoRRow", now()), date(11));
+    assert_eq!(ENGLISH.parse("NEXT Friday", now()), date(19));
+    assert_eq!(ENGLISH.parse("In 3 DAYS", now()), date(13));
+  }
+
+  #[test]
+  fn test_unrecognized_phrases() {
+    for phrase in
+      ["", "yesterday", "next", "next week", "in 3", "in three days", "in -1 days", "in 3 months", "fridays"]
+    {
+      assert_eq!(ENGLISH.parse(phrase, now()), None, "{phrase:?}");
+    }
+    assert_eq!(ENGLISH.parse("in 99999999999999999999 days", now()), None);
+  }
+
+  #[test]
+  fn test_extract_removes_phrase() {
+    assert_eq!(ENGLISH.extract("buy milk @tomorrow", now()), ("buy milk".to_string(), date(11)));
+    assert_eq!(ENGLISH.extract("@next friday call mum", now()), ("call mum".to_string(), date(19)));
+    assert_eq!(ENGLISH.extract("pay rent @in 3 days please", now()), ("pay rent please".to_string(), date(13)));
+  }
+
+  #[test]
+  fn test_extract_prefers_longest_phrase() {
+    // "@in 1 day" must not stop at a shorter match, and "@friday" must not swallow the following word.
+    assert_eq!(ENGLISH.extract("@in 1 day", now()), (String::new(), date(11)));
+    assert_eq!(ENGLISH.extract("@friday next", now()), ("next".to_string(), date(12)));
+  }
+
+  #[test]
+  fn test_extract_leaves_unrecognized_text() {
+    for input in ["email me@example.com", "meet @noon", "@next", "tomorrow without a marker"] {
+      assert_eq!(ENGLISH.extract(input, now()), (input.to_string(), None));
+    }
+  }
+
+  #[test]
+  fn test_extract_uses_first_resolvable_phrase() {
+    assert_eq!(ENGLISH.extract("@someday @today @tomorrow", now()), ("@someday @tomorrow".to_string(), date(10)));
+  }
+}
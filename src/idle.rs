@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+use crate::actions::{Action, EngineAction};
+
+/// Tracks user activity and decides when the app should enter or leave its idle state.
+///
+/// The tracker is fed timestamps rather than reading the clock itself so the transitions can be driven
+/// deterministically.
+#[derive(Debug, Clone)]
+pub struct IdleTracker {
+  timeout: Duration,
+  last_activity: Instant,
+  idle: bool,
+}
+
+impl IdleTracker {
+  pub fn new(timeout: Duration, now: Instant) -> Self {
+    Self { timeout, last_activity: now, idle: false }
+  }
+
+  pub fn is_idle(&self) -> bool {
+    self.idle
+  }
+
+  /// Records user input, returning [`EngineAction::IdleEnd`] if this wakes the app up.
+  pub fn record_activity(&mut self, now: Instant) -> Option<Action> {
+    self.last_activity = now;
+    if self.idle {
+      self.idle = false;
+      Some(EngineAction::IdleEnd.into())
+    } else {
+      None
+    }
+  }
+
+  /// Checks the time since the last input, returning [`EngineAction::IdleStart`] once the timeout is crossed.
+  pub fn check(&mut self, now: Instant) -> Option<Action> {
+    if !self.idle && now.saturating_duration_since(self.last_activity) >= self.timeout {
+      self.idle = true;
+      Some(EngineAction::IdleStart.into())
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::SystemTime;
+
+  use color_eyre::eyre::Result;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::config_watcher::ConfigWatcher;
+
+  /// Applies an idle transition to the watcher the way the app's event loop does.
+  fn apply(watcher: &ConfigWatcher, transition: Option<Action>) {
+    match transition {
+      Some(Action::Engine(EngineAction::IdleStart)) => watcher.pause(),
+      Some(Action::Engine(EngineAction::IdleEnd)) => watcher.resume(),
+      _ => {},
+    }
+  }
+
+  #[test]
+  fn test_ticks_cross_idle_threshold_once() {
+    let start = Instant::now();
+    let mut tracker = IdleTracker::new(Duration::from_secs(10), start);
+
+    let transitions: Vec<(u64, Action)> = (1..=30)
+      .filter_map(|secs| tracker.check(start + Duration::from_secs(secs)).map(|action| (secs, action)))
+      .collect();
+
+    assert_eq!(transitions, vec![(10, EngineAction::IdleStart.into())]);
+    assert!(tracker.is_idle());
+  }
+
+  #[test]
+  fn test_activity_ends_idle_and_restarts_timer() {
+    let start = Instant::now();
+    let mut tracker = IdleTracker::new(Duration::from_secs(10), start);
+
+    assert_eq!(tracker.record_activity(start + Duration::from_secs(5)), None);
+    assert_eq!(tracker.check(start + Duration::from_secs(12)), None);
+    assert_eq!(tracker.check(start + Duration::from_secs(15)), Some(EngineAction::IdleStart.into()));
+
+    assert_eq!(tracker.record_activity(start + Duration::from_secs(20)), Some(EngineAction::IdleEnd.into()));
+    assert!(!tracker.is_idle());
+    assert_eq!(tracker.check(start + Duration::from_secs(29)), None);
+    assert_eq!(tracker.check(start + Duration::from_secs(30)), Some(EngineAction::IdleStart.into()));
+  }
+
+  #[tokio::test]
+  async fn test_config_polling_pauses_while_idle() -> Result<()> {
+    let config_dir = std::env::temp_dir().join(format!("{}-idle-watch-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    std::fs::create_dir_all(&config_dir)?;
+    let config_file = config_dir.join("config.json5");
+    std::fs::write(&config_file, "{}")?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = ConfigWatcher::spawn(config_dir.clone(), Duration::from_millis(10), tx);
+
+    let start = Instant::now();
+    let mut tracker = IdleTracker::new(Duration::from_secs(10), start);
+    for secs in 1..=15 {
+      apply(&watcher, tracker.check(start + Duration::from_secs(secs)));
+    }
+    assert!(tracker.is_idle());
+
+    std::fs::write(&config_file, r#"{ "tick_rate": 2 }"#)?;
+    std::fs::File::options()
+      .write(true)
+      .open(&config_file)?
+      .set_modified(SystemTime::now() + Duration::from_secs(5))?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(rx.try_recv().is_err());
+
+    apply(&watcher, tracker.record_activity(start + Duration::from_secs(16)));
+    let action = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await?;
+    assert_eq!(action, Some(EngineAction::ReloadConfig.into()));
+
+    std::fs::remove_dir_all(config_dir)?;
+    Ok(())
+  }
+}
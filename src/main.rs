@@ -3,20 +3,36 @@
 #![allow(unused_variables)]
 
 // ANCHOR: all
+pub mod action_log;
 pub mod actions;
 pub mod app;
+pub mod cheatsheet;
 pub mod cli;
 pub mod components;
 pub mod config;
+pub mod config_watcher;
+pub mod due_date;
+pub mod idle;
+pub mod operations;
+pub mod persistence;
+pub mod recording;
+pub mod resources;
+pub mod storage;
+pub mod tasks;
+pub mod todos;
 pub mod tui;
 pub mod utils;
 
-use clap::Parser;
+use chrono::Local;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use cli::Cli;
 use color_eyre::eyre::Result;
 
 use crate::{
   app::App,
+  config::Config,
+  recording::{Recorder, Replay},
+  todos::default_todos_path,
   utils::{initialize_logging, initialize_panic_handler, version},
 };
 
@@ -26,7 +42,38 @@ async fn tokio_main() -> Result<()> {
   initialize_panic_handler()?;
 
   let args = Cli::parse();
-  let mut app = App::new(args.tick_rate, args.frame_rate)?;
+  if let (true, Some(command)) = (args.no_tui, &args.command) {
+    let report = operations::run(command, &default_todos_path(), std::io::stdin().lock(), Local::now().date_naive());
+    println!("{}", report.render(args.output)?);
+    std::process::exit(report.exit_code());
+  }
+  if let Some(format) = args.export_keys {
+    let cheatsheet = cheatsheet::render(&Config::new()?.keybindings, format)?;
+    match &args.export_keys_to {
+      Some(path) => std::fs::write(path, cheatsheet)?,
+      None => print!("{cheatsheet}"),
+    }
+    return Ok(());
+  }
+  match (args.no_tui, &args.command) {
+    (true, None) => Cli::command().error(ErrorKind::MissingSubcommand, "--no-tui needs an operation to run").exit(),
+    (false, Some(_)) => Cli::command().error(ErrorKind::MissingRequiredArgument, "operations need --no-tui").exit(),
+    _ => {},
+  }
+
+  let mut app = App::new(args.include_content)?;
+  if let Some(tick_rate) = args.tick_rate {
+    app.tick_rate = tick_rate;
+  }
+  if let Some(frame_rate) = args.frame_rate {
+    app.frame_rate = frame_rate;
+  }
+  if let Some(path) = &args.record {
+    app.recorder = Some(Recorder::create(path)?);
+  }
+  if let Some(path) = &args.replay {
+    app.replay = Some(Replay::load(path, args.replay_fast)?);
+  }
   app.run().await?;
 
   Ok(())
@@ -0,0 +1,256 @@
+//! Operations that run against the todo store and config without a terminal, for use from scripts.
+
+use std::{
+  io::BufRead,
+  path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::{
+  cheatsheet::{self, Section},
+  cli::{Command, OutputFormat},
+  config::{Config, Theme},
+  persistence::{load_todos, save_todos},
+  todos::TodoStore,
+};
+
+/// What an operation did, printed as its summary.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+  pub operation: String,
+  pub items_added: usize,
+  pub files_written: Vec<PathBuf>,
+  pub warnings: Vec<String>,
+  pub error: Option<String>,
+  /// The effective config, printed by `dump-config`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub config: Option<ConfigDump>,
+}
+
+/// The parts of the effective config that `dump-config` prints.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigDump {
+  /// The global bindings, then each mode's own, as the cheatsheet lists them.
+  pub keybindings: Vec<Section>,
+  pub theme: Theme,
+}
+
+impl Report {
+  fn new(operation: &str) -> Self {
+    Self { operation: operation.to_string(), ..Self::default() }
+  }
+
+  /// 0 on success, 1 if the operation finished with warnings and 2 if it failed.
+  pub fn exit_code(&self) -> i32 {
+    match (&self.error, self.warnings.is_empty()) {
+      (Some(_), _) => 2,
+      (None, false) => 1,
+      (None, true) => 0,
+    }
+  }
+
+  pub fn render(&self, format: OutputFormat) -> Result<String> {
+    match format {
+      OutputFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+      OutputFormat::Text => {
+        let mut lines = vec![format!("{}: {}", self.operation, if self.error.is_some() { "failed" } else { "done" })];
+        if self.items_added > 0 {
+          lines.push(format!("  items added: {}", self.items_added));
+        }
+        lines.extend(self.files_written.iter().map(|path| format!("  wrote {}", path.display())));
+        if let Some(config) = &self.config {
+          for section in &config.keybindings {
+            lines.extend(section.bindings.iter().map(|row| format!("  {} {}: {}", section.name, row.keys, row.action)));
+          }
+          let theme = serde_json::to_value(config.theme)?;
+          lines.extend(theme.as_object().into_iter().flatten().map(|(slot, value)| match value.as_str() {
+            Some(color) => format!("  theme.{slot}: {color}"),
+            None => format!("  theme.{slot}: {value}"),
+          }));
+        }
+        lines.extend(self.warnings.iter().map(|warning| format!("  warning: {warning}")));
+        lines.extend(self.error.iter().map(|error| format!("  error: {error}")));
+        Ok(lines.join("\n"))
+      },
+    }
+  }
+}
+
+/// Runs `command` against the todos saved at `todos_path`, reading any input from `input`.
+pub fn run(command: &Command, todos_path: &Path, input: impl BufRead, today: NaiveDate) -> Report {
+  let (mut report, result) = match command {
+    Command::Import => with_report("import", |report| import(report, todos_path, input, today)),
+    Command::Export { path } => with_report("export", |report| export(report, todos_path, path)),
+    Command::Check => with_report("check", |report| check(report, todos_path, Config::new().map_err(Into::into))),
+    Command::DumpConfig => with_report("dump-config", |report| dump_config(report, Config::new().map_err(Into::into))),
+  };
+  if let Err(e) = result {
+    report.error = Some(format!("{e:#}"));
+  }
+  report
+}
+
+fn with_report(operation: &str, f: impl FnOnce(&mut Report) -> Result<()>) -> (Report, Result<()>) {
+  let mut report = Report::new(operation);
+  let result = f(&mut report);
+  (report, result)
+}
+
+fn import(report: &mut Report, todos_path: &Path, input: impl BufRead, today: NaiveDate) -> Result<()> {
+  // Refuse to start from an empty list when the file is unreadable, or saving would clobber it.
+  let mut store = TodoStore::new(load_todos(todos_path)?);
+  for (n, line) in input.lines().enumerate() {
+    let line = line?;
+    if line.trim().is_empty() {
+      report.warnings.push(format!("skipped blank line {}", n + 1));
+      continue;
+    }
+    store.add_from_input(line.trim(), today);
+    report.items_added += 1;
+  }
+  store.save(todos_path)?;
+  report.files_written.push(todos_path.to_path_buf());
+  Ok(())
+}
+
+fn export(report: &mut Report, todos_path: &Path, out_path: &Path) -> Result<()> {
+  save_todos(out_path, &load_todos(todos_path)?)?;
+  report.files_written.push(out_path.to_path_buf());
+  Ok(())
+}
+
+fn check(report: &mut Report, todos_path: &Path, config: Result<Config>) -> Result<()> {
+  config?;
+  if let Err(e) = load_todos(todos_path) {
+    report.warnings.push(format!("todos at {} could not be read: {e}", todos_path.display()));
+  }
+  Ok(())
+}
+
+fn dump_config(report: &mut Report, config: Result<Config>) -> Result<()> {
+  let config = config?;
+  report.config = Some(ConfigDump { keybindings: cheatsheet::sections(&config.keybindings), theme: config.theme });
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}-operations-{}-{name}", env!("CARGO_PKG_NAME"), std::process::id()))
+  }
+
+  fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, 10).unwrap()
+  }
+
+  #[test]
+  fn test_import_adds_lines_and_warns_on_blanks() -> Result<()> {
+    let dir = temp_dir("import");
+    let todos_path = dir.join("todos.json");
+
+    let report = run(&Command::Import, &todos_path, "buy milk @tomorrow\n\nwalk dog\n".as_bytes(), today());
+
+    assert_eq!(report.exit_code(), 1);
+    assert_eq!(report.items_added, 2);
+    assert_eq!(report.warnings, vec!["skipped blank line 2".to_string()]);
+    let todos = load_todos(&todos_path)?;
+    assert_eq!(todos.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["buy milk", "walk dog"]);
+    assert_eq!(todos[0].due, NaiveDate::from_ymd_opt(2024, 1, 11));
+
+    let json: serde_json::Value = serde_json::from_str(&report.render(OutputFormat::Json)?)?;
+    assert_eq!(json["operation"], "import");
+    assert_eq!(json["items_added"], 2);
+    assert_eq!(json["files_written"][0], todos_path.to_str().unwrap());
+    assert_eq!(json["error"], serde_json::Value::Null);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_import_refuses_to_overwrite_corrupt_store() -> Result<()> {
+    let dir = temp_dir("corrupt");
+    let todos_path = dir.join("todos.json");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&todos_path, "not json")?;
+
+    let report = run(&Command::Import, &todos_path, "anything\n".as_bytes(), today());
+
+    assert_eq!(report.exit_code(), 2);
+    assert!(report.error.is_some());
+    assert_eq!(std::fs::read_to_string(&todos_path)?, "not json");
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_export_writes_file() -> Result<()> {
+    let dir = temp_dir("export");
+    let todos_path = dir.join("todos.json");
+    let out_path = dir.join("out.json");
+    run(&Command::Import, &todos_path, "one\n".as_bytes(), today());
+
+    let report = run(&Command::Export { path: out_path.clone() }, &todos_path, std::io::empty(), today());
+
+    assert_eq!(report.exit_code(), 0);
+    assert_eq!(report.files_written, vec![out_path.clone()]);
+    assert_eq!(load_todos(&out_path)?, load_todos(&todos_path)?);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_reports_unreadable_todos_and_bad_config() -> Result<()> {
+    let dir = temp_dir("check");
+    let todos_path = dir.join("todos.json");
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&todos_path, "not json")?;
+
+    let mut report = Report::new("check");
+    check(&mut report, &todos_path, Ok(Config::default()))?;
+    assert_eq!(report.exit_code(), 1);
+
+    assert!(check(&mut Report::new("check"), &todos_path, Err(color_eyre::eyre::eyre!("bad config"))).is_err());
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_dump_config_prints_bindings_and_theme() -> Result<()> {
+    let mut config = Config::default();
+    config.keybindings.global =
+      [(crate::config::parse_key_sequence("<g><g>").unwrap(), crate::actions::EngineAction::Quit.into())].into();
+
+    let (report, result) = with_report("dump-config", |report| dump_config(report, Ok(config)));
+    result?;
+    assert_eq!(report.exit_code(), 0);
+
+    let json: serde_json::Value = serde_json::from_str(&report.render(OutputFormat::Json)?)?;
+    assert_eq!(json["operation"], "dump-config");
+    assert_eq!(json["config"]["keybindings"][0]["name"], "Global");
+    assert_eq!(json["config"]["keybindings"][0]["bindings"][0]["keys"], "g g");
+    assert_eq!(json["config"]["keybindings"][0]["bindings"][0]["action"], "Engine.Quit");
+    assert_eq!(json["config"]["theme"]["primary"], "Cyan");
+    assert_eq!(json["config"]["theme"]["monochrome"], false);
+    let text = report.render(OutputFormat::Text)?;
+    assert!(text.contains("  Global g g: Engine.Quit"));
+    assert!(text.contains("  theme.border: LightBlue"));
+    assert!(text.contains("  theme.monochrome: false"));
+
+    let (report, result) =
+      with_report("dump-config", |report| dump_config(report, Err(color_eyre::eyre::eyre!("bad config"))));
+    assert!(result.is_err());
+    assert_eq!(report.config, None);
+    Ok(())
+  }
+}
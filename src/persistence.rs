@@ -0,0 +1,63 @@
+use std::{io::ErrorKind, path::Path};
+
+use color_eyre::eyre::Result;
+
+use crate::todos::Todo;
+
+/// Writes `todos` to `path` as JSON.
+///
+/// The list is written to a sibling temporary file first and then renamed into place, so an interrupted save never
+/// leaves a truncated file behind.
+pub fn save_todos(path: &Path, todos: &[Todo]) -> Result<()> {
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  let tmp_path = path.with_extension("json.tmp");
+  std::fs::write(&tmp_path, serde_json::to_string_pretty(todos)?)?;
+  std::fs::rename(tmp_path, path)?;
+  Ok(())
+}
+
+/// Reads the todos saved at `path`, treating a file that doesn't exist yet as an empty list.
+pub fn load_todos(path: &Path) -> Result<Vec<Todo>> {
+  match std::fs::read_to_string(path) {
+    Ok(contents) => Ok(serde_json::from_str(&contents)?),
+    Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+    Err(e) => Err(e.into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::todos::Priority;
+
+  fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+      .join(format!("{}-persistence-{}", env!("CARGO_PKG_NAME"), std::process::id()))
+      .join(format!("{name}.json"))
+  }
+
+  #[test]
+  fn test_save_load_round_trip() -> Result<()> {
+    let path = temp_path("round-trip");
+    let done = Todo { is_completed: true, priority: Priority::High, ..Todo::new(2, "Done") };
+    let todos = vec![Todo::new(1, "Hello World!"), done];
+
+    save_todos(&path, &todos)?;
+    assert_eq!(load_todos(&path)?, todos);
+
+    std::fs::remove_file(path)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_load_missing_file() -> Result<()> {
+    assert_eq!(load_todos(&temp_path("missing"))?, vec![]);
+    Ok(())
+  }
+}
@@ -0,0 +1,212 @@
+//! Records the actions produced by input to a file, and plays them back later in place of the terminal, so a session
+//! that went wrong can be reproduced.
+//!
+//! A recording is newline-delimited JSON, one [`RecordedAction`] per line. Actions that only mark the passage of time
+//! are left out, since the replaying app generates its own.
+//!
+//! Action traces, turned on with the `action_trace` config setting or its environment variable, are written the same
+//! way and can be played back like recordings. They hold every dispatched action rather than just those produced by
+//! input, with the ones components and the app send in response to others marked as derived. Playback skips those,
+//! since handling the input again produces them again.
+
+use std::{
+  fs::File,
+  io::{BufRead, BufReader, BufWriter, Write},
+  path::Path,
+  time::{Duration, Instant},
+};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::actions::{Action, EngineAction};
+
+/// One line of a recording: an action and how long after the start of the session it happened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedAction {
+  pub elapsed_ms: u64,
+  pub action: Action,
+  /// Whether the action was sent in response to another one rather than produced by input. Only traces have these.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub derived: bool,
+}
+
+/// Whether the replaying app produces `action` itself, so it has no place in a recording.
+pub fn is_regenerated(action: &Action) -> bool {
+  matches!(
+    action,
+    Action::Engine(
+      EngineAction::Tick
+        | EngineAction::Render
+        | EngineAction::Resize(..)
+        | EngineAction::IdleStart
+        | EngineAction::IdleEnd
+    )
+  )
+}
+
+/// Appends actions to a recording file as they happen.
+pub struct Recorder {
+  writer: Box<dyn Write + Send>,
+  started: Instant,
+}
+
+impl Recorder {
+  /// Starts a new recording at `path`, replacing any file already there.
+  pub fn create(path: &Path) -> Result<Self> {
+    let file = File::create(path).wrap_err_with(|| format!("Failed to create recording {}", path.display()))?;
+    Ok(Self::new(BufWriter::new(file), Instant::now()))
+  }
+
+  pub fn new(writer: impl Write + Send + 'static, started: Instant) -> Self {
+    Self { writer: Box::new(writer), started }
+  }
+
+  /// Writes `action` with its time since the recording started, unless it is one that gets regenerated.
+  pub fn record(&mut self, action: &Action, now: Instant) -> Result<()> {
+    self.write(action, false, now)
+  }
+
+  /// Writes `action` like [`Recorder::record`], marked as sent in response to another action so playback skips it.
+  pub fn record_derived(&mut self, action: &Action, now: Instant) -> Result<()> {
+    self.write(action, true, now)
+  }
+
+  fn write(&mut self, action: &Action, derived: bool, now: Instant) -> Result<()> {
+    if is_regenerated(action) {
+      return Ok(());
+    }
+    let entry = RecordedAction {
+      elapsed_ms: now.saturating_duration_since(self.started).as_millis() as u64,
+      action: action.clone(),
+      derived,
+    };
+    serde_json::to_writer(&mut self.writer, &entry)?;
+    self.writer.write_all(b"\n")?;
+    // Flushed every time, so the recording survives the crash it is meant to capture.
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+/// A recording loaded for playback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+  pub actions: Vec<RecordedAction>,
+  /// Whether to send everything at once instead of at the recorded pace.
+  pub fast: bool,
+}
+
+impl Replay {
+  pub fn load(path: &Path, fast: bool) -> Result<Self> {
+    let file = File::open(path).wrap_err_with(|| format!("Failed to open recording {}", path.display()))?;
+    let actions =
+      Self::parse(BufReader::new(file)).wrap_err_with(|| format!("Invalid recording {}", path.display()))?;
+    Ok(Self { actions, fast })
+  }
+
+  /// Reads the recorded actions, skipping blank lines.
+  pub fn parse(reader: impl BufRead) -> Result<Vec<RecordedAction>> {
+    let mut actions = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+      let line = line?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      actions.push(serde_json::from_str(&line).map_err(|e| eyre!("line {}: {e}", i + 1))?);
+    }
+    Ok(actions)
+  }
+
+  /// Sends the recorded actions to `action_tx`, then [`EngineAction::Quit`] once they have all gone out. Derived
+  /// actions are left for the app to produce again.
+  pub fn spawn(self, action_tx: UnboundedSender<Action>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+      let started = tokio::time::Instant::now();
+      for entry in self.actions.into_iter().filter(|entry| !entry.derived) {
+        if !self.fast {
+          tokio::time::sleep_until(started + Duration::from_millis(entry.elapsed_ms)).await;
+        }
+        if action_tx.send(entry.action).is_err() {
+          return;
+        }
+      }
+      log::info!("Replay finished");
+      let _ = action_tx.send(EngineAction::Quit.into());
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::actions::HomeAction;
+
+  /// A writer whose output can still be read after it has been handed to a [`Recorder`].
+  #[derive(Clone, Default)]
+  struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  #[tokio::test]
+  async fn test_recording_replays_the_same_actions() -> Result<()> {
+    let buffer = SharedBuffer::default();
+    let started = Instant::now();
+    let mut recorder = Recorder::new(buffer.clone(), started);
+    recorder.record(&HomeAction::EnterInsert.into(), started)?;
+    recorder.record(&EngineAction::Tick.into(), started + Duration::from_millis(5))?;
+    recorder
+      .record(&HomeAction::CompleteInput("say \"hi\"".to_string()).into(), started + Duration::from_millis(20))?;
+    recorder.record(&EngineAction::Render.into(), started + Duration::from_millis(25))?;
+
+    let recorded = buffer.0.lock().unwrap().clone();
+    let actions = Replay::parse(recorded.as_slice())?;
+    assert_eq!(
+      actions,
+      vec![
+        RecordedAction { elapsed_ms: 0, action: HomeAction::EnterInsert.into(), derived: false },
+        RecordedAction {
+          elapsed_ms: 20,
+          action: HomeAction::CompleteInput("say \"hi\"".to_string()).into(),
+          derived: false
+        },
+      ]
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    Replay { actions, fast: true }.spawn(tx).await?;
+    let mut replayed = Vec::new();
+    while let Ok(action) = rx.try_recv() {
+      replayed.push(action);
+    }
+    assert_eq!(
+      replayed,
+      vec![
+        HomeAction::EnterInsert.into(),
+        HomeAction::CompleteInput("say \"hi\"".to_string()).into(),
+        EngineAction::Quit.into()
+      ]
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_line_is_reported() {
+    let recording = "{\"elapsed_ms\":0,\"action\":\"Engine.Quit\"}\n\n{\"elapsed_ms\":1,\"action\":\"Engine.Nope\"}\n";
+    let error = Replay::parse(recording.as_bytes()).unwrap_err().to_string();
+    assert!(error.starts_with("line 3: Unknown Engine action `Nope`"), "{error}");
+  }
+}
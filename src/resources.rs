@@ -0,0 +1,230 @@
+//! Samples the memory the app is using and how much it is holding on to, for the diagnostics panel and exported action
+//! logs.
+
+use std::{
+  fmt,
+  sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::tasks::TaskSet;
+
+/// Reads the resident memory of the running process.
+pub trait MemoryReader: fmt::Debug + Send + Sync {
+  /// The resident set size in bytes, or `None` where it can't be read on this platform.
+  fn resident_bytes(&self) -> Option<u64>;
+}
+
+/// Reads the resident memory from the operating system: `/proc/self/statm` on Linux and `task_info` on macOS.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ProcessMemory;
+
+impl MemoryReader for ProcessMemory {
+  #[cfg(target_os = "linux")]
+  fn resident_bytes(&self) -> Option<u64> {
+    let page_size = u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).ok()?;
+    resident_from_statm(&std::fs::read_to_string("/proc/self/statm").ok()?, page_size)
+  }
+
+  #[cfg(target_os = "macos")]
+  #[allow(deprecated)]
+  fn resident_bytes(&self) -> Option<u64> {
+    let mut info = std::mem::MaybeUninit::<libc::mach_task_basic_info_data_t>::uninit();
+    let mut count = libc::MACH_TASK_BASIC_INFO_COUNT;
+    let result = unsafe {
+      libc::task_info(
+        libc::mach_task_self(),
+        libc::MACH_TASK_BASIC_INFO,
+        info.as_mut_ptr() as libc::task_info_t,
+        &mut count,
+      )
+    };
+    (result == libc::KERN_SUCCESS).then(|| unsafe { info.assume_init() }.resident_size)
+  }
+
+  #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+  fn resident_bytes(&self) -> Option<u64> {
+    None
+  }
+}
+
+/// The resident size in bytes from the contents of `/proc/self/statm`, whose second field counts resident pages.
+pub fn resident_from_statm(statm: &str, page_size: u64) -> Option<u64> {
+  let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+  pages.checked_mul(page_size)
+}
+
+/// How full one of the buffers a component keeps is. Buffers without a `capacity` grow without bound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Occupancy {
+  pub name: String,
+  pub len: usize,
+  pub capacity: Option<usize>,
+}
+
+impl Occupancy {
+  pub fn new(name: &str, len: usize, capacity: Option<usize>) -> Self {
+    Self { name: name.to_string(), len, capacity }
+  }
+}
+
+/// A snapshot of what the app is using.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ResourceUsage {
+  pub resident_bytes: Option<u64>,
+  /// Background tasks in the app's [`TaskSet`].
+  pub tasks: usize,
+  /// Actions waiting to be handled.
+  pub queued_actions: usize,
+  pub buffers: Vec<Occupancy>,
+}
+
+impl ResourceUsage {
+  /// The rows of the diagnostics panel, as label and value.
+  pub fn rows(&self) -> Vec<(String, String)> {
+    let memory = match self.resident_bytes {
+      Some(bytes) => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+      None => "unavailable".to_string(),
+    };
+    let mut rows = vec![
+      ("memory".to_string(), memory),
+      ("tasks".to_string(), self.tasks.to_string()),
+      ("queued actions".to_string(), self.queued_actions.to_string()),
+    ];
+    rows.extend(self.buffers.iter().map(|buffer| {
+      let value = match buffer.capacity {
+        Some(capacity) => format!("{}/{capacity}", buffer.len),
+        None => buffer.len.to_string(),
+      };
+      (buffer.name.clone(), value)
+    }));
+    rows
+  }
+}
+
+/// Takes a [`ResourceUsage`] snapshot every few ticks.
+///
+/// Reading the memory can block, so it happens on the blocking pool and each snapshot carries the latest reading that
+/// has come back. The snapshot is shared, so exported action logs can include it.
+#[derive(Debug)]
+pub struct ResourceMonitor {
+  reader: Arc<dyn MemoryReader>,
+  resident_bytes: Arc<Mutex<Option<u64>>>,
+  usage: Arc<Mutex<ResourceUsage>>,
+  interval: u32,
+  ticks: u32,
+}
+
+impl ResourceMonitor {
+  /// Creates a monitor that samples on every `interval`th tick.
+  pub fn new(reader: Arc<dyn MemoryReader>, interval: u32) -> Self {
+    Self { reader, resident_bytes: Arc::default(), usage: Arc::default(), interval: interval.max(1), ticks: 0 }
+  }
+
+  /// Counts a tick, returning whether a sample is due. The first tick is always due.
+  pub fn tick(&mut self) -> bool {
+    let due = self.ticks == 0;
+    self.ticks = (self.ticks + 1) % self.interval;
+    due
+  }
+
+  /// Starts reading the memory in `tasks`. The reading shows up in the snapshots taken after it comes back.
+  pub fn refresh_memory(&self, tasks: &TaskSet) {
+    let reader = self.reader.clone();
+    let resident_bytes = self.resident_bytes.clone();
+    tasks.spawn(async move {
+      if let Ok(bytes) = tokio::task::spawn_blocking(move || reader.resident_bytes()).await {
+        *resident_bytes.lock().unwrap_or_else(|e| e.into_inner()) = bytes;
+      }
+    });
+  }
+
+  /// Takes a snapshot from the given counts and the latest memory reading.
+  pub fn sample(&self, tasks: usize, queued_actions: usize, buffers: Vec<Occupancy>) {
+    let resident_bytes = *self.resident_bytes.lock().unwrap_or_else(|e| e.into_inner());
+    *self.usage.lock().unwrap_or_else(|e| e.into_inner()) =
+      ResourceUsage { resident_bytes, tasks, queued_actions, buffers };
+  }
+
+  /// The most recent snapshot.
+  pub fn usage(&self) -> ResourceUsage {
+    self.shared().lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+
+  /// A handle to the snapshot, which follows every later sample.
+  pub fn shared(&self) -> Arc<Mutex<ResourceUsage>> {
+    self.usage.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[derive(Debug)]
+  struct FixedMemory(Option<u64>);
+
+  impl MemoryReader for FixedMemory {
+    fn resident_bytes(&self) -> Option<u64> {
+      self.0
+    }
+  }
+
+  #[test]
+  fn test_resident_from_statm() {
+    assert_eq!(resident_from_statm("5000 1200 300 10 0 900 0\n", 4096), Some(1200 * 4096));
+    assert_eq!(resident_from_statm("5000", 4096), None);
+    assert_eq!(resident_from_statm("", 4096), None);
+  }
+
+  #[test]
+  fn test_samples_are_due_every_interval() {
+    let mut monitor = ResourceMonitor::new(Arc::new(FixedMemory(None)), 3);
+    let due: Vec<bool> = (0..7).map(|_| monitor.tick()).collect();
+    assert_eq!(due, vec![true, false, false, true, false, false, true]);
+  }
+
+  #[tokio::test]
+  async fn test_samples_carry_the_latest_memory_reading() -> color_eyre::eyre::Result<()> {
+    let monitor = ResourceMonitor::new(Arc::new(FixedMemory(Some(3 * 1024 * 1024))), 1);
+    let exported = monitor.shared();
+    monitor.sample(0, 0, Vec::new());
+    assert_eq!(monitor.usage().resident_bytes, None);
+
+    let tasks = TaskSet::new();
+    monitor.refresh_memory(&tasks);
+    tasks.shutdown(Duration::from_secs(5)).await?;
+    monitor.sample(2, 5, vec![Occupancy::new("action history", 40, Some(100))]);
+
+    let usage = exported.lock().unwrap().clone();
+    assert_eq!(usage.resident_bytes, Some(3 * 1024 * 1024));
+    assert_eq!(
+      usage.rows(),
+      vec![
+        ("memory".to_string(), "3.0 MiB".to_string()),
+        ("tasks".to_string(), "2".to_string()),
+        ("queued actions".to_string(), "5".to_string()),
+        ("action history".to_string(), "40/100".to_string()),
+      ]
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_unreadable_memory_is_shown_as_unavailable() {
+    let usage = ResourceUsage { buffers: vec![Occupancy::new("todos", 7, None)], ..Default::default() };
+    assert_eq!(usage.rows()[0], ("memory".to_string(), "unavailable".to_string()));
+    assert_eq!(usage.rows()[3], ("todos".to_string(), "7".to_string()));
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_process_memory_reads_statm() {
+    assert!(ProcessMemory.resident_bytes().is_some_and(|bytes| bytes > 0));
+  }
+}
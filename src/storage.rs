@@ -0,0 +1,92 @@
+//! Checks whether the app's directories can be written to, so it can keep running from a read-only location (a live
+//! CD, a restricted container) instead of failing on every save.
+
+use std::path::{Path, PathBuf};
+
+use crate::utils::{get_config_dir, get_data_dir};
+
+/// Whether files can be created in `dir`, found by writing and removing a probe file.
+pub fn probe_writable(dir: &Path) -> bool {
+  let probe = dir.join(format!(".write-probe-{}", std::process::id()));
+  let writable = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&probe, b"")).is_ok();
+  if writable {
+    let _ = std::fs::remove_file(probe);
+  }
+  writable
+}
+
+/// Which of the app's directories turned out to be read-only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageHealth {
+  read_only: Vec<PathBuf>,
+}
+
+impl StorageHealth {
+  /// Runs `probe` against each of `dirs`, remembering the ones it reports as not writable.
+  pub fn check(dirs: &[PathBuf], probe: impl Fn(&Path) -> bool) -> Self {
+    Self { read_only: dirs.iter().filter(|dir| !probe(dir)).cloned().collect() }
+  }
+
+  /// Probes the data and config directories.
+  pub fn current() -> Self {
+    Self::check(&[get_data_dir(), get_config_dir()], probe_writable)
+  }
+
+  pub fn is_read_only(&self) -> bool {
+    !self.read_only.is_empty()
+  }
+
+  /// The warning to show at startup when anything is read-only.
+  pub fn warning(&self) -> Option<String> {
+    self.is_read_only().then(|| {
+      let dirs: Vec<String> = self.read_only.iter().map(|dir| dir.display().to_string()).collect();
+      format!(
+        "Read-only storage ({}): autosave is off and logs are kept in memory. Save to export elsewhere.",
+        dirs.join(", ")
+      )
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_injected_probe_result() {
+    let dirs = [PathBuf::from("data"), PathBuf::from("config")];
+
+    let health = StorageHealth::check(&dirs, |dir| dir != Path::new("config"));
+    assert!(health.is_read_only());
+    assert!(health.warning().unwrap().contains("(config)"));
+
+    let health = StorageHealth::check(&dirs, |_| true);
+    assert_eq!(health, StorageHealth::default());
+    assert_eq!(health.warning(), None);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_probe_detects_read_only_dir() -> color_eyre::eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("{}-storage-{}", env!("CARGO_PKG_NAME"), std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    assert!(probe_writable(&dir));
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0, "the probe file is cleaned up");
+
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555))?;
+    // Root ignores permission bits, so only non-root users can observe the read-only case.
+    let is_root = unsafe { libc::geteuid() } == 0;
+    let writable = probe_writable(&dir);
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755))?;
+    if !is_root {
+      assert!(!writable);
+    }
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+}
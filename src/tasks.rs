@@ -0,0 +1,86 @@
+//! Tracks the background work components spawn, so the app can wind it down before restoring the terminal.
+
+use std::{
+  future::Future,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use color_eyre::eyre::{eyre, Result};
+use tokio::task::JoinSet;
+
+/// A shared handle to the app's background tasks. Clones all spawn into the same set.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSet {
+  tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl TaskSet {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Spawns `task` on the current runtime and keeps track of it until it finishes or the set is shut down.
+  pub fn spawn<F>(&self, task: F)
+  where
+    F: Future<Output = ()> + Send + 'static,
+  {
+    let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+    // Reap finished tasks so a long session doesn't accumulate their results.
+    while tasks.try_join_next().is_some() {}
+    tasks.spawn(task);
+  }
+
+  /// How many tasks are still tracked, including finished ones that haven't been reaped yet.
+  pub fn len(&self) -> usize {
+    self.tasks.lock().unwrap_or_else(|e| e.into_inner()).len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Gives outstanding tasks up to `grace` to finish, then aborts whatever is left.
+  ///
+  /// Returns an error if any of them panicked.
+  pub async fn shutdown(&self, grace: Duration) -> Result<()> {
+    let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut panicked = 0;
+    let _ = tokio::time::timeout(grace, async {
+      while let Some(result) = tasks.join_next().await {
+        if result.as_ref().is_err_and(|e| e.is_panic()) {
+          panicked += 1;
+        }
+      }
+    })
+    .await;
+    if !tasks.is_empty() {
+      log::warn!("Aborting {} background task(s) that didn't finish in {grace:?}", tasks.len());
+      tasks.shutdown().await;
+    }
+    match panicked {
+      0 => Ok(()),
+      n => Err(eyre!("{n} background task(s) panicked")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_shutdown_reports_panics_and_aborts_stragglers() {
+    let tasks = TaskSet::new();
+    tasks.spawn(async {});
+    tasks.spawn(async { panic!("boom") });
+    tasks.spawn(std::future::pending());
+    assert_eq!(tasks.len(), 3);
+
+    let result = tasks.shutdown(Duration::from_millis(50)).await;
+    assert_eq!(result.unwrap_err().to_string(), "1 background task(s) panicked");
+    assert!(tasks.is_empty());
+  }
+}
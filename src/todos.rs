@@ -0,0 +1,752 @@
+use std::{
+  ops::Range,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use chrono::NaiveDate;
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  due_date::ENGLISH,
+  persistence::{load_todos, save_todos},
+  utils::get_data_dir,
+};
+
+pub const TODOS_FILE: &str = "todos.json";
+
+/// Starts a tag in typed text, as in `buy milk #errands`.
+pub const TAG_MARKER: char = '#';
+
+/// Default location of the persisted todos inside the data directory.
+pub fn default_todos_path() -> PathBuf {
+  get_data_dir().join(TODOS_FILE)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Todo {
+  pub id: u32,
+  pub title: String,
+  pub is_completed: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub due: Option<NaiveDate>,
+  /// Todos saved before priorities existed load as [`Priority::Medium`].
+  #[serde(default)]
+  pub priority: Priority,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<String>,
+}
+
+impl Todo {
+  pub fn new(id: u32, title: impl Into<String>) -> Self {
+    Self { id, title: title.into(), is_completed: false, due: None, priority: Priority::default(), tags: Vec::new() }
+  }
+
+  /// Whether the todo carries `tag`, ignoring case.
+  pub fn has_tag(&self, tag: &str) -> bool {
+    self.tags.iter().any(|own| own.to_lowercase() == tag.to_lowercase())
+  }
+
+  /// Whether the todo is still open after the day it was due.
+  pub fn is_overdue(&self, today: NaiveDate) -> bool {
+    !self.is_completed && self.due.is_some_and(|due| due < today)
+  }
+}
+
+/// Pulls every `#tag` out of `input`, returning the remaining text and the tags in the order they were typed, each
+/// once.
+///
+/// A tag has to start with a letter, so `issue #5` keeps its number, and may go on with letters, digits, `-` and `_`.
+pub fn extract_tags(input: &str) -> (String, Vec<String>) {
+  let is_tag = |tag: &str| {
+    tag.starts_with(char::is_alphabetic) && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+  };
+  let mut tags: Vec<String> = Vec::new();
+  let mut rest = Vec::new();
+  for word in input.split_whitespace() {
+    match word.strip_prefix(TAG_MARKER).filter(|tag| is_tag(tag)) {
+      Some(tag) if tags.iter().any(|own| own == tag) => {},
+      Some(tag) => tags.push(tag.to_string()),
+      None => rest.push(word),
+    }
+  }
+  match tags.is_empty() {
+    true => (input.to_string(), tags),
+    false => (rest.join(" "), tags),
+  }
+}
+
+/// How important a todo is, lowest first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Priority {
+  Low,
+  #[default]
+  Medium,
+  High,
+}
+
+impl Priority {
+  /// The next level up, going from High back round to Low.
+  pub fn next(self) -> Self {
+    match self {
+      Self::Low => Self::Medium,
+      Self::Medium => Self::High,
+      Self::High => Self::Low,
+    }
+  }
+}
+
+/// The order todos are listed in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TodoSort {
+  /// The order they were added in.
+  #[default]
+  Created,
+  /// Soonest due first, then those without a due date.
+  Due,
+  /// Highest priority first.
+  Priority,
+}
+
+impl TodoSort {
+  /// Puts `todos` in this order, keeping todos that compare equal in the order they were added.
+  pub fn sort(self, todos: &mut [Todo]) {
+    match self {
+      Self::Created => todos.sort_by_key(|todo| todo.id),
+      Self::Due => todos.sort_by_key(|todo| (todo.due.is_none(), todo.due, todo.id)),
+      Self::Priority => todos.sort_by_key(|todo| (std::cmp::Reverse(todo.priority), todo.id)),
+    }
+  }
+}
+
+impl FromStr for TodoSort {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Created" => Ok(Self::Created),
+      "Due" => Ok(Self::Due),
+      "Priority" => Ok(Self::Priority),
+      _ => Err(format!("Unknown todo order: {s}")),
+    }
+  }
+}
+
+impl std::fmt::Display for TodoSort {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{self:?}")
+  }
+}
+
+/// What [`TodoStore::trim_titles`] changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrimReport {
+  /// Todos whose title had whitespace trimmed off, by id, with the title they had before.
+  pub trimmed: Vec<(u32, String)>,
+  /// Todos dropped because nothing was left of their title.
+  pub removed: Vec<Todo>,
+}
+
+impl TrimReport {
+  pub fn is_empty(&self) -> bool {
+    self.trimmed.is_empty() && self.removed.is_empty()
+  }
+
+  /// The changes as one op that undoes them all together, or `None` if nothing changed.
+  pub fn into_op(self) -> Option<TodoOp> {
+    let edited = self.trimmed.into_iter().map(|(id, old_title)| TodoOp::Edited { id, old_title });
+    let ops: Vec<_> = edited.chain(self.removed.into_iter().map(TodoOp::Removed)).collect();
+    (!ops.is_empty()).then_some(TodoOp::Compound(ops))
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TodoStore {
+  todos: Vec<Todo>,
+  /// Whether the todos changed since they were loaded or last saved.
+  #[serde(skip)]
+  dirty: bool,
+  /// Whether the file these todos should have come from couldn't be read, so saving must not replace it unseen.
+  #[serde(skip)]
+  load_failed: bool,
+}
+
+impl TodoStore {
+  pub fn new(todos: Vec<Todo>) -> Self {
+    Self { todos, dirty: false, load_failed: false }
+  }
+
+  /// An empty store standing in for todos that couldn't be read.
+  pub fn unreadable() -> Self {
+    Self { load_failed: true, ..Self::default() }
+  }
+
+  /// Loads the todos saved at `path`.
+  ///
+  /// An unreadable or corrupt file is not fatal: it is logged and an empty store is returned instead, with
+  /// [`Self::load_failed`] set.
+  pub fn load(path: &Path) -> Self {
+    match load_todos(path) {
+      Ok(todos) => Self::new(todos),
+      Err(e) => {
+        log::warn!("Could not load todos from {}, starting with an empty list: {e}", path.display());
+        Self::unreadable()
+      },
+    }
+  }
+
+  /// Whether these todos stand in for a file that couldn't be read. Such a store shouldn't be saved automatically,
+  /// and [`Self::save`] moves the file aside before writing over it.
+  pub fn load_failed(&self) -> bool {
+    self.load_failed
+  }
+
+  pub fn save(&mut self, path: &Path) -> Result<()> {
+    if self.load_failed && path.exists() {
+      let backup = path.with_extension("json.bak");
+      std::fs::rename(path, &backup)?;
+      log::warn!("Moved the unreadable {} aside to {}", path.display(), backup.display());
+    }
+    save_todos(path, &self.todos)?;
+    self.dirty = false;
+    self.load_failed = false;
+    Ok(())
+  }
+
+  /// Whether there are changes that haven't been saved anywhere.
+  pub fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+
+  pub fn add(&mut self, title: impl Into<String>) -> &mut Todo {
+    self.dirty = true;
+    let id = self.todos.iter().map(|t| t.id).max().map_or(1, |id| id + 1);
+    self.todos.push(Todo::new(id, title));
+    self.todos.last_mut().unwrap()
+  }
+
+  /// Adds a todo from text the user typed, taking its due date from any `@phrase` in it and its tags from any
+  /// `#tag`s.
+  pub fn add_from_input(&mut self, input: &str, today: NaiveDate) -> &mut Todo {
+    let (title, due) = ENGLISH.extract(input, today);
+    let (title, tags) = extract_tags(&title);
+    let todo = self.add(title);
+    todo.due = due;
+    todo.tags = tags;
+    todo
+  }
+
+  /// Trims leading and trailing whitespace from every title, dropping todos whose title ends up empty.
+  pub fn trim_titles(&mut self) -> TrimReport {
+    let mut report = TrimReport::default();
+    for todo in std::mem::take(&mut self.todos) {
+      let trimmed = todo.title.trim();
+      if trimmed.is_empty() {
+        report.removed.push(todo);
+        continue;
+      }
+      if trimmed.len() != todo.title.len() {
+        let trimmed = trimmed.to_string();
+        report.trimmed.push((todo.id, todo.title.clone()));
+        self.todos.push(Todo { title: trimmed, ..todo });
+      } else {
+        self.todos.push(todo);
+      }
+    }
+    self.dirty |= !report.is_empty();
+    report
+  }
+
+  /// Removes the todo with the given `id`, returning it if there was one.
+  pub fn remove(&mut self, id: u32) -> Option<Todo> {
+    let index = self.todos.iter().position(|todo| todo.id == id)?;
+    self.dirty = true;
+    Some(self.todos.remove(index))
+  }
+
+  /// The position of the todo with the given `id` in the list.
+  pub fn position(&self, id: u32) -> Option<usize> {
+    self.todos.iter().position(|todo| todo.id == id)
+  }
+
+  /// Puts a previously removed todo back at `index`, or at the end if the list has got shorter since.
+  pub fn insert_at(&mut self, index: usize, todo: Todo) {
+    self.todos.insert(index.min(self.todos.len()), todo);
+    self.dirty = true;
+  }
+
+  /// Puts a previously removed todo back, in id order.
+  pub fn insert(&mut self, todo: Todo) {
+    let index = self.todos.iter().position(|other| other.id > todo.id).unwrap_or(self.todos.len());
+    self.todos.insert(index, todo);
+    self.dirty = true;
+  }
+
+  /// Flips whether the todo with the given `id` is completed, returning whether there was one.
+  pub fn toggle_completed(&mut self, id: u32) -> bool {
+    let Some(todo) = self.todos.iter_mut().find(|todo| todo.id == id) else {
+      return false;
+    };
+    todo.is_completed = !todo.is_completed;
+    self.dirty = true;
+    true
+  }
+
+  /// Marks every todo completed, returning the ids of those that weren't already.
+  pub fn complete_all(&mut self) -> Vec<u32> {
+    let ids: Vec<u32> = self
+      .todos
+      .iter_mut()
+      .filter(|todo| !todo.is_completed)
+      .map(|todo| {
+        todo.is_completed = true;
+        todo.id
+      })
+      .collect();
+    self.dirty |= !ids.is_empty();
+    ids
+  }
+
+  /// Removes every completed todo, returning them in the order they were in.
+  pub fn clear_completed(&mut self) -> Vec<Todo> {
+    let (completed, remaining) = std::mem::take(&mut self.todos).into_iter().partition(|todo| todo.is_completed);
+    self.todos = remaining;
+    self.dirty |= !completed.is_empty();
+    completed
+  }
+
+  /// Sets or clears the due date of the todo with the given `id`, returning its old one if there was such a todo.
+  pub fn set_due(&mut self, id: u32, due: Option<NaiveDate>) -> Option<Option<NaiveDate>> {
+    let todo = self.todos.iter_mut().find(|todo| todo.id == id)?;
+    self.dirty |= todo.due != due;
+    Some(std::mem::replace(&mut todo.due, due))
+  }
+
+  /// Sets the priority of the todo with the given `id`, returning its old one if there was such a todo.
+  pub fn set_priority(&mut self, id: u32, priority: Priority) -> Option<Priority> {
+    let todo = self.todos.iter_mut().find(|todo| todo.id == id)?;
+    self.dirty |= todo.priority != priority;
+    Some(std::mem::replace(&mut todo.priority, priority))
+  }
+
+  /// Reorders the todos, which is saved along with them.
+  pub fn sort(&mut self, order: TodoSort) {
+    let before: Vec<u32> = self.todos.iter().map(|todo| todo.id).collect();
+    order.sort(&mut self.todos);
+    self.dirty |= self.todos.iter().map(|todo| todo.id).ne(before);
+  }
+
+  /// Retitles the todo with the given `id`, returning its old title if there was one.
+  pub fn set_title(&mut self, id: u32, title: String) -> Option<String> {
+    let todo = self.todos.iter_mut().find(|todo| todo.id == id)?;
+    self.dirty = true;
+    Some(std::mem::replace(&mut todo.title, title))
+  }
+
+  pub fn todos(&self) -> &[Todo] {
+    &self.todos
+  }
+
+  pub fn len(&self) -> usize {
+    self.todos.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.todos.is_empty()
+  }
+}
+
+/// A change applied to a range of the listed todos, like vim's `d` and `x` after a count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+  Delete,
+  ToggleCompleted,
+}
+
+impl Operator {
+  /// Applies the operator to the todos at `range` of `view`, the ids of the todos a list shows in the order it shows
+  /// them, so todos it leaves out are never touched. The range stops at the end of the view rather than failing.
+  ///
+  /// Returns the change as a single op to undo, or `None` if nothing was in range.
+  pub fn apply(self, store: &mut TodoStore, view: &[u32], range: Range<usize>) -> Option<TodoOp> {
+    let end = range.end.min(view.len());
+    let ids = &view[range.start.min(end)..end];
+    let ops: Vec<TodoOp> = match self {
+      Self::Delete => ids
+        .iter()
+        .filter_map(|&id| {
+          let index = store.position(id)?;
+          store.remove(id).map(|todo| TodoOp::RemovedAt { index, todo })
+        })
+        .collect(),
+      Self::ToggleCompleted => {
+        ids.iter().filter(|&&id| store.toggle_completed(id)).map(|&id| TodoOp::ToggledCompleted(id)).collect()
+      },
+    };
+    (!ops.is_empty()).then_some(TodoOp::Compound(ops))
+  }
+}
+
+/// A change made to a [`TodoStore`], recorded so it can be undone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TodoOp {
+  Added(u32),
+  Removed(Todo),
+  /// Removed from the given position in the list, which undoing puts it back at.
+  RemovedAt {
+    index: usize,
+    todo: Todo,
+  },
+  ToggledCompleted(u32),
+  Edited {
+    id: u32,
+    old_title: String,
+  },
+  DueChanged {
+    id: u32,
+    old_due: Option<NaiveDate>,
+  },
+  PriorityChanged {
+    id: u32,
+    old_priority: Priority,
+  },
+  /// Several changes made together, undone and redone as one.
+  Compound(Vec<TodoOp>),
+}
+
+impl TodoOp {
+  /// Reverses the change in `store`, returning the op that would reverse it again, or `None` if the todo it refers
+  /// to is gone.
+  pub fn revert(self, store: &mut TodoStore) -> Option<TodoOp> {
+    match self {
+      Self::Added(id) => {
+        let index = store.position(id)?;
+        store.remove(id).map(|todo| Self::RemovedAt { index, todo })
+      },
+      Self::Removed(todo) => {
+        let id = todo.id;
+        store.insert(todo);
+        Some(Self::Added(id))
+      },
+      Self::RemovedAt { index, todo } => {
+        let id = todo.id;
+        store.insert_at(index, todo);
+        Some(Self::Added(id))
+      },
+      Self::ToggledCompleted(id) => store.toggle_completed(id).then_some(Self::ToggledCompleted(id)),
+      Self::Edited { id, old_title } => store.set_title(id, old_title).map(|old_title| Self::Edited { id, old_title }),
+      Self::DueChanged { id, old_due } => store.set_due(id, old_due).map(|old_due| Self::DueChanged { id, old_due }),
+      Self::PriorityChanged { id, old_priority } => {
+        store.set_priority(id, old_priority).map(|old_priority| Self::PriorityChanged { id, old_priority })
+      },
+      // Reverted last to first, which leaves the reverses in the order they need to be reverted in again.
+      Self::Compound(ops) => {
+        let reverses: Vec<_> = ops.into_iter().rev().filter_map(|op| op.revert(store)).collect();
+        (!reverses.is_empty()).then_some(Self::Compound(reverses))
+      },
+    }
+  }
+}
+
+/// The todo changes that can be undone, and the undone ones that can be redone.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UndoStack {
+  undo: Vec<TodoOp>,
+  redo: Vec<TodoOp>,
+}
+
+impl UndoStack {
+  /// Records a change that was just made. Anything undone before it can no longer be redone.
+  pub fn record(&mut self, op: TodoOp) {
+    self.undo.push(op);
+    self.redo.clear();
+  }
+
+  /// Reverts the most recent change, returning whether there was one to revert.
+  pub fn undo(&mut self, store: &mut TodoStore) -> bool {
+    Self::shift(&mut self.undo, &mut self.redo, store)
+  }
+
+  /// Reapplies the most recently undone change, returning whether there was one to reapply.
+  pub fn redo(&mut self, store: &mut TodoStore) -> bool {
+    Self::shift(&mut self.redo, &mut self.undo, store)
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo.is_empty()
+  }
+
+  /// Reverts the op on top of `from`, pushing its reverse onto `to`.
+  fn shift(from: &mut Vec<TodoOp>, to: &mut Vec<TodoOp>, store: &mut TodoStore) -> bool {
+    let Some(op) = from.pop() else {
+      return false;
+    };
+    to.extend(op.revert(store));
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir()
+      .join(format!("{}-todos-{}", env!("CARGO_PKG_NAME"), std::process::id()))
+      .join(format!("{name}.json"))
+  }
+
+  #[test]
+  fn test_save_load_round_trip() -> Result<()> {
+    let path = temp_path("round-trip");
+    let mut store = TodoStore::default();
+    store.add("Hello World!");
+    store.add("Due tomorrow").due = NaiveDate::from_ymd_opt(2024, 1, 11);
+    assert!(store.is_dirty());
+
+    store.save(&path)?;
+    assert!(!store.is_dirty());
+    assert_eq!(TodoStore::load(&path), store);
+    store.toggle_completed(99);
+    assert!(!store.is_dirty(), "nothing changed");
+    store.toggle_completed(1);
+    assert!(store.is_dirty());
+    assert!(!path.with_extension("json.tmp").exists());
+
+    std::fs::remove_file(path)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_load_missing_file_is_empty() {
+    assert!(TodoStore::load(&temp_path("missing")).is_empty());
+  }
+
+  #[test]
+  fn test_load_corrupt_file_is_empty() -> Result<()> {
+    let path = temp_path("corrupt");
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(&path, "[{ \"id\": 1, \"title\": ")?;
+
+    let mut store = TodoStore::load(&path);
+    assert!(store.is_empty());
+    assert!(store.load_failed());
+
+    store.add("typed after the failed load");
+    store.save(&path)?;
+    assert!(!store.load_failed());
+    assert_eq!(std::fs::read_to_string(path.with_extension("json.bak"))?, "[{ \"id\": 1, \"title\": ");
+    assert_eq!(TodoStore::load(&path).len(), 1);
+
+    std::fs::remove_file(path.with_extension("json.bak"))?;
+    std::fs::remove_file(path)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_trim_titles() {
+    let mut store = TodoStore::new(
+      ["  padded  ", "clean", "   ", "\ttabbed", ""].iter().zip(1..).map(|(title, id)| Todo::new(id, *title)).collect(),
+    );
+
+    let report = store.trim_titles();
+    assert_eq!(report.trimmed, [(1, "  padded  ".to_string()), (4, "\ttabbed".to_string())]);
+    assert_eq!(report.removed.iter().map(|t| t.id).collect::<Vec<_>>(), [3, 5]);
+    assert_eq!(store.todos().iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["padded", "clean", "tabbed"]);
+    assert_eq!(store.todos().iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2, 4]);
+  }
+
+  #[test]
+  fn test_undo_redo_round_trip() {
+    let mut store = TodoStore::new(vec![Todo::new(1, "milk"), Todo::new(2, "eggs")]);
+    let mut history = UndoStack::default();
+    let titles = |store: &TodoStore| store.todos().iter().map(|t| t.title.clone()).collect::<Vec<_>>();
+
+    let id = store.add("bread").id;
+    history.record(TodoOp::Added(id));
+    history.record(TodoOp::Removed(store.remove(1).unwrap()));
+    store.toggle_completed(2);
+    history.record(TodoOp::ToggledCompleted(2));
+    let old_title = store.set_title(2, "free-range eggs".to_string()).unwrap();
+    history.record(TodoOp::Edited { id: 2, old_title });
+
+    assert!(history.undo(&mut store) && history.undo(&mut store));
+    assert_eq!(titles(&store), ["eggs", "bread"]);
+    assert!(!store.todos()[0].is_completed);
+    assert!(history.undo(&mut store) && history.undo(&mut store));
+    assert_eq!(titles(&store), ["milk", "eggs"]);
+    assert!(!history.undo(&mut store));
+
+    assert!(history.redo(&mut store) && history.redo(&mut store));
+    assert_eq!(titles(&store), ["eggs", "bread"]);
+
+    // A new change after undoing drops what could have been redone.
+    store.add("jam");
+    history.record(TodoOp::Added(4));
+    assert!(!history.can_redo());
+    assert!(!history.redo(&mut store));
+    assert!(history.undo(&mut store));
+    assert_eq!(titles(&store), ["eggs", "bread"]);
+  }
+
+  #[test]
+  fn test_bulk_changes_undo_as_one() {
+    let mut store = TodoStore::new(vec![Todo::new(1, "milk"), Todo::new(2, "eggs"), Todo::new(3, "bread")]);
+    let mut history = UndoStack::default();
+    let completed = |store: &TodoStore| store.todos().iter().map(|t| t.is_completed).collect::<Vec<_>>();
+    store.toggle_completed(2);
+
+    let ids = store.complete_all();
+    assert_eq!(ids, [1, 3]);
+    assert_eq!(completed(&store), [true, true, true]);
+    history.record(TodoOp::Compound(ids.into_iter().map(TodoOp::ToggledCompleted).collect()));
+    assert!(store.complete_all().is_empty());
+
+    assert!(history.undo(&mut store));
+    assert_eq!(completed(&store), [false, true, false]);
+    assert!(history.redo(&mut store));
+    assert_eq!(completed(&store), [true, true, true]);
+
+    store.toggle_completed(1);
+    let removed = store.clear_completed();
+    assert_eq!(removed.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 3]);
+    assert_eq!(store.todos(), [Todo::new(1, "milk")]);
+    history.record(TodoOp::Compound(removed.into_iter().map(TodoOp::Removed).collect()));
+    assert!(store.clear_completed().is_empty());
+
+    assert!(history.undo(&mut store));
+    assert_eq!(store.todos().iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 3]);
+    assert!(history.redo(&mut store));
+    assert_eq!(store.todos(), [Todo::new(1, "milk")]);
+  }
+
+  #[test]
+  fn test_operators_stop_at_the_end_of_the_list() {
+    let mut store = TodoStore::new((1..=4).map(|id| Todo::new(id, format!("todo {id}"))).collect());
+    let mut history = UndoStack::default();
+    let view = [1, 2, 3, 4];
+    let ids = |store: &TodoStore| store.todos().iter().map(|t| t.id).collect::<Vec<_>>();
+
+    history.record(Operator::Delete.apply(&mut store, &view, 2..10).unwrap());
+    assert_eq!(ids(&store), [1, 2]);
+    assert_eq!(Operator::Delete.apply(&mut store, &view, 7..9), None);
+    assert!(history.undo(&mut store));
+    assert_eq!(ids(&store), [1, 2, 3, 4]);
+
+    history.record(Operator::ToggleCompleted.apply(&mut store, &view, 3..5).unwrap());
+    assert_eq!(store.todos().iter().map(|t| t.is_completed).collect::<Vec<_>>(), [false, false, false, true]);
+  }
+
+  #[test]
+  fn test_operators_only_touch_the_todos_in_view() {
+    let mut store = TodoStore::new((1..=5).map(|id| Todo::new(id, format!("todo {id}"))).collect());
+    // A view filtered down to the odd todos.
+    let view = [1, 3, 5];
+
+    let op = Operator::Delete.apply(&mut store, &view, 1..3).unwrap();
+    assert_eq!(store.todos().iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 4]);
+
+    let mut history = UndoStack::default();
+    history.record(op);
+    Operator::ToggleCompleted.apply(&mut store, &view, 0..3);
+    assert_eq!(store.todos().iter().map(|t| t.is_completed).collect::<Vec<_>>(), [true, false, false]);
+    assert!(history.undo(&mut store));
+    assert_eq!(store.todos().iter().map(|t| t.id).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+  }
+
+  #[test]
+  fn test_undoing_a_deletion_restores_positions() {
+    // Sorted out of id order, which putting todos back by id would lose.
+    let mut store = TodoStore::new(vec![Todo::new(3, "c"), Todo::new(1, "a"), Todo::new(2, "b")]);
+    let mut history = UndoStack::default();
+    let ids = |store: &TodoStore| store.todos().iter().map(|t| t.id).collect::<Vec<_>>();
+
+    history.record(Operator::Delete.apply(&mut store, &[3, 1, 2], 0..2).unwrap());
+    assert_eq!(ids(&store), [2]);
+    assert!(history.undo(&mut store));
+    assert_eq!(ids(&store), [3, 1, 2]);
+    assert!(history.redo(&mut store));
+    assert!(history.undo(&mut store));
+    assert_eq!(ids(&store), [3, 1, 2]);
+  }
+
+  #[test]
+  fn test_sorts_by_due_date_or_creation() {
+    let date = |day| NaiveDate::from_ymd_opt(2024, 1, day);
+    let mut store = TodoStore::new(vec![Todo::new(1, "someday"), Todo::new(2, "later"), Todo::new(3, "soon")]);
+    store.set_due(2, date(20));
+    store.set_due(3, date(5));
+    let ids = |store: &TodoStore| store.todos().iter().map(|t| t.id).collect::<Vec<_>>();
+
+    store.sort(TodoSort::Due);
+    assert_eq!(ids(&store), [3, 2, 1]);
+    store.sort(TodoSort::Created);
+    assert_eq!(ids(&store), [1, 2, 3]);
+
+    let mut history = UndoStack::default();
+    let old_due = store.set_due(3, None).unwrap();
+    history.record(TodoOp::DueChanged { id: 3, old_due });
+    assert_eq!(store.todos()[2].due, None);
+    assert!(history.undo(&mut store));
+    assert_eq!(store.todos()[2].due, date(5));
+    assert_eq!(store.set_due(9, None), None);
+
+    assert!(store.todos()[2].is_overdue(date(6).unwrap()));
+    assert!(!store.todos()[2].is_overdue(date(5).unwrap()));
+    assert!(!store.todos()[0].is_overdue(date(6).unwrap()));
+  }
+
+  #[test]
+  fn test_priorities_sort_and_undo() -> Result<()> {
+    let mut store = TodoStore::new(vec![Todo::new(1, "chores"), Todo::new(2, "taxes"), Todo::new(3, "hobby")]);
+    store.set_priority(2, Priority::High);
+    store.set_priority(3, Priority::Low);
+    let ids = |store: &TodoStore| store.todos().iter().map(|t| t.id).collect::<Vec<_>>();
+
+    store.sort(TodoSort::Priority);
+    assert_eq!(ids(&store), [2, 1, 3]);
+    assert_eq!(Priority::High.next(), Priority::Low);
+
+    let mut history = UndoStack::default();
+    let old_priority = store.set_priority(1, Priority::Medium.next()).unwrap();
+    history.record(TodoOp::PriorityChanged { id: 1, old_priority });
+    assert_eq!(store.todos()[1].priority, Priority::High);
+    assert!(history.undo(&mut store));
+    assert_eq!(store.todos()[1].priority, Priority::Medium);
+    assert_eq!(store.set_priority(9, Priority::Low), None);
+
+    // Todos saved before priorities existed come back as Medium.
+    let old: Todo = serde_json::from_str(r#"{"id":1,"title":"old","is_completed":false}"#)?;
+    assert_eq!(old.priority, Priority::Medium);
+    Ok(())
+  }
+
+  #[test]
+  fn test_add_assigns_increasing_ids() {
+    let mut store = TodoStore::new(vec![Todo::new(7, "existing")]);
+    assert_eq!(store.add("next").id, 8);
+  }
+
+  #[test]
+  fn test_tags_are_taken_from_input() {
+    let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    assert_eq!(extract_tags("buy milk #errands #Home"), ("buy milk".to_string(), tags(&["errands", "Home"])));
+    assert_eq!(extract_tags("#work  fix issue #5 #work"), ("fix issue #5".to_string(), tags(&["work"])));
+    assert_eq!(extract_tags("no  tags # here"), ("no  tags # here".to_string(), vec![]));
+
+    let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+    let mut store = TodoStore::default();
+    let todo = store.add_from_input("call mum #family @tomorrow", today).clone();
+    assert_eq!((todo.title.as_str(), todo.due), ("call mum", NaiveDate::from_ymd_opt(2024, 1, 11)));
+    assert!(todo.has_tag("FAMILY"));
+    assert!(!todo.has_tag("work"));
+  }
+}
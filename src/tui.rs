@@ -43,6 +43,39 @@ pub enum Event {
   Resize(u16, u16),
 }
 
+/// Tracks whether the terminal is in TUI mode, so it is only restored once however many times teardown is requested,
+/// and restores it when dropped on every way out of the app, including early error returns and panics that unwind.
+struct TerminalGuard {
+  entered: bool,
+  restore: Box<dyn FnMut() -> Result<()> + Send>,
+}
+
+impl TerminalGuard {
+  fn new(entered: bool, restore: impl FnMut() -> Result<()> + Send + 'static) -> Self {
+    Self { entered, restore: Box::new(restore) }
+  }
+
+  fn enter(&mut self) {
+    self.entered = true;
+  }
+
+  /// Restores the terminal if it is still in TUI mode.
+  fn leave(&mut self) -> Result<()> {
+    if std::mem::take(&mut self.entered) {
+      (self.restore)()?;
+    }
+    Ok(())
+  }
+}
+
+impl Drop for TerminalGuard {
+  fn drop(&mut self) {
+    if let Err(e) = self.leave() {
+      log::error!("Failed to restore the terminal: {e:?}");
+    }
+  }
+}
+
 pub struct Tui {
   pub terminal: ratatui::Terminal<Backend<IO>>,
   pub task: JoinHandle<()>,
@@ -53,6 +86,7 @@ pub struct Tui {
   pub tick_rate: f64,
   pub mouse: bool,
   pub paste: bool,
+  state: TerminalGuard,
 }
 
 impl Tui {
@@ -65,7 +99,9 @@ impl Tui {
     let task = tokio::spawn(async {});
     let mouse = false;
     let paste = false;
-    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste })
+    // A terminal left in raw mode (e.g. by a panic) still needs restoring, even though this instance didn't enter it.
+    let state = TerminalGuard::new(crossterm::terminal::is_raw_mode_enabled()?, restore);
+    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste, state })
   }
 
   pub fn tick_rate(mut self, tick_rate: f64) -> Self {
@@ -170,6 +206,7 @@ impl Tui {
 
   pub fn enter(&mut self) -> Result<()> {
     crossterm::terminal::enable_raw_mode()?;
+    self.state.enter();
     crossterm::execute!(io(), EnterAlternateScreen, cursor::Hide)?;
     if self.mouse {
       crossterm::execute!(io(), EnableMouseCapture)?;
@@ -181,20 +218,13 @@ impl Tui {
     Ok(())
   }
 
+  /// Restores the terminal. Safe to call more than once; only the first call after [`Tui::enter`] does anything.
+  ///
+  /// Dropping the `Tui` restores it too, without waiting for the event task.
   pub fn exit(&mut self) -> Result<()> {
     self.stop()?;
-    if crossterm::terminal::is_raw_mode_enabled()? {
-      self.flush()?;
-      if self.paste {
-        crossterm::execute!(io(), DisableBracketedPaste)?;
-      }
-      if self.mouse {
-        crossterm::execute!(io(), DisableMouseCapture)?;
-      }
-      crossterm::execute!(io(), LeaveAlternateScreen, cursor::Show)?;
-      crossterm::terminal::disable_raw_mode()?;
-    }
-    Ok(())
+    self.terminal.flush()?;
+    self.state.leave()
   }
 
   pub fn cancel(&self) {
@@ -218,6 +248,16 @@ impl Tui {
   }
 }
 
+/// Leaves the alternate screen and raw mode without needing a [`Tui`], turning mouse capture and bracketed paste off
+/// in case they were on.
+///
+/// Used by the panic hook, which may run on any thread (or in a tokio task) while the app's [`Tui`] is out of reach.
+pub fn restore() -> Result<()> {
+  crossterm::execute!(io(), DisableBracketedPaste, DisableMouseCapture, LeaveAlternateScreen, cursor::Show)?;
+  crossterm::terminal::disable_raw_mode()?;
+  Ok(())
+}
+
 impl Deref for Tui {
   type Target = ratatui::Terminal<Backend<IO>>;
 
@@ -232,8 +272,54 @@ impl DerefMut for Tui {
   }
 }
 
-impl Drop for Tui {
-  fn drop(&mut self) {
-    self.exit().unwrap();
+#[cfg(test)]
+mod tests {
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  /// A guard that counts how often it restores the terminal instead of touching it.
+  fn counting_guard(entered: bool) -> (TerminalGuard, Arc<AtomicUsize>) {
+    let teardowns = Arc::new(AtomicUsize::new(0));
+    let counter = teardowns.clone();
+    let guard = TerminalGuard::new(entered, move || {
+      counter.fetch_add(1, Ordering::SeqCst);
+      Ok(())
+    });
+    (guard, teardowns)
+  }
+
+  #[test]
+  fn test_drop_without_exit_tears_down_once() {
+    let (mut guard, teardowns) = counting_guard(false);
+    guard.enter();
+    drop(guard);
+    assert_eq!(teardowns.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn test_exit_then_drop_tears_down_once() {
+    let (mut guard, teardowns) = counting_guard(false);
+    guard.enter();
+    guard.leave().unwrap();
+    guard.leave().unwrap();
+    drop(guard);
+    assert_eq!(teardowns.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn test_terminal_left_in_tui_mode_is_restored_without_entering() {
+    let (guard, teardowns) = counting_guard(true);
+    drop(guard);
+    assert_eq!(teardowns.load(Ordering::SeqCst), 1);
+
+    let (guard, teardowns) = counting_guard(false);
+    drop(guard);
+    assert_eq!(teardowns.load(Ordering::SeqCst), 0);
   }
 }
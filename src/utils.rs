@@ -1,11 +1,13 @@
-use std::path::PathBuf;
+use std::{io::Write, panic::PanicHookInfo, path::PathBuf, sync::Mutex};
 
 use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use tracing::error;
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{self, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer};
+use tracing_subscriber::{
+  self, fmt::writer::BoxMakeWriter, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer,
+};
 
 pub static GIT_COMMIT_HASH: &str = env!("RATATUI_EXPERIMENTATION_GIT_INFO");
 
@@ -16,6 +18,7 @@ lazy_static! {
   pub static ref CONFIG_FOLDER: Option<PathBuf> =
     std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone())).ok().map(PathBuf::from);
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
+  pub static ref ACTION_TRACE_ENV: String = format!("{}_ACTION_TRACE", PROJECT_NAME.clone());
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
@@ -31,13 +34,13 @@ pub fn initialize_panic_handler() -> Result<()> {
     .display_env_section(true)
     .into_hooks();
   eyre_hook.install()?;
-  std::panic::set_hook(Box::new(move |panic_info| {
-    if let Ok(mut t) = crate::tui::Tui::new() {
-      if let Err(r) = t.exit() {
-        error!("Unable to exit Terminal: {:?}", r);
-      }
+  let restore = || {
+    if crossterm::terminal::is_raw_mode_enabled()? {
+      crate::tui::restore()?;
     }
-
+    Ok(())
+  };
+  std::panic::set_hook(restoring_terminal(restore, move |panic_info| {
     #[cfg(not(debug_assertions))]
     {
       use human_panic::{handle_dump, print_msg, Metadata};
@@ -71,6 +74,24 @@ pub fn initialize_panic_handler() -> Result<()> {
   Ok(())
 }
 
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+/// Wraps a panic `hook` so the terminal is restored before it runs, keeping the report readable.
+///
+/// Panic hooks run on whichever thread panicked, so this also covers panics in tokio tasks such as the ones spawned by
+/// `Home::schedule_increment`.
+pub fn restoring_terminal(
+  restore: impl Fn() -> Result<()> + Send + Sync + 'static,
+  hook: impl Fn(&PanicHookInfo<'_>) + Send + Sync + 'static,
+) -> PanicHook {
+  Box::new(move |panic_info| {
+    if let Err(r) = restore() {
+      error!("Unable to exit Terminal: {:?}", r);
+    }
+    hook(panic_info);
+  })
+}
+
 pub fn get_data_dir() -> PathBuf {
   let directory = if let Some(s) = DATA_FOLDER.clone() {
     s
@@ -93,11 +114,37 @@ pub fn get_config_dir() -> PathBuf {
   directory
 }
 
+/// How many bytes of log output are kept when the data directory can't be written to.
+const MEMORY_LOG_CAPACITY: usize = 1 << 20;
+
+lazy_static! {
+  /// Log output, when it can't go to a file. Lines past [`MEMORY_LOG_CAPACITY`] are dropped.
+  pub static ref MEMORY_LOG: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+}
+
+struct MemoryLogWriter;
+
+impl Write for MemoryLogWriter {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    let mut log = MEMORY_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    if log.len() + buf.len() <= MEMORY_LOG_CAPACITY {
+      log.extend_from_slice(buf);
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
 pub fn initialize_logging() -> Result<()> {
   let directory = get_data_dir();
-  std::fs::create_dir_all(directory.clone())?;
-  let log_path = directory.join(LOG_FILE.clone());
-  let log_file = std::fs::File::create(log_path)?;
+  let writer = if crate::storage::probe_writable(&directory) {
+    BoxMakeWriter::new(std::fs::File::create(directory.join(LOG_FILE.clone()))?)
+  } else {
+    BoxMakeWriter::new(|| MemoryLogWriter)
+  };
   std::env::set_var(
     "RUST_LOG",
     std::env::var("RUST_LOG")
@@ -107,7 +154,7 @@ pub fn initialize_logging() -> Result<()> {
   let file_subscriber = tracing_subscriber::fmt::layer()
     .with_file(true)
     .with_line_number(true)
-    .with_writer(log_file)
+    .with_writer(writer)
     .with_target(false)
     .with_ansi(false)
     .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
@@ -160,3 +207,52 @@ Config directory: {config_dir_path}
 Data directory: {data_dir_path}"
   )
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use pretty_assertions::assert_eq;
+  use ratatui::layout::Rect;
+
+  use super::*;
+  use crate::{actions::Action, components::Component, tui::Frame};
+
+  struct PanickingComponent;
+
+  impl Component for PanickingComponent {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+      panic!("deliberate panic handling {action}");
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+      Ok(())
+    }
+  }
+
+  #[tokio::test]
+  async fn test_panic_in_task_restores_terminal_before_report() {
+    // The hook is process-wide, so panics from tests running alongside this one land in it too. The events are tagged
+    // with the panicking thread and only the task's own are checked.
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (restored, reported) = (events.clone(), events.clone());
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(restoring_terminal(
+      move || {
+        restored.lock().unwrap().push((std::thread::current().id(), "restore"));
+        Ok(())
+      },
+      move |_| reported.lock().unwrap().push((std::thread::current().id(), "report")),
+    ));
+
+    let result = tokio::spawn(async { PanickingComponent.update(crate::actions::EngineAction::Tick.into()) }).await;
+    std::panic::set_hook(default_hook);
+
+    assert!(result.unwrap_err().is_panic());
+    // The test runtime is single-threaded, so the task ran on this thread.
+    let task_thread = std::thread::current().id();
+    let task_events: Vec<_> =
+      events.lock().unwrap().iter().filter(|(thread, _)| *thread == task_thread).map(|(_, event)| *event).collect();
+    assert_eq!(task_events, vec!["restore", "report"]);
+  }
+}